@@ -1,3 +1,4 @@
+use crate::backend;
 use super::common::*;
 use super::*;
 
@@ -5,7 +6,7 @@ use plygui_api::controls;
 use plygui_api::types;
 
 use winapi::shared::windef;
-use winapi::um::commctrl;
+use winapi::um::{commctrl, ole2};
 
 use std::any::TypeId;
 
@@ -18,33 +19,40 @@ const DEFAULT_FRAME_SLEEP_MS: u32 = 10;
 pub struct WindowsApplication {
     pub(crate) root: windef::HWND,
     sleep: u32,
+    /// Whether OLE got initialized for the thread, to be balanced on drop.
+    ole: bool,
 }
 
 pub type Application = AApplication<WindowsApplication>;
 
 impl<O: controls::Application> NewApplicationInner<O> for WindowsApplication {
     fn with_uninit_params(u: &mut mem::MaybeUninit<O>, name: &str) -> Self {
+        #[cfg(not(feature = "headless"))]
+        common::enable_dpi_awareness();
+        #[cfg(not(feature = "headless"))]
         init_comctl();
+        #[cfg(not(feature = "headless"))]
+        let ole = init_ole();
+        #[cfg(feature = "headless")]
+        let ole = false;
         let osname = OsStr::new(name).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-        let hwnd = unsafe {
-            winuser::CreateWindowExW(
-                0,
-                WINDOW_CLASS.as_ptr(),
-                osname.as_ptr() as ntdef::LPCWSTR,
-                0,
-                winuser::CW_USEDEFAULT,
-                winuser::CW_USEDEFAULT,
-                1,
-                1,
-                ptr::null_mut(),
-                ptr::null_mut(),
-                hinstance(),
-                u as *mut _ as *mut c_void,
-            )
-        };
+        let hwnd = backend::current().create_window(&backend::CreateParams {
+            ex_style: 0,
+            class_name: WINDOW_CLASS.as_slice(),
+            window_name: osname.as_slice(),
+            style: 0,
+            x: winuser::CW_USEDEFAULT,
+            y: winuser::CW_USEDEFAULT,
+            width: 1,
+            height: 1,
+            parent: 0,
+            menu: 0,
+            param: u as *mut _ as usize,
+        }) as windef::HWND;
         WindowsApplication {
             sleep: DEFAULT_FRAME_SLEEP_MS,
             root: hwnd,
+            ole,
         }
     }
 }
@@ -62,9 +70,8 @@ impl ApplicationInner for WindowsApplication {
     }
     fn name(&self) -> Cow<str> {
         if self.root != 0 as windef::HWND {
-            let mut wbuffer = vec![0u16; 4096];
-            let len = unsafe { winuser::GetWindowTextW(self.root, wbuffer.as_mut_slice().as_mut_ptr(), 4096) };
-            Cow::Owned(String::from_utf16_lossy(&wbuffer.as_slice()[..len as usize]))
+            let wbuffer = backend::current().window_text(self.root as usize);
+            Cow::Owned(String::from_utf16_lossy(&wbuffer.as_slice()[..wbuffer.len() - 1]))
         } else {
             unreachable!();
         }
@@ -76,7 +83,6 @@ impl ApplicationInner for WindowsApplication {
         self.sleep = value;
     }    
     fn start(&mut self) {
-        let mut msg: winuser::MSG = unsafe { mem::zeroed() };
         let mut i;
         loop {
             let mut frame_callbacks = 0;
@@ -98,12 +104,8 @@ impl ApplicationInner for WindowsApplication {
                 }
                 unsafe {
                     synchapi::Sleep(self.sleep);
-    
-                    if winuser::PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, winuser::PM_REMOVE) > 0 {
-                        winuser::TranslateMessage(&mut msg);
-                        winuser::DispatchMessageW(&mut msg);
-                    }
                 }
+                backend::current().dispatch_message();
     
                 i = 0;
                 while i < w.windows.len() {
@@ -114,9 +116,7 @@ impl ApplicationInner for WindowsApplication {
                     }
                 }
                 if w.windows.len() < 1 && w.trays.len() < 1 {
-                    unsafe {
-                        winuser::DestroyWindow(self.root);
-                    }
+                    backend::current().destroy_window(self.root as usize);
                     break;
                 }
             }
@@ -294,43 +294,38 @@ impl HasNativeIdInner for WindowsApplication {
 impl Drop for WindowsApplication {
     fn drop(&mut self) {
         destroy_hwnd(self.root, 0, None);
+        if self.ole {
+            unsafe { ole2::OleUninitialize() };
+        }
     }
 }
 
 unsafe fn register_window_class() -> Vec<u16> {
     let class_name = OsStr::new("PlyguiWin32Application").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
 
-    let class = winuser::WNDCLASSEXW {
-        cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as minwindef::UINT,
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
         style: winuser::CS_DBLCLKS,
-        lpfnWndProc: Some(handler),
-        cbClsExtra: 0,
-        cbWndExtra: 0,
-        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
-        hIcon: ptr::null_mut(),
-        hCursor: ptr::null_mut(),
-        hbrBackground: ptr::null_mut(),
-        lpszMenuName: ptr::null(),
-        lpszClassName: class_name.as_ptr(),
-        hIconSm: ptr::null_mut(),
-    };
-    winuser::RegisterClassExW(&class);
+        wndproc: handler as usize,
+        background: None,
+        icon_and_cursor: false,
+    });
     class_name
 }
 
 // TODO <O: controls::Application>
 unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
         if winuser::WM_CREATE == msg {
             let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
-            winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, cs.lpCreateParams as WinPtr);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
         }
         //return winuser::DefWindowProcW(hwnd, msg, wparam, lparam);
     }
     match msg {
         winuser::WM_DESTROY => {
-            winuser::PostQuitMessage(0);
+            backend::current().post_quit(0);
         }
         winuser::WM_MENUSELECT => {
             //let flags = minwindef::HIWORD(wparam as u32);
@@ -377,7 +372,7 @@ unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wpar
         }
         _ => {}
     }
-    return winuser::DefWindowProcW(hwnd, msg, wparam, lparam);
+    return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
 }
 
 fn dispatch_window(hwnd: windef::HWND) -> i32 {
@@ -388,13 +383,21 @@ fn dispatch_window(hwnd: windef::HWND) -> i32 {
     }
 }
 
+#[cfg(not(feature = "headless"))]
 fn init_comctl() {
     unsafe {
         let mut icc: commctrl::INITCOMMONCONTROLSEX = mem::zeroed();
         icc.dwSize = mem::size_of::<commctrl::INITCOMMONCONTROLSEX>() as u32;
-        icc.dwICC = commctrl::ICC_STANDARD_CLASSES | commctrl::ICC_LISTVIEW_CLASSES | commctrl::ICC_TAB_CLASSES | commctrl::ICC_PROGRESS_CLASS | commctrl::ICC_UPDOWN_CLASS | commctrl::ICC_BAR_CLASSES;
+        icc.dwICC = commctrl::ICC_STANDARD_CLASSES | commctrl::ICC_LISTVIEW_CLASSES | commctrl::ICC_TAB_CLASSES | commctrl::ICC_PROGRESS_CLASS | commctrl::ICC_UPDOWN_CLASS | commctrl::ICC_BAR_CLASSES | commctrl::ICC_LINK_CLASS;
         if commctrl::InitCommonControlsEx(&icc) == 0 {
             common::log_error();
         }
     }
 }
+
+/// OLE is needed for the drop targets of the controls, which fall back to `WM_DROPFILES` if it could not be initialized.
+#[cfg(not(feature = "headless"))]
+fn init_ole() -> bool {
+    // S_FALSE when the thread has it initialized already, which has to be balanced all the same
+    winerror::SUCCEEDED(unsafe { ole2::OleInitialize(ptr::null_mut()) })
+}
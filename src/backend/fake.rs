@@ -0,0 +1,921 @@
+use super::{Backend, ClassParams, CreateParams, Handle, Rect, ScrollInfo, Snapshot};
+use crate::dpi;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::ptr;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+const GWL_STYLE: i32 = -16;
+const GWL_EXSTYLE: i32 = -20;
+const GWLP_USERDATA: i32 = -21;
+const SWP_NOSIZE: u32 = 0x0001;
+const SWP_NOMOVE: u32 = 0x0002;
+const WS_VISIBLE: u32 = 0x1000_0000;
+const WS_HSCROLL: u32 = 0x0010_0000;
+const WS_VSCROLL: u32 = 0x0020_0000;
+const SB_HORZ: i32 = 0;
+const SB_VERT: i32 = 1;
+const SB_BOTH: i32 = 3;
+const CW_USEDEFAULT: i32 = 0x8000_0000u32 as i32;
+const WM_CREATE: u32 = 0x0001;
+const WM_DESTROY: u32 = 0x0002;
+const WM_SIZE: u32 = 0x0005;
+const WM_CLOSE: u32 = 0x0010;
+const WM_SYSCOMMAND: u32 = 0x0112;
+const SC_CLOSE: usize = 0xF060;
+const SIZE_RESTORED: usize = 0;
+
+const FIRST_HANDLE: Handle = 0x1000;
+const HANDLE_STEP: Handle = 4;
+/// Width of every character and height of every line of text, at `dpi::BASE_DPI`.
+pub const CHAR_WIDTH: i32 = 8;
+pub const LINE_HEIGHT: i32 = 16;
+
+lazy_static! {
+    /// Window procedures by class name. Classes belong to the process, whichever backend registered them.
+    static ref WINDOW_PROCS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FakeMessage {
+    pub msg: u32,
+    pub wparam: usize,
+    pub lparam: isize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeMenuItem {
+    pub flags: u32,
+    pub id: usize,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeWindow {
+    pub class_name: String,
+    pub text: String,
+    pub style: u32,
+    pub ex_style: u32,
+    pub rect: Rect,
+    pub parent: Handle,
+    pub menu: Handle,
+    pub user_data: isize,
+    pub subclasses: Vec<(usize, usize, usize)>,
+    pub messages: Vec<FakeMessage>,
+    pub invalidated: usize,
+    /// Horizontal and vertical scroll bar.
+    pub scroll: [ScrollInfo; 2],
+}
+
+impl FakeWindow {
+    pub fn is_visible(&self) -> bool {
+        self.style & WS_VISIBLE != 0
+    }
+}
+
+/// Layout of `CREATESTRUCTW`, which the `lparam` of `WM_CREATE` points to.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FakeCreateStruct {
+    pub create_params: usize,
+    pub instance: Handle,
+    pub menu: Handle,
+    pub parent: Handle,
+    pub height: i32,
+    pub width: i32,
+    pub y: i32,
+    pub x: i32,
+    pub style: i32,
+    pub name: *const u16,
+    pub class_name: *const u16,
+    pub ex_style: u32,
+}
+
+/// Window procedure of a class registered with `FakeBackend::register_proc`.
+pub type FakeProc = Rc<dyn Fn(Handle, u32, usize, isize) -> isize>;
+
+type SubclassProc = unsafe extern "system" fn(Handle, u32, usize, isize, usize, usize) -> isize;
+type WindowProc = unsafe extern "system" fn(Handle, u32, usize, isize) -> isize;
+
+/// In-memory windowing system. Windows, menus and everything sent to them are kept until destroyed, nothing is ever painted.
+///
+/// Messages sent to a window, along with the `WM_SIZE` of every size change, are dispatched synchronously as the OS does:
+/// to the subclass procedure installed last on the window, or else to the procedure of its class, either registered
+/// through `Backend::register_class` or as a closure with `register_proc`. `def_subclass_proc` goes down the same chain.
+/// Posted messages wait for `dispatch_message`. Windows of classes without a procedure, like the common controls, take
+/// every message and do nothing with it.
+/// A window gets `WM_CREATE` and its first `WM_SIZE` from `create_window`, before any subclass can be installed, so those
+/// only reach class procedures. The `lparam` of `WM_CREATE` points to a `FakeCreateStruct`, laid out as `CREATESTRUCTW`,
+/// and a window is not created if its procedure answers -1.
+///
+/// `def_window_proc` turns `SC_CLOSE` into `WM_CLOSE`, and `WM_CLOSE` into `destroy_window`, which sends `WM_DESTROY` to
+/// the window and then to each of its descendants before they are gone. Anything else it answers with 0.
+///
+/// Text is measured as `CHAR_WIDTH` by `LINE_HEIGHT` a character, scaled to the DPI, whatever the font.
+#[derive(Debug, Default)]
+pub struct FakeBackend {
+    windows: RefCell<BTreeMap<Handle, FakeWindow>>,
+    menus: RefCell<BTreeMap<Handle, (bool, Vec<FakeMenuItem>)>>,
+    classes: RefCell<Classes>,
+    /// Subclass procedures being called, by window, along with how many were installed before them.
+    calls: RefCell<Vec<(Handle, usize)>>,
+    queue: RefCell<VecDeque<(Handle, FakeMessage)>>,
+    quit: Cell<Option<i32>>,
+    focus: Cell<Handle>,
+    capture: Cell<Handle>,
+    cursor: Cell<Handle>,
+    last_handle: Cell<Handle>,
+    dpi: Cell<u32>,
+    /// Windows whose `WM_DESTROY` is being sent.
+    destroying: RefCell<Vec<Handle>>,
+}
+
+#[derive(Default)]
+struct Classes(BTreeMap<String, FakeProc>);
+
+impl fmt::Debug for Classes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+impl FakeBackend {
+    /// Makes `proc` the window procedure of every window of `class_name`, for this backend only and before any procedure
+    /// registered through `Backend::register_class`.
+    pub fn register_proc<S: Into<String>>(&self, class_name: S, proc: FakeProc) {
+        self.classes.borrow_mut().0.insert(class_name.into(), proc);
+    }
+    pub fn window(&self, hwnd: Handle) -> Option<FakeWindow> {
+        self.windows.borrow().get(&hwnd).cloned()
+    }
+    pub fn windows(&self) -> Vec<(Handle, FakeWindow)> {
+        self.windows.borrow().iter().map(|(h, w)| (*h, w.clone())).collect()
+    }
+    pub fn children(&self, parent: Handle) -> Vec<Handle> {
+        self.windows.borrow().iter().filter(|(_, w)| w.parent == parent).map(|(h, _)| *h).collect()
+    }
+    pub fn menu(&self, menu: Handle) -> Option<Vec<FakeMenuItem>> {
+        self.menus.borrow().get(&menu).map(|m| m.1.clone())
+    }
+    /// Code `post_quit` was called with, if it was.
+    pub fn quit_code(&self) -> Option<i32> {
+        self.quit.get()
+    }
+    pub fn capture(&self) -> Handle {
+        self.capture.get()
+    }
+    pub fn cursor(&self) -> Handle {
+        self.cursor.get()
+    }
+
+    /// Puts every window onto a monitor with `dpi`, `BASE_DPI` until set.
+    pub fn set_dpi(&self, dpi: u32) {
+        self.dpi.set(dpi);
+    }
+    fn next_handle(&self) -> Handle {
+        let next = if self.last_handle.get() == 0 { FIRST_HANDLE } else { self.last_handle.get() + HANDLE_STEP };
+        self.last_handle.set(next);
+        next
+    }
+    fn with_window<R, F: FnOnce(&mut FakeWindow) -> R>(&self, hwnd: Handle, f: F) -> Option<R> {
+        self.windows.borrow_mut().get_mut(&hwnd).map(f)
+    }
+    /// Calls the procedure of `hwnd`, `None` if there is no such window. No borrow is held meanwhile, so the procedure may call
+    /// the backend again.
+    fn dispatch(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> Option<isize> {
+        let installed = self.with_window(hwnd, |w| w.subclasses.len())?;
+        self.dispatch_below(hwnd, installed, msg, wparam, lparam)
+    }
+    /// Calls the last subclass procedure among the first `installed` ones of `hwnd`, or else the procedure of its class.
+    fn dispatch_below(&self, hwnd: Handle, installed: usize, msg: u32, wparam: usize, lparam: isize) -> Option<isize> {
+        let (subclass, class_name) = self.with_window(hwnd, |w| {
+            let installed = installed.min(w.subclasses.len());
+            (w.subclasses[..installed].iter().enumerate().rev().find(|(_, s)| s.0 != 0).map(|(i, s)| (i, *s)), w.class_name.clone())
+        })?;
+        match subclass {
+            Some((index, (proc, id, data))) => {
+                // `set_subclass` takes nothing but the addresses of `SUBCLASSPROC`s
+                let proc = unsafe { std::mem::transmute::<usize, SubclassProc>(proc) };
+                self.calls.borrow_mut().push((hwnd, index));
+                let result = unsafe { proc(hwnd, msg, wparam, lparam, id, data) };
+                self.calls.borrow_mut().pop();
+                Some(result)
+            }
+            None => match self.window_proc(&class_name) {
+                Some(proc) => Some(proc(hwnd, msg, wparam, lparam)),
+                None => Some(self.def_window_proc(hwnd, msg, wparam, lparam)),
+            },
+        }
+    }
+    fn window_proc(&self, class_name: &str) -> Option<FakeProc> {
+        if let Some(proc) = self.classes.borrow().0.get(class_name) {
+            return Some(proc.clone());
+        }
+        let address = *WINDOW_PROCS.lock().unwrap().get(class_name)?;
+        // `register_class` takes nothing but the addresses of `WNDPROC`s
+        let proc = unsafe { std::mem::transmute::<usize, WindowProc>(address) };
+        Some(Rc::new(move |hwnd, msg, wparam, lparam| unsafe { proc(hwnd, msg, wparam, lparam) }))
+    }
+    fn dispatch_size(&self, hwnd: Handle, width: i32, height: i32) {
+        let size = (width as u16 as isize) | ((height as u16 as isize) << 16);
+        self.dispatch(hwnd, WM_SIZE, SIZE_RESTORED, size);
+    }
+}
+
+fn from_wide(s: &[u16]) -> String {
+    let len = s.iter().position(|c| *c == 0).unwrap_or(s.len());
+    String::from_utf16_lossy(&s[..len])
+}
+fn default_to_zero(value: i32) -> i32 {
+    if value == CW_USEDEFAULT {
+        0
+    } else {
+        value
+    }
+}
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+impl Backend for FakeBackend {
+    fn register_class(&self, class: &ClassParams) -> bool {
+        WINDOW_PROCS.lock().unwrap().insert(from_wide(class.class_name), class.wndproc);
+        true
+    }
+    fn create_window(&self, params: &CreateParams) -> Handle {
+        let hwnd = self.next_handle();
+        self.windows.borrow_mut().insert(
+            hwnd,
+            FakeWindow {
+                class_name: from_wide(params.class_name),
+                text: from_wide(params.window_name),
+                style: params.style,
+                ex_style: params.ex_style,
+                rect: Rect::with_size(default_to_zero(params.x), default_to_zero(params.y), default_to_zero(params.width), default_to_zero(params.height)),
+                parent: params.parent,
+                menu: params.menu,
+                user_data: 0,
+                subclasses: Vec::new(),
+                messages: Vec::new(),
+                invalidated: 0,
+                scroll: Default::default(),
+            },
+        );
+        let create = FakeCreateStruct {
+            create_params: params.param,
+            instance: 0,
+            menu: params.menu,
+            parent: params.parent,
+            height: params.height,
+            width: params.width,
+            y: params.y,
+            x: params.x,
+            style: params.style as i32,
+            name: if params.window_name.is_empty() { ptr::null() } else { params.window_name.as_ptr() },
+            class_name: params.class_name.as_ptr(),
+            ex_style: params.ex_style,
+        };
+        if self.dispatch(hwnd, WM_CREATE, 0, &create as *const _ as isize) == Some(-1) {
+            self.destroy_window(hwnd);
+            return 0;
+        }
+        self.dispatch_size(hwnd, default_to_zero(params.width), default_to_zero(params.height));
+        hwnd
+    }
+    fn destroy_window(&self, hwnd: Handle) -> bool {
+        if !self.is_window(hwnd) || self.destroying.borrow().contains(&hwnd) {
+            return false;
+        }
+        self.destroying.borrow_mut().push(hwnd);
+        let doomed: Vec<Handle> = Some(hwnd).into_iter().chain(self.descendants(hwnd)).collect();
+        for window in doomed.iter() {
+            self.dispatch(*window, WM_DESTROY, 0, 0);
+        }
+        {
+            let mut windows = self.windows.borrow_mut();
+            for window in doomed.iter() {
+                windows.remove(window);
+            }
+        }
+        self.destroying.borrow_mut().retain(|window| *window != hwnd);
+        true
+    }
+    fn is_window(&self, hwnd: Handle) -> bool {
+        self.windows.borrow().contains_key(&hwnd)
+    }
+    fn class_name(&self, hwnd: Handle) -> Vec<u16> {
+        self.with_window(hwnd, |w| w.class_name.encode_utf16().collect()).unwrap_or_default()
+    }
+
+    fn send_message(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> isize {
+        self.with_window(hwnd, |w| w.messages.push(FakeMessage { msg, wparam, lparam }));
+        self.dispatch(hwnd, msg, wparam, lparam).unwrap_or(0)
+    }
+    fn post_message(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> bool {
+        let message = FakeMessage { msg, wparam, lparam };
+        let posted = self.with_window(hwnd, |w| w.messages.push(message)).is_some();
+        if posted {
+            self.queue.borrow_mut().push_back((hwnd, message));
+        }
+        posted
+    }
+    fn dispatch_message(&self) -> bool {
+        let next = self.queue.borrow_mut().pop_front();
+        match next {
+            Some((hwnd, message)) => {
+                self.dispatch(hwnd, message.msg, message.wparam, message.lparam);
+                true
+            }
+            None => false,
+        }
+    }
+    fn post_quit(&self, code: i32) {
+        self.quit.set(Some(code));
+    }
+    fn def_window_proc(&self, hwnd: Handle, msg: u32, wparam: usize, _lparam: isize) -> isize {
+        match msg {
+            WM_SYSCOMMAND if wparam & 0xFFF0 == SC_CLOSE => {
+                self.send_message(hwnd, WM_CLOSE, 0, 0);
+            }
+            WM_CLOSE => {
+                self.destroy_window(hwnd);
+            }
+            _ => {}
+        }
+        0
+    }
+    fn def_subclass_proc(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> isize {
+        let installed = self.calls.borrow().iter().rev().find(|call| call.0 == hwnd).map(|call| call.1).unwrap_or(0);
+        self.dispatch_below(hwnd, installed, msg, wparam, lparam).unwrap_or(0)
+    }
+
+    fn set_subclass(&self, hwnd: Handle, proc: usize, id: usize, data: usize) -> bool {
+        self.with_window(hwnd, |w| {
+            w.subclasses.retain(|s| s.0 != proc || s.1 != id);
+            w.subclasses.push((proc, id, data));
+        })
+        .is_some()
+    }
+    fn remove_subclass(&self, hwnd: Handle, proc: usize, id: usize) -> bool {
+        self.with_window(hwnd, |w| {
+            let len = w.subclasses.len();
+            w.subclasses.retain(|s| s.0 != proc || s.1 != id);
+            len != w.subclasses.len()
+        })
+        .unwrap_or(false)
+    }
+
+    fn set_window_pos(&self, hwnd: Handle, x: i32, y: i32, width: i32, height: i32, flags: u32) -> bool {
+        let resized = self.with_window(hwnd, |w| {
+            let (width, height) = if flags & SWP_NOSIZE != 0 { (w.rect.width(), w.rect.height()) } else { (width, height) };
+            let (x, y) = if flags & SWP_NOMOVE != 0 { (w.rect.left, w.rect.top) } else { (x, y) };
+            let resized = width != w.rect.width() || height != w.rect.height();
+            w.rect = Rect::with_size(x, y, width, height);
+            resized
+        });
+        match resized {
+            Some(true) => {
+                let rect = self.client_rect(hwnd);
+                self.dispatch_size(hwnd, rect.width(), rect.height());
+                true
+            }
+            Some(false) => true,
+            None => false,
+        }
+    }
+    fn client_rect(&self, hwnd: Handle) -> Rect {
+        self.with_window(hwnd, |w| Rect::with_size(0, 0, w.rect.width(), w.rect.height())).unwrap_or_default()
+    }
+    fn show_window(&self, hwnd: Handle, visible: bool) -> bool {
+        self.with_window(hwnd, |w| {
+            let was_visible = w.is_visible();
+            if visible {
+                w.style |= WS_VISIBLE;
+            } else {
+                w.style &= !WS_VISIBLE;
+            }
+            was_visible
+        })
+        .unwrap_or(false)
+    }
+    fn invalidate(&self, hwnd: Handle, _erase: bool) -> bool {
+        self.with_window(hwnd, |w| w.invalidated += 1).is_some()
+    }
+    fn redraw(&self, hwnd: Handle, children: bool) -> bool {
+        if children {
+            for child in self.descendants(hwnd) {
+                self.invalidate(child, true);
+            }
+        }
+        self.invalidate(hwnd, true)
+    }
+    fn window_rect(&self, hwnd: Handle) -> Rect {
+        self.with_window(hwnd, |w| w.rect).unwrap_or_default()
+    }
+    fn is_visible(&self, hwnd: Handle) -> bool {
+        let mut hwnd = hwnd;
+        loop {
+            match self.with_window(hwnd, |w| (w.is_visible(), w.parent)) {
+                Some((true, 0)) => return true,
+                Some((true, parent)) => hwnd = parent,
+                _ => return false,
+            }
+        }
+    }
+    fn dpi(&self, _hwnd: Handle) -> u32 {
+        match self.dpi.get() {
+            0 => crate::dpi::BASE_DPI,
+            dpi => dpi,
+        }
+    }
+
+    fn parent(&self, hwnd: Handle) -> Handle {
+        self.with_window(hwnd, |w| w.parent).unwrap_or(0)
+    }
+    fn root(&self, hwnd: Handle) -> Handle {
+        let mut root = hwnd;
+        loop {
+            match self.parent(root) {
+                0 => return root,
+                parent => root = parent,
+            }
+        }
+    }
+    fn descendants(&self, hwnd: Handle) -> Vec<Handle> {
+        self.children(hwnd).into_iter().flat_map(|child| Some(child).into_iter().chain(self.descendants(child))).collect()
+    }
+    fn window_long_ptr(&self, hwnd: Handle, index: i32) -> isize {
+        self.with_window(hwnd, |w| match index {
+            GWL_STYLE => w.style as isize,
+            GWL_EXSTYLE => w.ex_style as isize,
+            GWLP_USERDATA => w.user_data,
+            _ => 0,
+        })
+        .unwrap_or(0)
+    }
+    fn set_window_long_ptr(&self, hwnd: Handle, index: i32, value: isize) -> isize {
+        self.with_window(hwnd, |w| match index {
+            GWL_STYLE => std::mem::replace(&mut w.style, value as u32) as isize,
+            GWL_EXSTYLE => std::mem::replace(&mut w.ex_style, value as u32) as isize,
+            GWLP_USERDATA => std::mem::replace(&mut w.user_data, value),
+            _ => 0,
+        })
+        .unwrap_or(0)
+    }
+
+    fn window_text(&self, hwnd: Handle) -> Vec<u16> {
+        self.with_window(hwnd, |w| to_wide(&w.text)).unwrap_or_else(|| vec![0])
+    }
+    fn set_window_text(&self, hwnd: Handle, text: &[u16]) -> bool {
+        self.with_window(hwnd, |w| w.text = from_wide(text)).is_some()
+    }
+    fn text_size(&self, _hwnd: Handle, _font: Handle, text: &[u16], max_width: Option<i32>) -> (i32, i32) {
+        let dpi = self.dpi(0);
+        let (char_width, line_height) = (dpi::scale(CHAR_WIDTH, dpi), dpi::scale(LINE_HEIGHT, dpi));
+        let (mut width, mut lines) = (0, 0);
+        for line in String::from_utf16_lossy(text).split('\n') {
+            let mut line_width = 0;
+            lines += 1;
+            for word in line.split(' ') {
+                let word_width = word.encode_utf16().count() as i32 * char_width;
+                if line_width == 0 {
+                    line_width = word_width;
+                } else if max_width.map(|max| line_width + char_width + word_width > max).unwrap_or(false) {
+                    width = width.max(line_width);
+                    lines += 1;
+                    line_width = word_width;
+                } else {
+                    line_width += char_width + word_width;
+                }
+            }
+            width = width.max(line_width);
+        }
+        (width, lines * line_height)
+    }
+
+    fn focus(&self) -> Handle {
+        self.focus.get()
+    }
+    fn set_focus(&self, hwnd: Handle) -> Handle {
+        self.focus.replace(hwnd)
+    }
+    fn set_capture(&self, hwnd: Handle) {
+        self.capture.set(hwnd);
+    }
+    fn release_capture(&self) -> bool {
+        self.capture.set(0);
+        true
+    }
+    fn set_cursor(&self, cursor: Handle) -> Handle {
+        self.cursor.replace(cursor)
+    }
+
+    fn scroll_info(&self, hwnd: Handle, bar: i32) -> Option<ScrollInfo> {
+        match bar {
+            SB_HORZ | SB_VERT => self.with_window(hwnd, |w| w.scroll[bar as usize]),
+            _ => None,
+        }
+    }
+    fn set_scroll_info(&self, hwnd: Handle, bar: i32, info: &ScrollInfo) -> i32 {
+        if bar != SB_HORZ && bar != SB_VERT {
+            return 0;
+        }
+        self.with_window(hwnd, |w| {
+            // the last position shows the last page
+            let last = info.max.saturating_sub((info.page as i32).saturating_sub(1).max(0)).max(info.min);
+            let pos = info.pos.max(info.min).min(last);
+            w.scroll[bar as usize] = ScrollInfo { pos, track_pos: pos, ..*info };
+            pos
+        })
+        .unwrap_or(0)
+    }
+    fn show_scroll_bar(&self, hwnd: Handle, bar: i32, show: bool) -> bool {
+        let bits = match bar {
+            SB_HORZ => WS_HSCROLL,
+            SB_VERT => WS_VSCROLL,
+            SB_BOTH => WS_HSCROLL | WS_VSCROLL,
+            _ => return false,
+        };
+        self.with_window(hwnd, |w| if show { w.style |= bits } else { w.style &= !bits }).is_some()
+    }
+
+    fn create_menu(&self, popup: bool) -> Handle {
+        let menu = self.next_handle();
+        self.menus.borrow_mut().insert(menu, (popup, Vec::new()));
+        menu
+    }
+    fn append_menu(&self, menu: Handle, flags: u32, id: usize, label: Option<&[u16]>) -> bool {
+        self.menus
+            .borrow_mut()
+            .get_mut(&menu)
+            .map(|m| {
+                m.1.push(FakeMenuItem {
+                    flags,
+                    id,
+                    label: label.map(from_wide),
+                })
+            })
+            .is_some()
+    }
+    fn set_menu(&self, hwnd: Handle, menu: Handle) -> bool {
+        self.with_window(hwnd, |w| w.menu = menu).is_some()
+    }
+
+    fn snapshot(&self, hwnd: Handle) -> Option<Snapshot> {
+        let window = self.window(hwnd)?;
+        Some(Snapshot {
+            class_name: window.class_name,
+            style: window.style,
+            ex_style: window.ex_style,
+            text: window.text,
+            rect: window.rect,
+            children: self.children(hwnd).into_iter().filter_map(|child| self.snapshot(child)).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create(backend: &FakeBackend, class_name: &str, parent: Handle, (x, y, width, height): (i32, i32, i32, i32)) -> Handle {
+        backend.create_window(&CreateParams {
+            ex_style: 0,
+            class_name: to_wide(class_name).as_slice(),
+            window_name: to_wide("text").as_slice(),
+            style: WS_VISIBLE,
+            x,
+            y,
+            width,
+            height,
+            parent,
+            menu: 0,
+            param: 42,
+        })
+    }
+
+    #[test]
+    fn create_window_records_params() {
+        let backend = FakeBackend::default();
+        let hwnd = create(&backend, "Button", 0, (CW_USEDEFAULT, 5, 30, 40));
+        let window = backend.window(hwnd).unwrap();
+        assert_eq!(window.class_name, "Button");
+        assert_eq!(window.text, "text");
+        assert_eq!(window.rect, Rect::with_size(0, 5, 30, 40));
+        assert!(window.is_visible());
+        assert_eq!(backend.class_name(hwnd), "Button".encode_utf16().collect::<Vec<_>>());
+        // left to the window procedure, as the OS does
+        assert_eq!(backend.window_long_ptr(hwnd, GWLP_USERDATA), 0);
+    }
+
+    #[test]
+    fn create_struct_carries_the_params() {
+        let backend = FakeBackend::default();
+        let received = Rc::new(RefCell::new(None));
+        let log = received.clone();
+        backend.register_proc(
+            "Created",
+            Rc::new(move |hwnd, msg, _, lparam| {
+                if msg == WM_CREATE {
+                    let create = unsafe { &*(lparam as *const FakeCreateStruct) };
+                    let len = (0..).take_while(|i| unsafe { *create.name.offset(*i) } != 0).count();
+                    let name = String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(create.name, len) });
+                    *log.borrow_mut() = Some((hwnd, create.create_params, create.parent, (create.x, create.y, create.width, create.height), create.style as u32, name));
+                }
+                0
+            }),
+        );
+        let parent = create(&backend, "Parent", 0, (0, 0, 100, 100));
+        let hwnd = create(&backend, "Created", parent, (1, 2, 3, 4));
+        assert_eq!(*received.borrow(), Some((hwnd, 42, parent, (1, 2, 3, 4), WS_VISIBLE, "text".to_owned())));
+    }
+
+    #[test]
+    fn create_fails_when_refused() {
+        let backend = FakeBackend::default();
+        backend.register_proc("Refused", Rc::new(|_, msg, _, _| if msg == WM_CREATE { -1 } else { 0 }));
+        assert_eq!(create(&backend, "Refused", 0, (0, 0, 1, 1)), 0);
+        assert!(backend.windows().is_empty());
+    }
+
+    #[test]
+    fn destroy_window_takes_children_along() {
+        let backend = FakeBackend::default();
+        let root = create(&backend, "Root", 0, (0, 0, 100, 100));
+        let child = create(&backend, "Child", root, (0, 0, 10, 10));
+        let grandchild = create(&backend, "Grandchild", child, (0, 0, 1, 1));
+        let other = create(&backend, "Other", 0, (0, 0, 1, 1));
+        assert_eq!(backend.root(grandchild), root);
+        assert_eq!(backend.parent(grandchild), child);
+
+        assert!(backend.destroy_window(child));
+        assert!(!backend.is_window(child));
+        assert!(!backend.is_window(grandchild));
+        assert!(backend.is_window(root));
+        assert!(backend.is_window(other));
+        assert!(!backend.destroy_window(child));
+    }
+
+    #[test]
+    fn destroy_sends_wm_destroy_parent_first() {
+        let backend = Rc::new(FakeBackend::default());
+        let destroyed = Rc::new(RefCell::new(Vec::new()));
+        let log = destroyed.clone();
+        let weak = Rc::downgrade(&backend);
+        backend.register_proc(
+            "Node",
+            Rc::new(move |hwnd, msg, _, _| {
+                if msg == WM_DESTROY {
+                    // still there to be looked at
+                    assert!(weak.upgrade().unwrap().is_window(hwnd));
+                    log.borrow_mut().push(hwnd);
+                }
+                0
+            }),
+        );
+        let root = create(&backend, "Node", 0, (0, 0, 100, 100));
+        let child = create(&backend, "Node", root, (0, 0, 10, 10));
+        let grandchild = create(&backend, "Node", child, (0, 0, 1, 1));
+        assert!(backend.destroy_window(root));
+        assert_eq!(*destroyed.borrow(), vec![root, child, grandchild]);
+        assert!(backend.windows().is_empty());
+    }
+
+    #[test]
+    fn sc_close_destroys_unless_vetoed() {
+        let backend = Rc::new(FakeBackend::default());
+        let veto = Rc::new(Cell::new(true));
+        let (weak, vetoed) = (Rc::downgrade(&backend), veto.clone());
+        backend.register_proc(
+            "Closing",
+            Rc::new(move |hwnd, msg, wparam, lparam| {
+                if msg == WM_CLOSE && vetoed.get() {
+                    return 0;
+                }
+                weak.upgrade().unwrap().def_window_proc(hwnd, msg, wparam, lparam)
+            }),
+        );
+        let hwnd = create(&backend, "Closing", 0, (0, 0, 1, 1));
+        backend.send_message(hwnd, WM_SYSCOMMAND, SC_CLOSE, 0);
+        assert!(backend.is_window(hwnd));
+        assert_eq!(backend.window(hwnd).unwrap().messages.last(), Some(&FakeMessage { msg: WM_CLOSE, wparam: 0, lparam: 0 }));
+        veto.set(false);
+        backend.send_message(hwnd, WM_SYSCOMMAND, SC_CLOSE, 0);
+        assert!(!backend.is_window(hwnd));
+    }
+
+    #[test]
+    fn set_window_pos_honors_flags() {
+        let backend = FakeBackend::default();
+        let hwnd = create(&backend, "Static", 0, (1, 2, 3, 4));
+        assert!(backend.set_window_pos(hwnd, 10, 20, 30, 40, SWP_NOSIZE));
+        assert_eq!(backend.window(hwnd).unwrap().rect, Rect::with_size(10, 20, 3, 4));
+        assert!(backend.set_window_pos(hwnd, 0, 0, 50, 60, SWP_NOMOVE));
+        assert_eq!(backend.window(hwnd).unwrap().rect, Rect::with_size(10, 20, 50, 60));
+        assert_eq!(backend.client_rect(hwnd), Rect::with_size(0, 0, 50, 60));
+        assert!(!backend.set_window_pos(hwnd + HANDLE_STEP, 0, 0, 1, 1, 0));
+    }
+
+    #[test]
+    fn messages_are_recorded_for_live_windows_only() {
+        let backend = FakeBackend::default();
+        let hwnd = create(&backend, "Edit", 0, (0, 0, 1, 1));
+        backend.send_message(hwnd, 0x000C, 1, 2);
+        assert!(backend.post_message(hwnd, 0x0010, 3, 4));
+        assert_eq!(
+            backend.window(hwnd).unwrap().messages,
+            vec![FakeMessage { msg: 0x000C, wparam: 1, lparam: 2 }, FakeMessage { msg: 0x0010, wparam: 3, lparam: 4 }]
+        );
+        backend.destroy_window(hwnd);
+        assert!(!backend.post_message(hwnd, 0x0010, 0, 0));
+    }
+
+    #[test]
+    fn visibility_and_styles() {
+        let backend = FakeBackend::default();
+        let hwnd = create(&backend, "Static", 0, (0, 0, 1, 1));
+        assert!(backend.show_window(hwnd, false));
+        assert!(!backend.window(hwnd).unwrap().is_visible());
+        assert!(!backend.show_window(hwnd, true));
+        assert_eq!(backend.window_long_ptr(hwnd, GWL_STYLE), WS_VISIBLE as isize);
+        assert_eq!(backend.set_window_long_ptr(hwnd, GWL_EXSTYLE, 0x20), 0);
+        assert_eq!(backend.window(hwnd).unwrap().ex_style, 0x20);
+        backend.invalidate(hwnd, true);
+        assert_eq!(backend.window(hwnd).unwrap().invalidated, 1);
+    }
+
+    #[test]
+    fn subclasses_replace_same_proc_and_id() {
+        let backend = FakeBackend::default();
+        let hwnd = create(&backend, "Static", 0, (0, 0, 1, 1));
+        assert!(backend.set_subclass(hwnd, 1, 7, 100));
+        assert!(backend.set_subclass(hwnd, 1, 7, 200));
+        assert!(backend.set_subclass(hwnd, 2, 7, 300));
+        assert_eq!(backend.window(hwnd).unwrap().subclasses, vec![(1, 7, 200), (2, 7, 300)]);
+        assert!(backend.remove_subclass(hwnd, 1, 7));
+        assert!(!backend.remove_subclass(hwnd, 1, 7));
+    }
+
+    #[test]
+    fn text_and_menus() {
+        let backend = FakeBackend::default();
+        let hwnd = create(&backend, "Window", 0, (0, 0, 1, 1));
+        assert!(backend.set_window_text(hwnd, to_wide("title").as_slice()));
+        assert_eq!(backend.window_text(hwnd), to_wide("title"));
+
+        let menu = backend.create_menu(false);
+        assert!(backend.append_menu(menu, 0, 5, Some(to_wide("File").as_slice())));
+        assert!(backend.append_menu(menu, 0x800, 0, None));
+        assert!(backend.set_menu(hwnd, menu));
+        assert_eq!(backend.window(hwnd).unwrap().menu, menu);
+        assert_eq!(
+            backend.menu(menu).unwrap(),
+            vec![FakeMenuItem { flags: 0, id: 5, label: Some("File".into()) }, FakeMenuItem { flags: 0x800, id: 0, label: None }]
+        );
+    }
+
+    #[test]
+    fn size_changes_reach_class_procedures() {
+        let backend = FakeBackend::default();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let log = received.clone();
+        backend.register_proc(
+            "Layout",
+            Rc::new(move |_, msg, wparam, lparam| {
+                let lparam = if msg == WM_CREATE { unsafe { (*(lparam as *const FakeCreateStruct)).create_params as isize } } else { lparam };
+                log.borrow_mut().push(FakeMessage { msg, wparam, lparam });
+                0
+            }),
+        );
+        let hwnd = create(&backend, "Layout", 0, (0, 0, 10, 20));
+        backend.set_window_pos(hwnd, 5, 5, 0, 0, SWP_NOSIZE);
+        backend.set_window_pos(hwnd, 0, 0, 30, 40, SWP_NOMOVE);
+        backend.set_window_pos(hwnd, 0, 0, 30, 40, 0);
+        assert_eq!(
+            *received.borrow(),
+            vec![
+                FakeMessage { msg: WM_CREATE, wparam: 0, lparam: 42 },
+                FakeMessage { msg: WM_SIZE, wparam: SIZE_RESTORED, lparam: 10 | 20 << 16 },
+                FakeMessage { msg: WM_SIZE, wparam: SIZE_RESTORED, lparam: 30 | 40 << 16 },
+            ]
+        );
+    }
+
+    #[test]
+    fn subclass_procedures_come_before_the_class_one() {
+        unsafe extern "system" fn proc(_: Handle, msg: u32, _: usize, _: isize, _: usize, data: usize) -> isize {
+            (data + msg as usize) as isize
+        }
+        let backend = FakeBackend::default();
+        backend.register_proc("Edit", Rc::new(|_, msg, _, _| if msg == WM_CREATE { 0 } else { -1 }));
+        let address = proc as SubclassProc as usize;
+        let hwnd = create(&backend, "Edit", 0, (0, 0, 1, 1));
+        assert_eq!(backend.send_message(hwnd, 0x0400, 0, 0), -1);
+        backend.set_subclass(hwnd, address, 1, 100);
+        assert_eq!(backend.send_message(hwnd, 0x0400, 0, 0), 0x0400 + 100);
+        backend.remove_subclass(hwnd, address, 1);
+        assert_eq!(backend.send_message(hwnd, 0x0400, 0, 0), -1);
+    }
+
+    #[test]
+    fn def_subclass_proc_goes_down_the_chain() {
+        unsafe extern "system" fn add(hwnd: Handle, msg: u32, wparam: usize, lparam: isize, _: usize, data: usize) -> isize {
+            data as isize + super::super::current().def_subclass_proc(hwnd, msg, wparam, lparam)
+        }
+        unsafe extern "system" fn window_proc(_: Handle, msg: u32, _: usize, _: isize) -> isize {
+            msg as isize
+        }
+        let backend = Rc::new(FakeBackend::default());
+        let previous = super::super::set_current(backend.clone());
+        backend.register_class(&ClassParams {
+            class_name: to_wide("FakeChained").as_slice(),
+            style: 0,
+            wndproc: window_proc as WindowProc as usize,
+            background: None,
+            icon_and_cursor: false,
+        });
+        let hwnd = create(&backend, "FakeChained", 0, (0, 0, 1, 1));
+        backend.set_subclass(hwnd, add as SubclassProc as usize, 1, 10);
+        backend.set_subclass(hwnd, add as SubclassProc as usize, 2, 100);
+        assert_eq!(backend.send_message(hwnd, 0x0400, 0, 0), 0x0400 + 110);
+        super::super::set_current(previous);
+    }
+
+    #[test]
+    fn posted_messages_wait_for_dispatch() {
+        let backend = FakeBackend::default();
+        let received = Rc::new(Cell::new(0));
+        let log = received.clone();
+        backend.register_proc("Queue", Rc::new(move |_, msg, _, _| {
+            log.set(msg);
+            0
+        }));
+        let hwnd = create(&backend, "Queue", 0, (0, 0, 1, 1));
+        assert!(backend.post_message(hwnd, 0x0401, 0, 0));
+        assert_eq!(received.get(), WM_SIZE);
+        assert!(backend.dispatch_message());
+        assert_eq!(received.get(), 0x0401);
+        assert!(!backend.dispatch_message());
+        backend.post_quit(3);
+        assert_eq!(backend.quit_code(), Some(3));
+    }
+
+    #[test]
+    fn text_is_measured_by_characters_and_lines() {
+        let backend = FakeBackend::default();
+        let text = |s: &str| s.encode_utf16().collect::<Vec<_>>();
+        assert_eq!(backend.text_size(0, 0, text("Ok").as_slice(), None), (2 * CHAR_WIDTH, LINE_HEIGHT));
+        assert_eq!(backend.text_size(0, 0, text("one\nthree").as_slice(), None), (5 * CHAR_WIDTH, 2 * LINE_HEIGHT));
+        assert_eq!(backend.text_size(0, 0, text("one two three").as_slice(), Some(8 * CHAR_WIDTH)), (7 * CHAR_WIDTH, 2 * LINE_HEIGHT));
+        backend.set_dpi(192);
+        assert_eq!(backend.text_size(0, 0, text("Ok").as_slice(), None), (4 * CHAR_WIDTH, 2 * LINE_HEIGHT));
+    }
+
+    #[test]
+    fn scroll_position_stays_within_the_range() {
+        let backend = FakeBackend::default();
+        let hwnd = create(&backend, "Scroll", 0, (0, 0, 1, 1));
+        let info = ScrollInfo { min: 0, max: 99, page: 30, pos: 90, track_pos: 0 };
+        assert_eq!(backend.set_scroll_info(hwnd, SB_VERT, &info), 70);
+        assert_eq!(backend.scroll_info(hwnd, SB_VERT), Some(ScrollInfo { pos: 70, track_pos: 70, ..info }));
+        assert_eq!(backend.scroll_info(hwnd, SB_HORZ), Some(ScrollInfo::default()));
+        assert!(backend.show_scroll_bar(hwnd, SB_BOTH, true));
+        assert_eq!(backend.window(hwnd).unwrap().style & (WS_HSCROLL | WS_VSCROLL), WS_HSCROLL | WS_VSCROLL);
+    }
+
+    #[test]
+    fn visibility_counts_the_ancestors() {
+        let backend = FakeBackend::default();
+        let root = create(&backend, "Root", 0, (0, 0, 100, 100));
+        let child = create(&backend, "Child", root, (0, 0, 10, 10));
+        let grandchild = create(&backend, "Grandchild", child, (1, 2, 3, 4));
+        assert_eq!(backend.descendants(root), vec![child, grandchild]);
+        assert_eq!(backend.window_rect(grandchild), Rect::with_size(1, 2, 3, 4));
+        assert!(backend.is_visible(grandchild));
+        backend.show_window(root, false);
+        assert!(!backend.is_visible(grandchild));
+    }
+
+    #[test]
+    fn redraw_reaches_the_children_when_asked() {
+        let backend = FakeBackend::default();
+        let root = create(&backend, "Root", 0, (0, 0, 100, 100));
+        let child = create(&backend, "Child", root, (0, 0, 10, 10));
+        backend.redraw(root, false);
+        assert_eq!(backend.window(child).unwrap().invalidated, 0);
+        backend.redraw(root, true);
+        assert_eq!(backend.window(root).unwrap().invalidated, 2);
+        assert_eq!(backend.window(child).unwrap().invalidated, 1);
+    }
+
+    #[test]
+    fn dpi_defaults_to_base() {
+        let backend = FakeBackend::default();
+        assert_eq!(backend.dpi(0), crate::dpi::BASE_DPI);
+        backend.set_dpi(144);
+        assert_eq!(backend.dpi(0), 144);
+    }
+}
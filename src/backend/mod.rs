@@ -0,0 +1,237 @@
+//! The seam between the controls and the windowing system.
+//!
+//! The controls and `common` register their window classes, create and destroy their windows, send and post messages,
+//! move, show and measure them, and run the message loop through the `Backend` of the current thread. On Windows this is
+//! `Win32Backend`, which simply forwards to the OS. `FakeBackend` keeps the windows in memory, calls the registered window
+//! procedures and subclass procedures with the messages as the OS would, and records what was done with them.
+//!
+//! What stays direct is painting (device contexts, brushes, `DrawTextW` in `WM_PAINT`), icons, bitmaps and image lists,
+//! system metrics and settings, the state of the keyboard and mouse at the time of a message, tracking a popup menu,
+//! the shell and OLE, and the z-order of the label edit box of a tree: the fake never paints, and none of them changes
+//! the window tree or where the controls lay their children out. Text is measured through `Backend::text_size`,
+//! so layouts made with the fake do not depend on the fonts of the host.
+//!
+//! The controls are built on Windows only, as `winapi` is empty anywhere else. What compiles and runs under `cargo test`
+//! on every host is this module together with the window-free arithmetic of `dpi`, `layout_engine`, `navigation`,
+//! `numeric` and `selection`, which the controls delegate their logic to.
+//!
+//! Handles are plain `usize` values here, so this module does not depend on `winapi` and compiles everywhere.
+//!
+//! With the `headless` feature the fake is the default on Windows as well, and `snapshot` turns any window
+//! created through it into a `Snapshot` tree that can be serialized and compared between runs. As the fake hands
+//! `WM_CREATE` a `CREATESTRUCTW`, dispatches `WM_SIZE` to the subclass procedures of the controls and closes and destroys
+//! windows as `DefWindowProcW` does, the containers lay their children out, and windows and the message loop of the
+//! application come and go, just as they do on screen. Such tests run with `cargo test --features headless` on Windows.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod fake;
+mod snapshot;
+#[cfg(target_os = "windows")]
+mod win32;
+
+pub use self::fake::{FakeBackend, FakeCreateStruct, FakeMenuItem, FakeMessage, FakeWindow};
+pub use self::snapshot::Snapshot;
+#[cfg(target_os = "windows")]
+pub use self::win32::Win32Backend;
+
+pub type Handle = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "headless", derive(serde::Serialize))]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Rect {
+    pub fn with_size(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect {
+            left: x,
+            top: y,
+            right: x + width,
+            bottom: y + height,
+        }
+    }
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+}
+
+/// Arguments of `RegisterClassExW` that differ between the window classes of this crate.
+#[derive(Debug, Clone, Copy)]
+pub struct ClassParams<'a> {
+    pub class_name: &'a [u16],
+    pub style: u32,
+    /// Address of the `WNDPROC`.
+    pub wndproc: usize,
+    /// `COLOR_*` index to paint the background in, none for windows that paint all of it themselves.
+    pub background: Option<i32>,
+    /// Whether the windows get the application icon and the arrow cursor.
+    pub icon_and_cursor: bool,
+}
+
+/// Range, page and position of a scroll bar, as `SCROLLINFO` has them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScrollInfo {
+    pub min: i32,
+    pub max: i32,
+    pub page: u32,
+    pub pos: i32,
+    /// Where the thumb is while being dragged, only ever read.
+    pub track_pos: i32,
+}
+
+/// Arguments of `CreateWindowExW`. Strings are NUL-terminated UTF-16, as they are passed to the OS as is.
+#[derive(Debug, Clone, Copy)]
+pub struct CreateParams<'a> {
+    pub ex_style: u32,
+    pub class_name: &'a [u16],
+    pub window_name: &'a [u16],
+    pub style: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub parent: Handle,
+    pub menu: Handle,
+    pub param: usize,
+}
+
+pub trait Backend {
+    fn register_class(&self, class: &ClassParams) -> bool;
+    fn create_window(&self, params: &CreateParams) -> Handle;
+    fn destroy_window(&self, hwnd: Handle) -> bool;
+    fn is_window(&self, hwnd: Handle) -> bool;
+    fn class_name(&self, hwnd: Handle) -> Vec<u16>;
+
+    fn send_message(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> isize;
+    fn post_message(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> bool;
+    /// Takes the next message posted to the thread and dispatches it to its window. `false` if there was none.
+    fn dispatch_message(&self) -> bool;
+    fn post_quit(&self, code: i32);
+    fn def_window_proc(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> isize;
+    /// Passes a message on from a subclass procedure to the one installed before it, or to the window procedure.
+    fn def_subclass_proc(&self, hwnd: Handle, msg: u32, wparam: usize, lparam: isize) -> isize;
+
+    /// `proc` is the address of the `SUBCLASSPROC`, `0` meaning none.
+    fn set_subclass(&self, hwnd: Handle, proc: usize, id: usize, data: usize) -> bool;
+    fn remove_subclass(&self, hwnd: Handle, proc: usize, id: usize) -> bool;
+
+    fn set_window_pos(&self, hwnd: Handle, x: i32, y: i32, width: i32, height: i32, flags: u32) -> bool;
+    fn client_rect(&self, hwnd: Handle) -> Rect;
+    fn show_window(&self, hwnd: Handle, visible: bool) -> bool;
+    fn invalidate(&self, hwnd: Handle, erase: bool) -> bool;
+    /// Invalidates all of `hwnd`, and of its descendants with `children`, and paints it right away.
+    fn redraw(&self, hwnd: Handle, children: bool) -> bool;
+    /// Rectangle of `hwnd` in the client coordinates of its parent.
+    fn window_rect(&self, hwnd: Handle) -> Rect;
+    /// Whether `hwnd` and all of its ancestors are visible.
+    fn is_visible(&self, hwnd: Handle) -> bool;
+    /// DPI of the monitor `hwnd` is on, or of the primary one for no window.
+    fn dpi(&self, _hwnd: Handle) -> u32 {
+        crate::dpi::BASE_DPI
+    }
+
+    fn parent(&self, hwnd: Handle) -> Handle;
+    fn root(&self, hwnd: Handle) -> Handle;
+    /// Children of `hwnd`, their children and so on.
+    fn descendants(&self, hwnd: Handle) -> Vec<Handle>;
+    fn window_long_ptr(&self, hwnd: Handle, index: i32) -> isize;
+    fn set_window_long_ptr(&self, hwnd: Handle, index: i32, value: isize) -> isize;
+
+    fn window_text(&self, hwnd: Handle) -> Vec<u16>;
+    fn set_window_text(&self, hwnd: Handle, text: &[u16]) -> bool;
+    /// Width and height of `text`, not NUL-terminated, drawn in `font` on `hwnd`, a line per `\n`. With `max_width`, lines are
+    /// also broken between words to fit into it. A `font` of `0` is the one selected into the DC.
+    fn text_size(&self, hwnd: Handle, font: Handle, text: &[u16], max_width: Option<i32>) -> (i32, i32);
+
+    fn focus(&self) -> Handle;
+    /// Returns the window that had the focus.
+    fn set_focus(&self, hwnd: Handle) -> Handle;
+    fn set_capture(&self, hwnd: Handle);
+    fn release_capture(&self) -> bool;
+    /// Returns the previous cursor.
+    fn set_cursor(&self, cursor: Handle) -> Handle;
+
+    /// `bar` is `SB_HORZ` or `SB_VERT`.
+    fn scroll_info(&self, hwnd: Handle, bar: i32) -> Option<ScrollInfo>;
+    /// Sets range, page and position, the latter kept within the range, and returns the position.
+    fn set_scroll_info(&self, hwnd: Handle, bar: i32, info: &ScrollInfo) -> i32;
+    fn show_scroll_bar(&self, hwnd: Handle, bar: i32, show: bool) -> bool;
+
+    fn create_menu(&self, popup: bool) -> Handle;
+    fn append_menu(&self, menu: Handle, flags: u32, id: usize, label: Option<&[u16]>) -> bool;
+    fn set_menu(&self, hwnd: Handle, menu: Handle) -> bool;
+
+    /// Captures `hwnd` and its children, if this backend keeps track of them.
+    fn snapshot(&self, _hwnd: Handle) -> Option<Snapshot> {
+        None
+    }
+}
+
+thread_local! {
+    static BACKEND: RefCell<Rc<dyn Backend>> = RefCell::new(default_backend());
+}
+
+#[cfg(all(target_os = "windows", not(feature = "headless")))]
+fn default_backend() -> Rc<dyn Backend> {
+    Rc::new(Win32Backend)
+}
+#[cfg(any(not(target_os = "windows"), feature = "headless"))]
+fn default_backend() -> Rc<dyn Backend> {
+    Rc::new(FakeBackend::default())
+}
+
+/// Returns the backend of the current thread.
+///
+/// The value is cloned out of the thread-local, so the backend may be swapped or called again from within a
+/// window procedure that it has itself triggered.
+pub fn current() -> Rc<dyn Backend> {
+    BACKEND.with(|b| b.borrow().clone())
+}
+
+/// Installs `backend` for the current thread, returning the previous one.
+pub fn set_current(backend: Rc<dyn Backend>) -> Rc<dyn Backend> {
+    BACKEND.with(|b| b.replace(backend))
+}
+
+/// Captures `hwnd` and its children using the backend of the current thread.
+pub fn snapshot(hwnd: Handle) -> Option<Snapshot> {
+    current().snapshot(hwnd)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_current_swaps_the_backend_of_the_thread() {
+        let fake = Rc::new(FakeBackend::default());
+        let previous = set_current(fake.clone());
+        let hwnd = current().create_window(&CreateParams {
+            ex_style: 0,
+            class_name: &[0],
+            window_name: &[0],
+            style: 0,
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+            parent: 0,
+            menu: 0,
+            param: 0,
+        });
+        assert!(fake.is_window(hwnd));
+        assert_eq!(snapshot(hwnd).map(|s| s.rect), Some(Rect::with_size(0, 0, 10, 10)));
+        set_current(previous);
+        assert!(!Rc::ptr_eq(&current(), &(fake as Rc<dyn Backend>)));
+    }
+}
@@ -0,0 +1,185 @@
+use super::Rect;
+
+use std::fmt;
+
+/// What a fake window looked like at the moment it was captured, along with all of its children in creation order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "headless", derive(serde::Serialize))]
+pub struct Snapshot {
+    pub class_name: String,
+    pub style: u32,
+    pub ex_style: u32,
+    pub text: String,
+    pub rect: Rect,
+    pub children: Vec<Snapshot>,
+}
+
+impl Snapshot {
+    fn fmt_level(&self, f: &mut fmt::Formatter, level: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:indent$}{} {:?} style={:#010x} ex_style={:#010x} [{}, {} {}x{}]",
+            "",
+            self.class_name,
+            self.text,
+            self.style,
+            self.ex_style,
+            self.rect.left,
+            self.rect.top,
+            self.rect.width(),
+            self.rect.height(),
+            indent = level * 2
+        )?;
+        for child in self.children.iter() {
+            child.fmt_level(f, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line per window, children indented below their parent. Stable enough to be compared against a stored file.
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_level(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Backend, CreateParams, FakeBackend, Handle, Rect};
+
+    fn create(backend: &FakeBackend, class_name: &str, text: &str, parent: Handle, (style, ex_style): (u32, u32), (x, y, width, height): (i32, i32, i32, i32)) -> Handle {
+        backend.create_window(&CreateParams {
+            ex_style,
+            class_name: class_name.encode_utf16().chain(Some(0)).collect::<Vec<_>>().as_slice(),
+            window_name: text.encode_utf16().chain(Some(0)).collect::<Vec<_>>().as_slice(),
+            style,
+            x,
+            y,
+            width,
+            height,
+            parent,
+            menu: 0,
+            param: 0,
+        })
+    }
+
+    #[test]
+    fn one_line_per_window_in_creation_order() {
+        let backend = FakeBackend::default();
+        let window = create(&backend, "Window", "Snapshot", 0, (0x10cf_0003, 0x0204_0000), (0, 0, 320, 240));
+        let frame = create(&backend, "Frame", "", window, (0x5000_0000, 0x0201_0000), (6, 6, 308, 100));
+        create(&backend, "Button", "Ok", frame, (0x5003_0000, 0), (6, 22, 28, 28));
+        create(&backend, "static", "Last", window, (0x5003_0080, 0), (6, 112, 32, 16));
+
+        let snapshot = backend.snapshot(window).unwrap();
+        assert_eq!(snapshot.rect, Rect::with_size(0, 0, 320, 240));
+        assert_eq!(
+            snapshot.to_string(),
+            r#"Window "Snapshot" style=0x10cf0003 ex_style=0x02040000 [0, 0 320x240]
+  Frame "" style=0x50000000 ex_style=0x02010000 [6, 6 308x100]
+    Button "Ok" style=0x50030000 ex_style=0x00000000 [6, 22 28x28]
+  static "Last" style=0x50030080 ex_style=0x00000000 [6, 112 32x16]
+"#
+        );
+        assert_eq!(backend.snapshot(window + 1), None);
+    }
+
+    /// The containers of the crate, built and laid out by their own window procedures and subclass procedures.
+    #[cfg(all(target_os = "windows", feature = "headless"))]
+    mod containers {
+        use super::super::super::{self as backend, FakeBackend, Handle, Rect, Snapshot};
+        use crate::application::WindowsApplication;
+        use crate::button::WindowsButton;
+        use crate::common::*;
+        use crate::frame::WindowsFrame;
+        use crate::layout_linear::WindowsLinearLayout;
+        use crate::splitted::WindowsSplitted;
+        use crate::text::WindowsText;
+        use crate::window::WindowsWindow;
+
+        use plygui_api::controls::{Application, Closeable, MultiContainer, SingleContainer};
+
+        use std::rc::Rc;
+
+        fn outline(snapshot: &Snapshot, level: usize, lines: &mut Vec<(usize, String, String, u32, u32)>) {
+            lines.push((level, snapshot.class_name.clone(), snapshot.text.clone(), snapshot.style, snapshot.ex_style));
+            for child in snapshot.children.iter() {
+                outline(child, level + 1, lines);
+            }
+        }
+        fn assert_within_parents(snapshot: &Snapshot) {
+            for child in snapshot.children.iter() {
+                let inside = child.rect.left >= 0 && child.rect.top >= 0 && child.rect.right <= snapshot.rect.width() && child.rect.bottom <= snapshot.rect.height();
+                assert!(inside, "{} {:?} sticks out of {} {:?}", child.class_name, child.rect, snapshot.class_name, snapshot.rect);
+                assert_within_parents(child);
+            }
+        }
+
+        #[test]
+        fn window_linear_frame_splitted() {
+            let fake = Rc::new(FakeBackend::default());
+            let previous = backend::set_current(fake.clone());
+            {
+                let mut app = WindowsApplication::with_name("Snapshot");
+                app.set_frame_sleep(0);
+                let mut window = WindowsWindow::with_params(app.as_mut(), "Snapshot", types::WindowStartSize::Exact(320, 240), None);
+                let hwnd: Handle = fake.windows().into_iter().find(|(_, w)| w.class_name == "PlyguiWin32Window").unwrap().0;
+
+                let splitted = WindowsSplitted::with_content(
+                    WindowsButton::with_label("Left").into_control(),
+                    WindowsText::with_text("Right").into_control(),
+                    layout::Orientation::Horizontal,
+                );
+                let mut frame = WindowsFrame::with_label("Group");
+                frame.set_child(Some(splitted.into_control()));
+                let mut linear = WindowsLinearLayout::with_orientation(layout::Orientation::Vertical);
+                linear.push_child(WindowsButton::with_label("Ok").into_control());
+                linear.push_child(frame.into_control());
+                linear.push_child(WindowsText::with_text("Last").into_control());
+                window.set_child(Some(linear.into_control()));
+                // no frame around the client area here, which `AdjustWindowRectEx` made room for
+                fake.set_window_pos(hwnd, 0, 0, 320, 240, winuser::SWP_NOMOVE);
+
+                let snapshot = backend::snapshot(hwnd).unwrap();
+                let mut lines = Vec::new();
+                outline(&snapshot, 0, &mut lines);
+                let (child, container) = (winuser::WS_CHILD | winuser::WS_VISIBLE, winuser::WS_EX_CONTROLPARENT | winuser::WS_CLIPCHILDREN);
+                let button = child | winuser::BS_PUSHBUTTON | winuser::WS_TABSTOP | winuser::WS_GROUP;
+                let text = child | winuser::SS_NOPREFIX | winuser::WS_TABSTOP | winuser::WS_GROUP;
+                let expected = vec![
+                    (
+                        0,
+                        "PlyguiWin32Window",
+                        "Snapshot",
+                        winuser::WS_OVERLAPPEDWINDOW | winuser::WS_VISIBLE | winuser::CS_HREDRAW | winuser::CS_VREDRAW,
+                        winuser::WS_EX_APPWINDOW | winuser::WS_EX_COMPOSITED,
+                    ),
+                    (1, "PlyguiWin32LinearLayout", "", child, container),
+                    (2, "Button", "Ok", button, 0),
+                    (2, "PlyguiWin32Frame", "", child, container),
+                    (3, "PlyguiWin32Splitted", "", child, container),
+                    (4, "Button", "Left", button, 0),
+                    (4, "static", "Right", text, 0),
+                    (2, "Button", "Group", child | winuser::BS_GROUPBOX, 0),
+                    (2, "static", "Last", text, 0),
+                ];
+                let expected: Vec<_> = expected.into_iter().map(|(level, class_name, text, style, ex_style)| (level, class_name.to_owned(), text.to_owned(), style, ex_style)).collect();
+                assert_eq!(lines, expected, "\n{}", snapshot);
+                assert_eq!(snapshot.rect, Rect::with_size(0, 0, 320, 240));
+                assert_within_parents(&snapshot);
+
+                // through `WM_SYSCOMMAND`, `WM_CLOSE` and `WM_DESTROY` of the window procedure
+                let tree: Vec<Handle> = Some(hwnd).into_iter().chain(fake.descendants(hwnd)).collect();
+                assert!(window.close(false));
+                assert!(tree.iter().all(|hwnd| !fake.is_window(*hwnd)));
+                drop(window);
+
+                // no window left, so the loop destroys the root window, which posts the quit message
+                app.start();
+                assert_eq!(fake.quit_code(), Some(0));
+            }
+            backend::set_current(previous);
+        }
+    }
+}
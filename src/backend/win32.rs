@@ -0,0 +1,305 @@
+use super::{Backend, ClassParams, CreateParams, Handle, Rect, ScrollInfo};
+
+use winapi::shared::minwindef;
+use winapi::shared::windef;
+use winapi::um::commctrl;
+use winapi::um::libloaderapi;
+use winapi::um::wingdi;
+use winapi::um::winuser;
+
+use std::{mem, ptr};
+
+type GetDpiForWindow = unsafe extern "system" fn(windef::HWND) -> minwindef::UINT;
+
+lazy_static! {
+    /// Missing before Windows 10 1607, so looked up instead of linked.
+    static ref GET_DPI_FOR_WINDOW: Option<GetDpiForWindow> = unsafe {
+        let user32 = libloaderapi::GetModuleHandleW("user32.dll\0".encode_utf16().collect::<Vec<_>>().as_ptr());
+        let proc = libloaderapi::GetProcAddress(user32, b"GetDpiForWindow\0".as_ptr() as *const _);
+        if proc.is_null() {
+            None
+        } else {
+            Some(mem::transmute::<minwindef::FARPROC, GetDpiForWindow>(proc))
+        }
+    };
+}
+
+/// Forwards everything to the OS.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Win32Backend;
+
+#[inline]
+fn hwnd(h: Handle) -> windef::HWND {
+    h as windef::HWND
+}
+#[inline]
+fn subclass_proc(proc: usize) -> commctrl::SUBCLASSPROC {
+    unsafe { mem::transmute::<usize, commctrl::SUBCLASSPROC>(proc) }
+}
+#[inline]
+fn rect(rect: windef::RECT) -> Rect {
+    Rect {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+    }
+}
+
+impl Backend for Win32Backend {
+    fn register_class(&self, class: &ClassParams) -> bool {
+        unsafe {
+            let (icon, cursor) = if class.icon_and_cursor {
+                (winuser::LoadIconW(ptr::null_mut(), winuser::IDI_APPLICATION), winuser::LoadCursorW(ptr::null_mut(), winuser::IDC_ARROW))
+            } else {
+                (ptr::null_mut(), ptr::null_mut())
+            };
+            let class = winuser::WNDCLASSEXW {
+                cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as minwindef::UINT,
+                style: class.style,
+                lpfnWndProc: Some(mem::transmute::<usize, unsafe extern "system" fn(windef::HWND, minwindef::UINT, minwindef::WPARAM, minwindef::LPARAM) -> minwindef::LRESULT>(class.wndproc)),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
+                hIcon: icon,
+                hCursor: cursor,
+                hbrBackground: class.background.map(|color| (color + 1) as windef::HBRUSH).unwrap_or(ptr::null_mut()),
+                lpszMenuName: ptr::null(),
+                lpszClassName: class.class_name.as_ptr(),
+                hIconSm: ptr::null_mut(),
+            };
+            winuser::RegisterClassExW(&class) != 0
+        }
+    }
+    fn create_window(&self, params: &CreateParams) -> Handle {
+        unsafe {
+            winuser::CreateWindowExW(
+                params.ex_style,
+                params.class_name.as_ptr(),
+                params.window_name.as_ptr(),
+                params.style,
+                params.x,
+                params.y,
+                params.width,
+                params.height,
+                hwnd(params.parent),
+                params.menu as windef::HMENU,
+                libloaderapi::GetModuleHandleW(ptr::null()),
+                params.param as minwindef::LPVOID,
+            ) as Handle
+        }
+    }
+    fn destroy_window(&self, h: Handle) -> bool {
+        unsafe { winuser::DestroyWindow(hwnd(h)) != 0 }
+    }
+    fn is_window(&self, h: Handle) -> bool {
+        unsafe { winuser::IsWindow(hwnd(h)) != 0 }
+    }
+    fn class_name(&self, h: Handle) -> Vec<u16> {
+        let mut name = vec![0u16; 256];
+        let len = unsafe { winuser::GetClassNameW(hwnd(h), name.as_mut_ptr(), name.len() as i32) };
+        name.truncate(len as usize);
+        name
+    }
+
+    fn send_message(&self, h: Handle, msg: u32, wparam: usize, lparam: isize) -> isize {
+        unsafe { winuser::SendMessageW(hwnd(h), msg, wparam, lparam) }
+    }
+    fn post_message(&self, h: Handle, msg: u32, wparam: usize, lparam: isize) -> bool {
+        unsafe { winuser::PostMessageW(hwnd(h), msg, wparam, lparam) != 0 }
+    }
+    fn dispatch_message(&self) -> bool {
+        unsafe {
+            let mut msg: winuser::MSG = mem::zeroed();
+            if winuser::PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, winuser::PM_REMOVE) > 0 {
+                winuser::TranslateMessage(&msg);
+                winuser::DispatchMessageW(&msg);
+                true
+            } else {
+                false
+            }
+        }
+    }
+    fn post_quit(&self, code: i32) {
+        unsafe { winuser::PostQuitMessage(code) }
+    }
+    fn def_window_proc(&self, h: Handle, msg: u32, wparam: usize, lparam: isize) -> isize {
+        unsafe { winuser::DefWindowProcW(hwnd(h), msg, wparam, lparam) }
+    }
+    fn def_subclass_proc(&self, h: Handle, msg: u32, wparam: usize, lparam: isize) -> isize {
+        unsafe { commctrl::DefSubclassProc(hwnd(h), msg, wparam, lparam) }
+    }
+
+    fn set_subclass(&self, h: Handle, proc: usize, id: usize, data: usize) -> bool {
+        unsafe { commctrl::SetWindowSubclass(hwnd(h), subclass_proc(proc), id, data) != 0 }
+    }
+    fn remove_subclass(&self, h: Handle, proc: usize, id: usize) -> bool {
+        unsafe { commctrl::RemoveWindowSubclass(hwnd(h), subclass_proc(proc), id) != 0 }
+    }
+
+    fn set_window_pos(&self, h: Handle, x: i32, y: i32, width: i32, height: i32, flags: u32) -> bool {
+        unsafe { winuser::SetWindowPos(hwnd(h), ptr::null_mut(), x, y, width, height, flags) != 0 }
+    }
+    fn client_rect(&self, h: Handle) -> Rect {
+        let mut rc: windef::RECT = unsafe { mem::zeroed() };
+        unsafe {
+            winuser::GetClientRect(hwnd(h), &mut rc);
+        }
+        rect(rc)
+    }
+    fn window_rect(&self, h: Handle) -> Rect {
+        let mut rc: windef::RECT = unsafe { mem::zeroed() };
+        unsafe {
+            winuser::GetWindowRect(hwnd(h), &mut rc);
+            winuser::MapWindowPoints(ptr::null_mut(), winuser::GetParent(hwnd(h)), &mut rc as *mut _ as *mut windef::POINT, 2);
+        }
+        rect(rc)
+    }
+    fn is_visible(&self, h: Handle) -> bool {
+        unsafe { winuser::IsWindowVisible(hwnd(h)) != 0 }
+    }
+    fn show_window(&self, h: Handle, visible: bool) -> bool {
+        unsafe { winuser::ShowWindow(hwnd(h), if visible { winuser::SW_SHOW } else { winuser::SW_HIDE }) != 0 }
+    }
+    fn invalidate(&self, h: Handle, erase: bool) -> bool {
+        unsafe { winuser::InvalidateRect(hwnd(h), ptr::null_mut(), if erase { minwindef::TRUE } else { minwindef::FALSE }) != 0 }
+    }
+    fn redraw(&self, h: Handle, children: bool) -> bool {
+        let flags = winuser::RDW_INVALIDATE | winuser::RDW_UPDATENOW | if children { winuser::RDW_ALLCHILDREN } else { 0 };
+        unsafe { winuser::RedrawWindow(hwnd(h), ptr::null_mut(), ptr::null_mut(), flags) != 0 }
+    }
+    fn dpi(&self, h: Handle) -> u32 {
+        unsafe {
+            if let Some(get_dpi_for_window) = *GET_DPI_FOR_WINDOW {
+                match get_dpi_for_window(hwnd(h)) {
+                    0 => {}
+                    dpi => return dpi,
+                }
+            }
+            // the system DPI, all a process not aware of monitors gets
+            let dc = winuser::GetDC(ptr::null_mut());
+            let dpi = wingdi::GetDeviceCaps(dc, wingdi::LOGPIXELSX);
+            winuser::ReleaseDC(ptr::null_mut(), dc);
+            dpi as u32
+        }
+    }
+
+    fn parent(&self, h: Handle) -> Handle {
+        unsafe { winuser::GetParent(hwnd(h)) as Handle }
+    }
+    fn root(&self, h: Handle) -> Handle {
+        unsafe { winuser::GetAncestor(hwnd(h), winuser::GA_ROOT) as Handle }
+    }
+    fn descendants(&self, h: Handle) -> Vec<Handle> {
+        unsafe extern "system" fn collect(child: windef::HWND, found: minwindef::LPARAM) -> minwindef::BOOL {
+            (*(found as *mut Vec<Handle>)).push(child as Handle);
+            minwindef::TRUE
+        }
+        let mut found: Vec<Handle> = Vec::new();
+        unsafe {
+            winuser::EnumChildWindows(hwnd(h), Some(collect), &mut found as *mut _ as minwindef::LPARAM);
+        }
+        found
+    }
+    fn window_long_ptr(&self, h: Handle, index: i32) -> isize {
+        unsafe { winuser::GetWindowLongPtrW(hwnd(h), index) as isize }
+    }
+    fn set_window_long_ptr(&self, h: Handle, index: i32, value: isize) -> isize {
+        unsafe { winuser::SetWindowLongPtrW(hwnd(h), index, value as _) as isize }
+    }
+
+    fn window_text(&self, h: Handle) -> Vec<u16> {
+        unsafe {
+            let len = winuser::GetWindowTextLengthW(hwnd(h));
+            let mut text = vec![0u16; len as usize + 1];
+            let len = winuser::GetWindowTextW(hwnd(h), text.as_mut_ptr(), text.len() as i32);
+            text.truncate(len as usize);
+            text.push(0);
+            text
+        }
+    }
+    fn set_window_text(&self, h: Handle, text: &[u16]) -> bool {
+        unsafe { winuser::SetWindowTextW(hwnd(h), text.as_ptr()) != 0 }
+    }
+    fn text_size(&self, h: Handle, font: Handle, text: &[u16], max_width: Option<i32>) -> (i32, i32) {
+        let mut rc = windef::RECT {
+            left: 0,
+            top: 0,
+            right: max_width.unwrap_or(0),
+            bottom: 0,
+        };
+        let mut format = winuser::DT_CALCRECT | winuser::DT_NOPREFIX | winuser::DT_EXPANDTABS;
+        if max_width.is_some() {
+            format |= winuser::DT_WORDBREAK;
+        }
+        unsafe {
+            let dc = winuser::GetDC(hwnd(h));
+            let old_font = if font == 0 { ptr::null_mut() } else { wingdi::SelectObject(dc, font as windef::HGDIOBJ) };
+            winuser::DrawTextW(dc, text.as_ptr(), text.len() as i32, &mut rc, format);
+            if !old_font.is_null() {
+                wingdi::SelectObject(dc, old_font);
+            }
+            winuser::ReleaseDC(hwnd(h), dc);
+        }
+        (rc.right - rc.left, rc.bottom - rc.top)
+    }
+
+    fn focus(&self) -> Handle {
+        unsafe { winuser::GetFocus() as Handle }
+    }
+    fn set_focus(&self, h: Handle) -> Handle {
+        unsafe { winuser::SetFocus(hwnd(h)) as Handle }
+    }
+    fn set_capture(&self, h: Handle) {
+        unsafe {
+            winuser::SetCapture(hwnd(h));
+        }
+    }
+    fn release_capture(&self) -> bool {
+        unsafe { winuser::ReleaseCapture() != 0 }
+    }
+    fn set_cursor(&self, cursor: Handle) -> Handle {
+        unsafe { winuser::SetCursor(cursor as windef::HCURSOR) as Handle }
+    }
+
+    fn scroll_info(&self, h: Handle, bar: i32) -> Option<ScrollInfo> {
+        let mut info: winuser::SCROLLINFO = unsafe { mem::zeroed() };
+        info.cbSize = mem::size_of::<winuser::SCROLLINFO>() as u32;
+        info.fMask = winuser::SIF_ALL;
+        if unsafe { winuser::GetScrollInfo(hwnd(h), bar, &mut info) } == 0 {
+            return None;
+        }
+        Some(ScrollInfo {
+            min: info.nMin,
+            max: info.nMax,
+            page: info.nPage,
+            pos: info.nPos,
+            track_pos: info.nTrackPos,
+        })
+    }
+    fn set_scroll_info(&self, h: Handle, bar: i32, info: &ScrollInfo) -> i32 {
+        let info = winuser::SCROLLINFO {
+            cbSize: mem::size_of::<winuser::SCROLLINFO>() as u32,
+            fMask: winuser::SIF_RANGE | winuser::SIF_PAGE | winuser::SIF_POS,
+            nMin: info.min,
+            nMax: info.max,
+            nPage: info.page,
+            nPos: info.pos,
+            nTrackPos: 0,
+        };
+        unsafe { winuser::SetScrollInfo(hwnd(h), bar, &info, minwindef::TRUE) }
+    }
+    fn show_scroll_bar(&self, h: Handle, bar: i32, show: bool) -> bool {
+        unsafe { winuser::ShowScrollBar(hwnd(h), bar, if show { minwindef::TRUE } else { minwindef::FALSE }) != 0 }
+    }
+
+    fn create_menu(&self, popup: bool) -> Handle {
+        unsafe { (if popup { winuser::CreatePopupMenu() } else { winuser::CreateMenu() }) as Handle }
+    }
+    fn append_menu(&self, menu: Handle, flags: u32, id: usize, label: Option<&[u16]>) -> bool {
+        unsafe { winuser::AppendMenuW(menu as windef::HMENU, flags, id, label.map(|l| l.as_ptr()).unwrap_or(ptr::null())) != 0 }
+    }
+    fn set_menu(&self, h: Handle, menu: Handle) -> bool {
+        unsafe { winuser::SetMenu(hwnd(h), menu as windef::HMENU) != 0 }
+    }
+}
@@ -1,4 +1,9 @@
+use crate::backend;
 use crate::common::{self, *};
+use crate::font::Font;
+
+use std::cell::RefCell;
+use std::rc::Rc;
 
 const CLASS_ID: &str = "Button";
 
@@ -7,14 +12,253 @@ lazy_static! {
 }
 
 pub type Button = AMember<AControl<AButton<WindowsButton>>>;
+/// Check boxes and radio buttons are buttons of another kind, see `WindowsButton::check_box` and `WindowsButton::radio`.
+pub type CheckBox = Button;
+pub type RadioButton = Button;
+
+pub type OnToggle = Box<dyn FnMut(&mut dyn controls::Button, CheckState)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckState {
+    Unchecked,
+    Checked,
+    /// Only reachable by clicking in tri-state check boxes.
+    Indeterminate,
+}
+impl CheckState {
+    fn from_native(value: minwindef::LRESULT) -> CheckState {
+        match value as usize {
+            winuser::BST_CHECKED => CheckState::Checked,
+            winuser::BST_INDETERMINATE => CheckState::Indeterminate,
+            _ => CheckState::Unchecked,
+        }
+    }
+    fn to_native(self) -> minwindef::WPARAM {
+        match self {
+            CheckState::Unchecked => winuser::BST_UNCHECKED,
+            CheckState::Checked => winuser::BST_CHECKED,
+            CheckState::Indeterminate => winuser::BST_INDETERMINATE,
+        }
+    }
+}
+
+/// Radio buttons of one group have at most one of them checked, wherever they are placed in the window tree.
+/// Members are the buttons currently placed in a container, indexed in the order they were placed there.
+#[derive(Clone, Default)]
+pub struct RadioGroup(Rc<RefCell<RadioGroupInner>>);
+
+#[derive(Default)]
+struct RadioGroupInner {
+    /// Id and window of each member.
+    members: Vec<(usize, windef::HWND)>,
+    /// Id of the checked button, which may be out of any container at the moment.
+    selected: Option<usize>,
+    last_id: usize,
+}
+
+impl RadioGroupInner {
+    fn hwnd(&self, id: usize) -> Option<windef::HWND> {
+        self.members.iter().find(|(member, _)| *member == id).map(|(_, hwnd)| *hwnd)
+    }
+}
+
+impl RadioGroup {
+    pub fn new() -> RadioGroup {
+        RadioGroup::default()
+    }
+    pub fn len(&self) -> usize {
+        self.0.borrow().members.len()
+    }
+    pub fn selected(&self) -> Option<usize> {
+        let inner = self.0.borrow();
+        inner.selected.and_then(|id| inner.members.iter().position(|(member, _)| *member == id))
+    }
+    /// Does not call `on_toggle`.
+    pub fn select(&self, index: Option<usize>) {
+        let id = index.and_then(|i| self.0.borrow().members.get(i).map(|(id, _)| *id));
+        self.set_selected(id);
+    }
+
+    /// Returns the id a new button is known by in the group.
+    fn join(&self) -> usize {
+        let mut inner = self.0.borrow_mut();
+        inner.last_id += 1;
+        inner.last_id
+    }
+    fn attach(&self, id: usize, hwnd: windef::HWND) {
+        self.0.borrow_mut().members.push((id, hwnd));
+        self.restyle();
+    }
+    fn detach(&self, id: usize) {
+        self.0.borrow_mut().members.retain(|(member, _)| *member != id);
+        self.restyle();
+    }
+    fn is_selected(&self, id: usize) -> bool {
+        self.0.borrow().selected == Some(id)
+    }
+    /// Member next to `id`, wrapping around at the ends.
+    fn neighbour(&self, id: usize, forward: bool) -> Option<(usize, windef::HWND)> {
+        let inner = self.0.borrow();
+        let count = inner.members.len();
+        let index = inner.members.iter().position(|(member, _)| *member == id)?;
+        let next = if forward { (index + 1) % count } else { (index + count - 1) % count };
+        Some(inner.members[next])
+    }
+    /// Returns the members whose check state has changed.
+    fn set_selected(&self, id: Option<usize>) -> Vec<windef::HWND> {
+        let (unchecked, checked) = {
+            let mut inner = self.0.borrow_mut();
+            if inner.selected == id {
+                return Vec::new();
+            }
+            let previous = inner.selected.and_then(|id| inner.hwnd(id));
+            inner.selected = id;
+            (previous, id.and_then(|id| inner.hwnd(id)))
+        };
+        let mut changed = Vec::new();
+        for (hwnd, state) in unchecked.into_iter().map(|h| (h, CheckState::Unchecked)).chain(checked.into_iter().map(|h| (h, CheckState::Checked))) {
+            backend::current().send_message(hwnd as usize, winuser::BM_SETCHECK, state.to_native(), 0);
+            changed.push(hwnd);
+        }
+        self.restyle();
+        changed
+    }
+    /// Keeps `WS_GROUP` on the first member only, and `WS_TABSTOP` on the checked member, or on the first one when none is checked.
+    fn restyle(&self) {
+        let inner = self.0.borrow();
+        let tab_stop = inner.selected.filter(|id| inner.hwnd(*id).is_some()).or_else(|| inner.members.first().map(|(id, _)| *id));
+        for (index, (id, hwnd)) in inner.members.iter().enumerate() {
+            let mut style = backend::current().window_long_ptr(*hwnd as usize, winuser::GWL_STYLE) as minwindef::DWORD & !(winuser::WS_GROUP | winuser::WS_TABSTOP);
+            if index == 0 {
+                style |= winuser::WS_GROUP;
+            }
+            if tab_stop == Some(*id) {
+                style |= winuser::WS_TABSTOP;
+            }
+            backend::current().set_window_long_ptr(*hwnd as usize, winuser::GWL_STYLE, style as isize);
+        }
+    }
+}
+
+enum ButtonKind {
+    Push,
+    Check { tri_state: bool, state: CheckState },
+    Radio { group: RadioGroup, id: usize },
+}
 
 #[repr(C)]
 pub struct WindowsButton {
     base: common::WindowsControlBase<Button>,
     label: String,
+    kind: ButtonKind,
     h_left_clicked: Option<callbacks::OnClick>,
+    h_toggled: Option<OnToggle>,
     skip_callbacks: bool,
 }
+
+impl WindowsButton {
+    fn with_kind<S: AsRef<str>>(label: S, kind: ButtonKind) -> Box<Button> {
+        let mut b: Box<mem::MaybeUninit<Button>> = Box::new_uninit();
+        let mut ab = AMember::with_inner(
+            AControl::with_inner(
+                AButton::with_inner(
+                    <Self as NewButtonInner<Button>>::with_uninit(b.as_mut())
+                )
+            ),
+        );
+        ab.inner_mut().inner_mut().inner_mut().kind = kind;
+        controls::HasLabel::set_label(&mut ab, label.as_ref().into());
+        unsafe {
+	        b.as_mut_ptr().write(ab);
+	        b.assume_init()
+        }
+    }
+    /// With `tri_state`, clicking cycles through `CheckState::Indeterminate` as well.
+    pub fn check_box<S: AsRef<str>>(label: S, tri_state: bool) -> Box<CheckBox> {
+        Self::with_kind(label, ButtonKind::Check { tri_state, state: CheckState::Unchecked })
+    }
+    pub fn radio<S: AsRef<str>>(label: S, group: &RadioGroup) -> Box<RadioButton> {
+        Self::with_kind(label, ButtonKind::Radio { group: group.clone(), id: group.join() })
+    }
+
+    /// Always `CheckState::Unchecked` for push buttons.
+    pub fn check_state(&self) -> CheckState {
+        match self.kind {
+            ButtonKind::Push => CheckState::Unchecked,
+            ButtonKind::Check { state, .. } => state,
+            ButtonKind::Radio { ref group, id } => {
+                if group.is_selected(id) {
+                    CheckState::Checked
+                } else {
+                    CheckState::Unchecked
+                }
+            }
+        }
+    }
+    pub fn is_checked(&self) -> bool {
+        self.check_state() == CheckState::Checked
+    }
+    /// Does not call `on_toggle`. Unchecking a radio button leaves its group without a selection.
+    pub fn set_check_state(&mut self, value: CheckState) {
+        match self.kind {
+            ButtonKind::Push => {}
+            ButtonKind::Check { ref mut state, .. } => {
+                *state = value;
+                if !self.base.hwnd.is_null() {
+                    backend::current().send_message(self.base.hwnd as usize, winuser::BM_SETCHECK, value.to_native(), 0);
+                }
+            }
+            ButtonKind::Radio { ref group, id } => {
+                if value == CheckState::Checked {
+                    group.set_selected(Some(id));
+                } else if group.is_selected(id) {
+                    group.set_selected(None);
+                }
+            }
+        }
+    }
+    pub fn set_checked(&mut self, checked: bool) {
+        self.set_check_state(if checked { CheckState::Checked } else { CheckState::Unchecked });
+    }
+    pub fn radio_group(&self) -> Option<&RadioGroup> {
+        match self.kind {
+            ButtonKind::Radio { ref group, .. } => Some(group),
+            _ => None,
+        }
+    }
+    pub fn on_toggle(&mut self, handle: Option<OnToggle>) {
+        self.h_toggled = handle;
+    }
+    pub fn font(&self) -> Option<&Font> {
+        self.base.font()
+    }
+    /// `None` brings back the default font.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.base.set_font(font);
+    }
+
+    fn style(&self) -> minwindef::DWORD {
+        match self.kind {
+            ButtonKind::Push => winuser::BS_PUSHBUTTON,
+            ButtonKind::Check { tri_state: false, .. } => winuser::BS_AUTOCHECKBOX,
+            ButtonKind::Check { tri_state: true, .. } => winuser::BS_AUTO3STATE,
+            // grouping is ours, as auto radio buttons only see their siblings
+            ButtonKind::Radio { .. } => winuser::BS_RADIOBUTTON,
+        }
+    }
+    /// Returns the buttons whose check state has changed.
+    fn on_native_click(&mut self) -> Vec<windef::HWND> {
+        let hwnd = self.base.hwnd;
+        match self.kind {
+            ButtonKind::Push => Vec::new(),
+            ButtonKind::Check { ref mut state, .. } => {
+                *state = CheckState::from_native(backend::current().send_message(hwnd as usize, winuser::BM_GETCHECK, 0, 0));
+                vec![hwnd]
+            }
+            ButtonKind::Radio { ref group, id } => group.set_selected(Some(id)),
+        }
+    }
+}
 impl HasLabelInner for WindowsButton {
     fn label<'a>(&'a self, _: &MemberBase) -> Cow<'a, str> {
         Cow::Borrowed(self.label.as_ref())
@@ -24,9 +268,7 @@ impl HasLabelInner for WindowsButton {
         let hwnd = self.base.hwnd;
         if !hwnd.is_null() {
             let control_name = OsStr::new(&self.label).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-            unsafe {
-                winuser::SetWindowTextW(self.base.hwnd, control_name.as_ptr());
-            }
+            backend::current().set_window_text(self.base.hwnd as usize, control_name.as_slice());
             self.base.invalidate();
         }
     }
@@ -39,9 +281,7 @@ impl ClickableInner for WindowsButton {
     fn click(&mut self, skip_callbacks: bool) {
         if !self.base.hwnd.is_null() {
             self.skip_callbacks = skip_callbacks;
-            unsafe {
-                winuser::SendMessageW(self.base.hwnd, winuser::BM_CLICK, 0, 0);
-            }
+            backend::current().send_message(self.base.hwnd as usize, winuser::BM_CLICK, 0, 0);
         }
     }
 }
@@ -50,7 +290,9 @@ impl<O: controls::Button> NewButtonInner<O> for WindowsButton {
         WindowsButton {
             base: common::WindowsControlBase::with_handler(Some(handler::<O>)),
             h_left_clicked: None,
+            h_toggled: None,
             label: String::new(),
+            kind: ButtonKind::Push,
             skip_callbacks: false,
         }
     }
@@ -58,19 +300,7 @@ impl<O: controls::Button> NewButtonInner<O> for WindowsButton {
 
 impl ButtonInner for WindowsButton {
     fn with_label<S: AsRef<str>>(label: S) -> Box<dyn controls::Button> {
-        let mut b: Box<mem::MaybeUninit<Button>> = Box::new_uninit();
-        let mut ab = AMember::with_inner(
-            AControl::with_inner(
-                AButton::with_inner(
-                    <Self as NewButtonInner<Button>>::with_uninit(b.as_mut())
-                )
-            ),
-        );
-        controls::HasLabel::set_label(&mut ab, label.as_ref().into());
-        unsafe {
-	        b.as_mut_ptr().write(ab);
-	        b.assume_init()
-        }
+        Self::with_kind(label, ButtonKind::Push)
     }
 }
 
@@ -79,6 +309,11 @@ impl ControlInner for WindowsButton {
         let selfptr = member as *mut _ as *mut c_void;
         self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
         let (w, h, _) = self.measure(member, control, pw, ph);
+        // the group moves the tab stop of its members along with the check
+        let tab_stop = match self.kind {
+            ButtonKind::Radio { .. } => 0,
+            _ => winuser::WS_TABSTOP,
+        };
         self.base.create_control_hwnd(
             x as i32,
             y as i32,
@@ -88,11 +323,21 @@ impl ControlInner for WindowsButton {
             0,
             WINDOW_CLASS.as_ptr(),
             self.label.as_str(),
-            winuser::BS_PUSHBUTTON | winuser::WS_TABSTOP,
+            self.style() | tab_stop,
             selfptr,
         );
+        let state = self.check_state();
+        if let ButtonKind::Radio { ref group, id } = self.kind {
+            group.attach(id, self.base.hwnd);
+        }
+        if state != CheckState::Unchecked {
+            backend::current().send_message(self.base.hwnd as usize, winuser::BM_SETCHECK, state.to_native(), 0);
+        }
     }
     fn on_removed_from_container(&mut self, _member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        if let ButtonKind::Radio { ref group, id } = self.kind {
+            group.detach(id);
+        }
         self.base.destroy_control_hwnd();
     }
     fn parent(&self) -> Option<&dyn controls::Member> {
@@ -156,32 +401,22 @@ impl Drawable for WindowsButton {
         control.measured = match control.visibility {
             types::Visibility::Gone => (0, 0),
             _ => {
-                let mut label_size: windef::SIZE = unsafe { mem::zeroed() };
+                let label_size = common::measure_text(self.base.hwnd, self.base.hfont(), self.label.as_str(), None);
                 let w = match control.layout.width {
                     layout::Size::MatchParent => parent_width as i32,
                     layout::Size::Exact(w) => w as i32,
                     layout::Size::WrapContent => {
-                        if label_size.cx < 1 {
-                            let label = OsStr::new(self.label.as_str()).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-                            unsafe {
-                                wingdi::GetTextExtentPointW(winuser::GetDC(self.base.hwnd), label.as_ptr(), self.label.len() as i32, &mut label_size);
-                            }
-                        }
-                        label_size.cx as i32 + DEFAULT_PADDING + DEFAULT_PADDING
+                        let check = match self.kind {
+                            ButtonKind::Push => 0,
+                            _ => common::system_metric(self.base.hwnd, winuser::SM_CXMENUCHECK),
+                        };
+                        label_size.cx as i32 + check + common::default_padding(self.base.hwnd) * 2
                     }
                 };
                 let h = match control.layout.height {
                     layout::Size::MatchParent => parent_height as i32,
                     layout::Size::Exact(h) => h as i32,
-                    layout::Size::WrapContent => {
-                        if label_size.cy < 1 {
-                            let label = OsStr::new(self.label.as_str()).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-                            unsafe {
-                                wingdi::GetTextExtentPointW(winuser::GetDC(self.base.hwnd), label.as_ptr(), self.label.len() as i32, &mut label_size);
-                            }
-                        }
-                        label_size.cy as i32 + DEFAULT_PADDING + DEFAULT_PADDING
-                    }
+                    layout::Size::WrapContent => label_size.cy as i32 + common::default_padding(self.base.hwnd) * 2,
                 };
                 (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
             }
@@ -194,9 +429,9 @@ impl Drawable for WindowsButton {
 }
 
 unsafe extern "system" fn handler<T: controls::Button>(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
-        winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, param as WinPtr);
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
     }
     match msg {
         winuser::WM_LBUTTONUP => {
@@ -209,6 +444,37 @@ unsafe extern "system" fn handler<T: controls::Button>(hwnd: windef::HWND, msg:
                 }
             } 
         }
+        m if m == common::OCM_BASE + winuser::WM_COMMAND => {
+            if minwindef::HIWORD(wparam as u32) == winuser::BN_CLICKED {
+                let button: &mut Button = mem::transmute(param);
+                let skip_callbacks = button.inner().inner().inner().skip_callbacks;
+                let changed = button.inner_mut().inner_mut().inner_mut().on_native_click();
+                if !skip_callbacks {
+                    notify_toggled(changed);
+                }
+            }
+            return 0;
+        }
+        winuser::WM_KEYDOWN => {
+            let forward = match wparam as i32 {
+                winuser::VK_DOWN | winuser::VK_RIGHT => Some(true),
+                winuser::VK_UP | winuser::VK_LEFT => Some(false),
+                _ => None,
+            };
+            let button: &mut Button = mem::transmute(param);
+            let radio = match button.inner().inner().inner().kind {
+                ButtonKind::Radio { ref group, id } => Some((group.clone(), id)),
+                _ => None,
+            };
+            // there is no dialog manager in the message loop to move through the group, so the arrows are ours
+            if let (Some(forward), Some((group, id))) = (forward, radio) {
+                if let Some((next, next_hwnd)) = group.neighbour(id, forward) {
+                    backend::current().set_focus(next_hwnd as usize);
+                    notify_toggled(group.set_selected(Some(next)));
+                }
+                return 0;
+            }
+        }
         winuser::WM_SIZE => {
             let width = lparam as u16;
             let height = (lparam >> 16) as u16;
@@ -220,7 +486,24 @@ unsafe extern "system" fn handler<T: controls::Button>(hwnd: windef::HWND, msg:
         _ => {}
     }
 
-    commctrl::DefSubclassProc(hwnd, msg, wparam, lparam)
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
+
+/// Calls `on_toggle` of each of the `changed` buttons, reaching them through their windows.
+/// The callback is taken out of its button for the call, so that it may replace itself.
+fn notify_toggled(changed: Vec<windef::HWND>) {
+    for hwnd in changed {
+        if let Some(toggled) = common::member_from_hwnd::<Button>(hwnd) {
+            let state = toggled.inner().inner().inner().check_state();
+            if let Some(mut cb) = toggled.inner_mut().inner_mut().inner_mut().h_toggled.take() {
+                (cb.as_mut())(toggled, state);
+                let h_toggled = &mut toggled.inner_mut().inner_mut().inner_mut().h_toggled;
+                if h_toggled.is_none() {
+                    *h_toggled = Some(cb);
+                }
+            }
+        }
+    }
 }
 
 impl Spawnable for WindowsButton {
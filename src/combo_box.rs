@@ -0,0 +1,418 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::font::Font;
+
+const CLASS_ID: &str = commctrl::WC_COMBOBOX;
+const VISIBLE_ITEMS: usize = 12;
+
+lazy_static! {
+    pub static ref WINDOW_CLASS: Vec<u16> = OsStr::new(CLASS_ID).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+}
+
+pub type ComboBox = AMember<AControl<AContainer<AAdapted<WindowsComboBox>>>>;
+
+pub type OnSelect = Box<dyn FnMut(&mut ComboBox, Option<usize>)>;
+/// Receives the text typed into an editable combo box.
+pub type OnComboTextChange = Box<dyn FnMut(&mut ComboBox, &str)>;
+
+/// Item views spawned by the adapter stay hidden, the drop-down shows their window text.
+#[repr(C)]
+pub struct WindowsComboBox {
+    base: WindowsControlBase<ComboBox>,
+    editable: bool,
+    items: Vec<Box<dyn controls::Control>>,
+    selected: Option<usize>,
+    text: String,
+    on_select: Option<OnSelect>,
+    on_text_change: Option<OnComboTextChange>,
+}
+
+impl WindowsComboBox {
+    fn with_uninit(_: &mut mem::MaybeUninit<ComboBox>, editable: bool) -> Self {
+        WindowsComboBox {
+            base: WindowsControlBase::with_handler(Some(handler)),
+            editable,
+            items: vec![],
+            selected: None,
+            text: String::new(),
+            on_select: None,
+            on_text_change: None,
+        }
+    }
+    fn with_params(adapter: Box<dyn types::Adapter>, editable: bool) -> Box<ComboBox> {
+        let mut b: Box<mem::MaybeUninit<ComboBox>> = Box::new_uninit();
+        let ab = AMember::with_inner(
+            AControl::with_inner(
+                AContainer::with_inner(
+                    AAdapted::with_inner(
+                        Self::with_uninit(b.as_mut(), editable),
+                        adapter,
+                        &mut b,
+                    ),
+                )
+            ),
+        );
+        unsafe {
+	        b.as_mut_ptr().write(ab);
+	        b.assume_init()
+        }
+    }
+    /// Only items of the adapter can be chosen.
+    pub fn with_adapter(adapter: Box<dyn types::Adapter>) -> Box<ComboBox> {
+        Self::with_params(adapter, false)
+    }
+    /// Any text can be typed in besides choosing an item.
+    pub fn editable(adapter: Box<dyn types::Adapter>) -> Box<ComboBox> {
+        Self::with_params(adapter, true)
+    }
+
+    pub fn is_editable(&self) -> bool {
+        self.editable
+    }
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+    /// Does not call `on_select`.
+    pub fn set_selected(&mut self, index: Option<usize>) {
+        self.selected = index.filter(|i| self.base.hwnd.is_null() || *i < self.items.len());
+        self.apply_selected();
+    }
+    /// The typed text of editable combo boxes, the text of the selected item otherwise.
+    pub fn text(&self) -> String {
+        if self.base.hwnd.is_null() {
+            return self.text.clone();
+        }
+        common::window_text(self.base.hwnd)
+    }
+    /// Only applies to editable combo boxes, and clears the selection.
+    pub fn set_text<S: AsRef<str>>(&mut self, text: S) {
+        if !self.editable {
+            return;
+        }
+        self.text = text.as_ref().into();
+        self.selected = None;
+        if !self.base.hwnd.is_null() {
+            let text = common::str_to_wchar(self.text.as_str());
+            backend::current().send_message(self.base.hwnd as usize, winuser::CB_SETCURSEL, -1isize as usize, 0);
+            backend::current().set_window_text(self.base.hwnd as usize, text.as_slice());
+        }
+    }
+    /// Also called with `None` once typing into an editable combo box makes the text differ from the selected item.
+    pub fn on_select(&mut self, callback: Option<OnSelect>) {
+        self.on_select = callback;
+    }
+    /// Called as the user types into an editable combo box, not for `set_text` or choosing an item.
+    pub fn on_text_change(&mut self, callback: Option<OnComboTextChange>) {
+        self.on_text_change = callback;
+    }
+    pub fn font(&self) -> Option<&Font> {
+        self.base.font()
+    }
+    /// `None` brings back the default font.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.base.set_font(font);
+    }
+
+    fn apply_selected(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let index = self.selected.map(|i| i as isize).unwrap_or(-1);
+            backend::current().send_message(self.base.hwnd as usize, winuser::CB_SETCURSEL, index as usize, 0);
+        }
+    }
+    fn add_item_inner(&mut self, base: &mut MemberBase, indexes: &[usize]) {
+        let (member, control, adapter, _) = unsafe { ComboBox::adapter_base_parts_mut(base) };
+        let (pw, ph) = control.measured;
+        let this: &mut ComboBox = unsafe { utils::base_to_impl_mut(member) };
+
+        let mut item = adapter.adapter.spawn_item_view(indexes, this).unwrap();
+        item.on_added_to_container(this, 0, 0, pw, ph);
+        let item_hwnd = unsafe { item.native_id() as windef::HWND };
+        let label = common::str_to_wchar(common::window_text(item_hwnd));
+        backend::current().show_window(item_hwnd as usize, false);
+
+        let i = cmp::min(indexes[0], self.items.len());
+        self.items.insert(i, item);
+        unsafe {
+            if i as isize != backend::current().send_message(self.base.hwnd as usize, winuser::CB_INSERTSTRING, i, label.as_ptr() as isize) {
+                common::log_error();
+            }
+        }
+        if let Some(selected) = self.selected.as_mut() {
+            if *selected >= i {
+                *selected += 1;
+                self.apply_selected();
+            }
+        }
+    }
+    fn remove_item_inner(&mut self, base: &mut MemberBase, indexes: &[usize]) {
+        let i = indexes[0];
+        if i >= self.items.len() {
+            return;
+        }
+        let this: &mut ComboBox = unsafe { utils::base_to_impl_mut(base) };
+        self.items.remove(i).on_removed_from_container(this);
+        unsafe {
+            if winuser::CB_ERR == backend::current().send_message(self.base.hwnd as usize, winuser::CB_DELETESTRING, i, 0) {
+                common::log_error();
+            }
+        }
+        self.selected = match self.selected {
+            Some(selected) if selected == i => None,
+            Some(selected) if selected > i => Some(selected - 1),
+            other => other,
+        };
+        self.apply_selected();
+    }
+}
+
+impl AdaptedInner for WindowsComboBox {
+    fn on_item_change(&mut self, base: &mut MemberBase, value: adapter::Change) {
+        if !self.base.hwnd.is_null() {
+            match value {
+                adapter::Change::Added(at, _) => {
+                    self.add_item_inner(base, at);
+                }
+                adapter::Change::Removed(at) => {
+                    self.remove_item_inner(base, at);
+                }
+                adapter::Change::Edited(at, _) => {
+                    let selected = self.selected;
+                    self.remove_item_inner(base, at);
+                    self.add_item_inner(base, at);
+                    self.selected = selected;
+                    self.apply_selected();
+                }
+            }
+            self.base.invalidate();
+        }
+    }
+}
+
+impl ControlInner for WindowsComboBox {
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, px: i32, py: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (w, h, _) = self.measure(member, control, pw, ph);
+        self.base.create_control_hwnd(
+            px as i32,
+            py as i32,
+            w as i32,
+            h as i32,
+            self.base.hwnd,
+            0,
+            WINDOW_CLASS.as_ptr(),
+            "",
+            winuser::WS_TABSTOP | winuser::WS_VSCROLL | if self.editable { winuser::CBS_DROPDOWN | winuser::CBS_AUTOHSCROLL } else { winuser::CBS_DROPDOWNLIST },
+            selfptr,
+        );
+        control.coords = Some((px as i32, py as i32));
+        // with visual styles, the drop-down height comes from here rather than from the window height
+        backend::current().send_message(self.base.hwnd as usize, commctrl::CB_SETMINVISIBLE, VISIBLE_ITEMS, 0);
+
+        let selected = self.selected.take();
+        let (member, _, adapter, _) = unsafe { ComboBox::adapter_base_parts_mut(member) };
+        adapter.adapter.for_each(&mut (|indexes, _node| {
+            self.add_item_inner(member, indexes);
+        }));
+        self.selected = selected.filter(|i| *i < self.items.len());
+        if self.editable && self.selected.is_none() && !self.text.is_empty() {
+            let text = common::str_to_wchar(self.text.as_str());
+            backend::current().set_window_text(self.base.hwnd as usize, text.as_slice());
+        } else {
+            self.apply_selected();
+        }
+    }
+    fn on_removed_from_container(&mut self, member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        self.text = self.text();
+        for mut child in self.items.drain(..) {
+            let self2: &mut ComboBox = unsafe { utils::base_to_impl_mut(member) };
+            child.on_removed_from_container(self2);
+        }
+        self.base.destroy_control_hwnd();
+    }
+}
+impl ContainerInner for WindowsComboBox {
+    fn find_control_mut<'a>(&'a mut self, arg: types::FindBy<'a>) -> Option<&'a mut dyn controls::Control> {
+        for child in self.items.as_mut_slice() {
+            match arg {
+                types::FindBy::Id(ref id) => {
+                    if child.as_member_mut().id() == *id {
+                        return Some(child.as_mut());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member_mut().tag() {
+                        if tag == mytag {
+                            return Some(child.as_mut());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container_mut() {
+                let ret = c.find_control_mut(arg.clone());
+                if ret.is_none() {
+                    continue;
+                }
+                return ret;
+            }
+        }
+        None
+    }
+    fn find_control<'a>(&'a self, arg: types::FindBy<'a>) -> Option<&'a dyn controls::Control> {
+        for child in self.items.as_slice() {
+            match arg {
+                types::FindBy::Id(ref id) => {
+                    if child.as_member().id() == *id {
+                        return Some(child.as_ref());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member().tag() {
+                        if tag == mytag {
+                            return Some(child.as_ref());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container() {
+                let ret = c.find_control(arg.clone());
+                if ret.is_none() {
+                    continue;
+                }
+                return ret;
+            }
+        }
+        None
+    }
+}
+impl HasLayoutInner for WindowsComboBox {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        self.base.invalidate();
+    }
+}
+impl HasNativeIdInner for WindowsComboBox {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+impl MemberInner for WindowsComboBox {}
+
+impl HasSizeInner for WindowsComboBox {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsComboBox {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl Drawable for WindowsComboBox {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let w = match control.layout.width {
+                    layout::Size::MatchParent => parent_width as i32,
+                    layout::Size::Exact(w) => w as i32,
+                    layout::Size::WrapContent => {
+                        let mut widest = common::default_height(self.base.hwnd) * 4;
+                        let hfont = self.base.hfont();
+                        for item in self.items.as_slice() {
+                            let label = common::window_text(unsafe { item.native_id() as windef::HWND });
+                            widest = cmp::max(widest, common::measure_text(self.base.hwnd, hfont, label.as_str(), None).cx);
+                        }
+                        widest + common::system_metric(self.base.hwnd, winuser::SM_CXVSCROLL) + common::default_padding(self.base.hwnd) * 2
+                    }
+                };
+                let h = match control.layout.height {
+                    layout::Size::MatchParent => parent_height as i32,
+                    layout::Size::Exact(h) => h as i32,
+                    layout::Size::WrapContent => common::default_height(self.base.hwnd),
+                };
+                (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+impl Spawnable for WindowsComboBox {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_adapter(Box::new(types::imp::StringVecAdapter::<crate::imp::Text>::new())).into_control()
+    }
+}
+
+unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
+    }
+    match msg {
+        m if m == common::OCM_BASE + winuser::WM_COMMAND => {
+            match minwindef::HIWORD(wparam as u32) {
+                winuser::CBN_SELCHANGE => {
+                    let combo: &mut ComboBox = mem::transmute(param);
+                    let inner = combo.inner_mut().inner_mut().inner_mut().inner_mut();
+                    let index = backend::current().send_message(hwnd as usize, winuser::CB_GETCURSEL, 0, 0);
+                    inner.selected = if index == winuser::CB_ERR { None } else { Some(index as usize) };
+                    if let Some(ref mut cb) = inner.on_select {
+                        let combo2: &mut ComboBox = mem::transmute(param);
+                        (cb.as_mut())(combo2, inner.selected);
+                    }
+                }
+                winuser::CBN_EDITCHANGE => {
+                    let combo: &mut ComboBox = mem::transmute(param);
+                    let inner = combo.inner_mut().inner_mut().inner_mut().inner_mut();
+                    // the typed text is no item any more
+                    if inner.selected.take().is_some() {
+                        if let Some(ref mut cb) = inner.on_select {
+                            let combo2: &mut ComboBox = mem::transmute(param);
+                            (cb.as_mut())(combo2, None);
+                        }
+                    }
+                    let text = common::window_text(hwnd);
+                    if let Some(ref mut cb) = inner.on_text_change {
+                        let combo2: &mut ComboBox = mem::transmute(param);
+                        (cb.as_mut())(combo2, text.as_str());
+                    }
+                }
+                _ => {}
+            }
+            return 0;
+        }
+        winuser::WM_SIZE => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+
+            let combo: &mut ComboBox = mem::transmute(param);
+            combo.call_on_size::<ComboBox>(width, height);
+        }
+        _ => {}
+    }
+
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
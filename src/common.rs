@@ -24,13 +24,26 @@ pub use std::marker::PhantomData;
 pub use std::os::windows::ffi::OsStrExt;
 pub use std::{cmp, mem, ops, ptr, str, sync::mpsc};
 
+use crate::backend;
+use crate::dpi;
+use crate::font::{Font, FontRef};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
 lazy_static! {
 	pub static ref THEME_EXPLORER: Vec<u16> = OsStr::new("EXPLORER").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
 }
 
+/// At `dpi::BASE_DPI`, see `default_padding`.
 pub const DEFAULT_PADDING: i32 = 6;
+/// At `dpi::BASE_DPI`, see `default_height`.
 pub const DEFAULT_HEIGHT: i32 = 24;
+/// Number of sizes `measure_text` keeps, the least recently used one making room for a new one.
+const TEXT_SIZES_LIMIT: usize = 1024;
 pub const WM_UPDATE_INNER: u32 = winuser::WM_APP + 1;
+/// Notifications reflected back to the control that sent them arrive as `OCM_BASE + msg`, see `reflect_notification`.
+pub const OCM_BASE: u32 = winuser::WM_USER + 0x1c00;
 
 #[cfg(not(target_pointer_width = "32"))]
 pub type WinPtr = isize;
@@ -86,10 +99,94 @@ impl Drop for Hfont {
     }
 }
 unsafe impl Sync for Hfont {}
+unsafe impl Send for Hfont {}
+
+/// The message font at `dpi`, created on first use.
+fn hfont(dpi: u32) -> windef::HFONT {
+    *HFONTS.lock().unwrap().entry(dpi).or_insert_with(|| unsafe { message_font(dpi) }).as_ref()
+}
+fn is_default_font(hfont: windef::HFONT) -> bool {
+    HFONTS.lock().unwrap().values().any(|font| *font.as_ref() == hfont)
+}
+unsafe fn message_font(dpi: u32) -> Hfont {
+    let hfont = wingdi::CreateFontIndirectW(&message_logfont(dpi));
+    if hfont.is_null() {
+        log_error();
+    }
+    hfont.into()
+}
+/// Description of the system message font at `dpi`, the font of the controls unless they have one set.
+pub(crate) unsafe fn message_logfont(dpi: u32) -> wingdi::LOGFONTW {
+    type SystemParametersInfoForDpi = unsafe extern "system" fn(minwindef::UINT, minwindef::UINT, minwindef::LPVOID, minwindef::UINT, minwindef::UINT) -> minwindef::BOOL;
+
+    let mut ncm: winuser::NONCLIENTMETRICSW = mem::zeroed();
+    let size = mem::size_of::<winuser::NONCLIENTMETRICSW>() as u32;
+    ncm.cbSize = size;
+    let proc = user32_proc(b"SystemParametersInfoForDpi\0");
+    let got = if proc.is_null() {
+        // no per monitor DPI before Windows 10 1607, so the system metrics are the ones of the only DPI there is
+        winuser::SystemParametersInfoW(winuser::SPI_GETNONCLIENTMETRICS, size, &mut ncm as *mut _ as *mut c_void, 0)
+    } else {
+        mem::transmute::<minwindef::FARPROC, SystemParametersInfoForDpi>(proc)(winuser::SPI_GETNONCLIENTMETRICS, size, &mut ncm as *mut _ as *mut c_void, 0, dpi)
+    };
+    if got == 0 {
+        panic!("Cannot get NonClientMetrics for Font");
+    }
+    ncm.lfMessageFont
+}
+/// Looks up a function of `user32.dll` missing in older Windows versions. `name` is NUL-terminated.
+pub(crate) unsafe fn user32_proc(name: &[u8]) -> minwindef::FARPROC {
+    let user32 = str_to_wchar("user32.dll");
+    libloaderapi::GetProcAddress(libloaderapi::GetModuleHandleW(user32.as_ptr()), name.as_ptr() as *const _)
+}
 
+/// Makes the process aware of the DPI of each monitor, unless the manifest did that already.
+pub(crate) fn enable_dpi_awareness() {
+    type SetProcessDpiAwarenessContext = unsafe extern "system" fn(windef::DPI_AWARENESS_CONTEXT) -> minwindef::BOOL;
+
+    unsafe {
+        let proc = user32_proc(b"SetProcessDpiAwarenessContext\0");
+        if !proc.is_null() {
+            // fails with ERROR_ACCESS_DENIED when already set, which is fine
+            mem::transmute::<minwindef::FARPROC, SetProcessDpiAwarenessContext>(proc)(windef::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2);
+        }
+    }
+}
+/// DPI of the monitor `hwnd` is on.
+#[inline]
+pub fn dpi(hwnd: windef::HWND) -> u32 {
+    backend::current().dpi(hwnd as usize)
+}
+/// Scale of the monitor `hwnd` is on, `1.0` being 100%.
 #[inline]
-fn hfont() -> windef::HFONT {
-    *(*HFONT).as_ref()
+pub fn scale_factor(hwnd: windef::HWND) -> f32 {
+    dpi::scale_factor(dpi(hwnd))
+}
+/// Scales `value`, given at `dpi::BASE_DPI`, to the monitor `hwnd` is on.
+#[inline]
+pub fn scaled(hwnd: windef::HWND, value: i32) -> i32 {
+    dpi::scale(value, dpi(hwnd))
+}
+/// `GetSystemMetrics` for the monitor `hwnd` is on.
+pub fn system_metric(hwnd: windef::HWND, index: i32) -> i32 {
+    type GetSystemMetricsForDpi = unsafe extern "system" fn(i32, minwindef::UINT) -> i32;
+
+    unsafe {
+        let proc = user32_proc(b"GetSystemMetricsForDpi\0");
+        if proc.is_null() {
+            winuser::GetSystemMetrics(index)
+        } else {
+            mem::transmute::<minwindef::FARPROC, GetSystemMetricsForDpi>(proc)(index, dpi(hwnd))
+        }
+    }
+}
+#[inline]
+pub fn default_padding(hwnd: windef::HWND) -> i32 {
+    scaled(hwnd, DEFAULT_PADDING)
+}
+#[inline]
+pub fn default_height(hwnd: windef::HWND) -> i32 {
+    scaled(hwnd, DEFAULT_HEIGHT)
 }
 
 #[inline]
@@ -97,19 +194,9 @@ pub fn hinstance() -> minwindef::HINSTANCE {
     *INSTANCE as *mut c_void as minwindef::HINSTANCE
 }
 lazy_static! {
-    static ref HFONT: Hfont = unsafe {
-        let mut ncm: winuser::NONCLIENTMETRICSW = mem::zeroed();
-        let size = mem::size_of::<winuser::NONCLIENTMETRICSW>() as u32;
-        ncm.cbSize = size;
-        if winuser::SystemParametersInfoW(winuser::SPI_GETNONCLIENTMETRICS, size, &mut ncm as *mut _ as *mut ::winapi::ctypes::c_void, size) == 0 {
-            panic!("Cannot get NonClientMetrics for Font");
-        }
-        let hfont = wingdi::CreateFontIndirectW(&mut ncm.lfMessageFont);
-        if hfont.is_null() {
-            log_error();
-        }
-        hfont.into()
-    };
+    /// Message fonts by DPI.
+    static ref HFONTS: Mutex<HashMap<u32, Hfont>> = Mutex::new(HashMap::new());
+    static ref TEXT_SIZES: Mutex<TextSizes> = Mutex::new(TextSizes::default());
     static ref INSTANCE: usize = unsafe { libloaderapi::GetModuleHandleW(ptr::null()) as usize };
 }
 
@@ -145,6 +232,8 @@ pub struct WindowsControlBase<T: controls::Control + Sized> {
     pub hwnd: windef::HWND,
     pub subclass_id: usize,
     pub proc_handler: WndProcHandler<T>,
+    font: Option<Font>,
+    hfont: Option<FontRef>,
 }
 /* hello 0119
 pub trait HasWindowsControlBase {
@@ -168,6 +257,8 @@ impl<T: controls::Control + Sized> WindowsControlBase<T> {
             hwnd: 0 as windef::HWND,
             subclass_id: 0,
             proc_handler: h,
+            font: None,
+            hfont: None,
         }
     }
     pub fn with_handler(handler: Option<WndHandler>) -> WindowsControlBase<T> {
@@ -178,58 +269,40 @@ impl<T: controls::Control + Sized> WindowsControlBase<T> {
     }
 
     pub fn parent_hwnd(&self) -> Option<windef::HWND> {
-        unsafe {
-            let parent_hwnd = winuser::GetParent(self.hwnd);
-            if parent_hwnd == self.hwnd {
-                None
-            } else {
-                Some(parent_hwnd)
-            }
+        let parent_hwnd = backend::current().parent(self.hwnd as usize) as windef::HWND;
+        if parent_hwnd == self.hwnd {
+            None
+        } else {
+            Some(parent_hwnd)
         }
     }
     pub fn parent(&self) -> Option<&MemberBase> {
-        unsafe {
-            let parent_hwnd = winuser::GetParent(self.hwnd);
-            if parent_hwnd == self.hwnd {
-                return None;
-            }
-
-            let parent_ptr = winuser::GetWindowLongPtrW(parent_hwnd, winuser::GWLP_USERDATA);
-            mem::transmute(parent_ptr as *mut c_void)
+        let parent_hwnd = backend::current().parent(self.hwnd as usize) as windef::HWND;
+        if parent_hwnd == self.hwnd {
+            return None;
         }
+        unsafe { cast_hwnd(parent_hwnd).map(|p| &*p) }
     }
     pub fn parent_mut(&mut self) -> Option<&mut MemberBase> {
-        unsafe {
-            let parent_hwnd = winuser::GetParent(self.hwnd);
-            if parent_hwnd == self.hwnd {
-                return None;
-            }
-
-            let parent_ptr = winuser::GetWindowLongPtrW(parent_hwnd, winuser::GWLP_USERDATA);
-            mem::transmute(parent_ptr as *mut c_void)
+        let parent_hwnd = backend::current().parent(self.hwnd as usize) as windef::HWND;
+        if parent_hwnd == self.hwnd {
+            return None;
         }
+        unsafe { cast_hwnd(parent_hwnd) }
     }
     pub fn root(&self) -> Option<&MemberBase> {
-        unsafe {
-            let parent_hwnd = winuser::GetAncestor(self.hwnd, 2); //GA_ROOT
-            if parent_hwnd == self.hwnd {
-                return None;
-            }
-
-            let parent_ptr = winuser::GetWindowLongPtrW(parent_hwnd, winuser::GWLP_USERDATA);
-            mem::transmute(parent_ptr as *mut c_void)
+        let root_hwnd = backend::current().root(self.hwnd as usize) as windef::HWND;
+        if root_hwnd == self.hwnd {
+            return None;
         }
+        unsafe { cast_hwnd(root_hwnd).map(|p| &*p) }
     }
     pub fn root_mut(&mut self) -> Option<&mut MemberBase> {
-        unsafe {
-            let parent_hwnd = winuser::GetAncestor(self.hwnd, 2); //GA_ROOT
-            if parent_hwnd == self.hwnd {
-                return None;
-            }
-
-            let parent_ptr = winuser::GetWindowLongPtrW(parent_hwnd, winuser::GWLP_USERDATA);
-            mem::transmute(parent_ptr as *mut c_void)
+        let root_hwnd = backend::current().root(self.hwnd as usize) as windef::HWND;
+        if root_hwnd == self.hwnd {
+            return None;
         }
+        unsafe { cast_hwnd(root_hwnd) }
     }
     pub fn as_outer(&self) -> &T {
         member_from_hwnd::<T>(self.hwnd).unwrap()
@@ -256,18 +329,65 @@ impl<T: controls::Control + Sized> WindowsControlBase<T> {
             }
         }*/
         //this.draw(None);
-        unsafe {
-           winuser::InvalidateRect(self.hwnd, ptr::null_mut(), minwindef::FALSE);
+        backend::current().invalidate(self.hwnd as usize, false);
+    }
+    /// Lays the children of the parent out anew, which measures this control again, for when the size it wraps has changed.
+    pub fn relayout_parent(&self) {
+        if self.hwnd.is_null() {
+            return;
+        }
+        if let Some(parent) = self.parent_hwnd() {
+            let (width, height) = size_hwnd(parent);
+            let packed = ((height as i32) << 16) + width as i32;
+            backend::current().send_message(parent as usize, WM_UPDATE_INNER, 0, packed as isize);
         }
     }
     pub fn draw(&mut self, coords: Option<(i32, i32)>, (width, height): (u16, u16)) -> bool {
+        if self.font.is_some() && !self.hwnd.is_null() {
+            // the window may have moved to a monitor with another DPI
+            let hwnd = self.hwnd;
+            self.apply_font(hwnd);
+        }
         draw(self.hwnd, coords, (width, height))
     }
-    pub fn on_set_visibility(&mut self, visibility: types::Visibility) -> bool {
+    pub fn font(&self) -> Option<&Font> {
+        self.font.as_ref()
+    }
+    /// `None` brings back the default font. The parent lays its children out again, as the text takes another size.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.font = font;
+        self.hfont = None;
         if !self.hwnd.is_null() {
-            unsafe {
-                winuser::ShowWindow(self.hwnd, if visibility == types::Visibility::Visible { winuser::SW_SHOW } else { winuser::SW_HIDE });
+            let hwnd = self.hwnd;
+            self.apply_font(hwnd);
+            self.invalidate();
+            self.relayout_parent();
+        }
+    }
+    /// Font to draw and measure the text of the control with, at the DPI of the monitor it is on.
+    pub fn hfont(&mut self) -> windef::HFONT {
+        let dpi = dpi(self.hwnd);
+        match self.font {
+            Some(ref font) => {
+                if self.hfont.as_ref().map(|hfont| hfont.dpi()) != Some(dpi) {
+                    self.hfont = Some(FontRef::new(font, dpi));
+                }
+                self.hfont.as_ref().unwrap().hfont()
             }
+            None => hfont(dpi),
+        }
+    }
+    /// Gives the font of the control to `hwnd`, the control window or the one showing its text.
+    pub fn apply_font(&mut self, hwnd: windef::HWND) {
+        let hfont = self.hfont();
+        let backend = backend::current();
+        if backend.send_message(hwnd as usize, winuser::WM_GETFONT, 0, 0) as windef::HFONT != hfont {
+            backend.send_message(hwnd as usize, winuser::WM_SETFONT, hfont as usize, minwindef::TRUE as isize);
+        }
+    }
+    pub fn on_set_visibility(&mut self, visibility: types::Visibility) -> bool {
+        if !self.hwnd.is_null() {
+            backend::current().show_window(self.hwnd as usize, visibility == types::Visibility::Visible);
             self.invalidate();
             true
         } else {
@@ -290,6 +410,9 @@ impl<T: controls::Control + Sized> WindowsControlBase<T> {
         let (hwnd, subclass_id) = unsafe { create_control_hwnd(x, y, w, h, parent, ex_style, class_name, control_name, style, param, self.proc_handler.as_handler()) };
         self.hwnd = hwnd; 
         self.subclass_id = subclass_id;
+        if self.font.is_some() {
+            self.apply_font(hwnd);
+        }
     }
     pub fn destroy_control_hwnd(&mut self) {
         match self.proc_handler {
@@ -313,11 +436,7 @@ pub fn pos_hwnd(hwnd: windef::HWND) -> (i32, i32) {
 }
 
 pub unsafe fn get_class_name_by_hwnd(hwnd: windef::HWND) -> Vec<u16> {
-    let mut max_id = 256;
-    let mut name = vec![0u16; max_id];
-    max_id = winuser::GetClassNameW(hwnd, name.as_mut_slice().as_ptr(), max_id as i32) as usize;
-    name.truncate(max_id);
-    name
+    backend::current().class_name(hwnd as usize)
 }
 
 pub unsafe fn create_control_hwnd(
@@ -338,27 +457,62 @@ pub unsafe fn create_control_hwnd(
         style |= winuser::WS_GROUP;
     }
     let os_control_name = OsStr::new(control_name).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-    let hwnd = winuser::CreateWindowExW(
+    let backend = backend::current();
+    let hwnd = backend.create_window(&backend::CreateParams {
         ex_style,
-        class_name,
-        os_control_name.as_ptr(),
-        style | winuser::WS_CHILD | winuser::WS_VISIBLE,
+        class_name: wchar_slice(class_name),
+        window_name: os_control_name.as_slice(),
+        style: style | winuser::WS_CHILD | winuser::WS_VISIBLE,
         x,
         y,
-        w,
-        h,
-        parent,
-        ptr::null_mut(),
-        hinstance(),
-        param,
-    );
+        width: w,
+        height: h,
+        parent: parent as usize,
+        menu: 0,
+        param: param as usize,
+    }) as windef::HWND;
     if hwnd.is_null() {
         log_error();
         panic!("Cannot create window {}", control_name);
     }
-    commctrl::SetWindowSubclass(hwnd, handler, subclass_id(class_name) as usize, param as usize);
+    let id = subclass_id(class_name) as usize;
+    backend.set_subclass(hwnd as usize, handler.map(|h| h as usize).unwrap_or(0), id, param as usize);
     set_default_font(hwnd);
-    (hwnd, subclass_id as usize)
+    (hwnd, id)
+}
+
+/// Creates a window a control is made of besides its own one, e.g. the group box of a frame, as a visible child of `parent`.
+/// With `handler`, the window is subclassed with `param` as the data, the same way `create_control_hwnd` does.
+pub unsafe fn create_part_hwnd(
+    (x, y, w, h): (i32, i32, i32, i32),
+    parent: windef::HWND,
+    ex_style: minwindef::DWORD,
+    class_name: ntdef::LPCWSTR,
+    window_name: &[u16],
+    style: minwindef::DWORD,
+    param: minwindef::LPVOID,
+    handler: Option<WndHandler>,
+) -> windef::HWND {
+    let backend = backend::current();
+    let hwnd = backend.create_window(&backend::CreateParams {
+        ex_style,
+        class_name: wchar_slice(class_name),
+        window_name,
+        style: style | winuser::WS_CHILD | winuser::WS_VISIBLE,
+        x,
+        y,
+        width: w,
+        height: h,
+        parent: parent as usize,
+        menu: 0,
+        param: param as usize,
+    }) as windef::HWND;
+    if hwnd.is_null() {
+        log_error();
+    } else if let Some(handler) = handler {
+        backend.set_subclass(hwnd as usize, handler as usize, subclass_id(class_name) as usize, param as usize);
+    }
+    hwnd
 }
 
 pub fn subclass_id(class_name: ntdef::LPCWSTR) -> u64 {
@@ -373,25 +527,116 @@ pub fn subclass_id(class_name: ntdef::LPCWSTR) -> u64 {
 pub fn str_to_wchar<S: AsRef<str>>(a: S) -> Vec<u16> {
     OsStr::new(a.as_ref()).encode_wide().chain(Some(0).into_iter()).collect()
 }
+/// Text of `hwnd` without the terminating NUL.
+pub fn window_text(hwnd: windef::HWND) -> String {
+    let wbuffer = backend::current().window_text(hwnd as usize);
+    let len = wbuffer.iter().position(|c| *c == 0).unwrap_or(wbuffer.len());
+    String::from_utf16_lossy(&wbuffer[..len])
+}
 pub unsafe fn wchar_to_str(p: *const u16) -> String {
     let len = (0..).take_while(|&i| *p.offset(i) != 0).count();
     let slice = std::slice::from_raw_parts(p, len);
     String::from_utf16_lossy(slice)
 }
+/// Borrows a NUL-terminated wide string, including the terminator.
+pub unsafe fn wchar_slice<'a>(p: *const u16) -> &'a [u16] {
+    let len = (0..).take_while(|&i| *p.offset(i) != 0).count();
+    std::slice::from_raw_parts(p, len + 1)
+}
+
+type TextKey = (usize, Option<i32>, String);
+
+/// Sizes measured by `measure_text`, by font, width to wrap at and text, along with when each was last used.
+#[derive(Default)]
+struct TextSizes {
+    sizes: HashMap<TextKey, (windef::SIZE, u64)>,
+    clock: u64,
+}
+impl TextSizes {
+    fn get(&mut self, key: &TextKey) -> Option<windef::SIZE> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.sizes.get_mut(key).map(|(size, used)| {
+            *used = clock;
+            *size
+        })
+    }
+    /// Once full, the least recently used size is looked up among all of them and evicted, which is still far cheaper than `DrawTextW`.
+    fn insert(&mut self, key: TextKey, size: windef::SIZE) {
+        if self.sizes.len() >= TEXT_SIZES_LIMIT && !self.sizes.contains_key(&key) {
+            if let Some(oldest) = self.sizes.iter().min_by_key(|(_, (_, used))| *used).map(|(key, _)| key.clone()) {
+                self.sizes.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.sizes.insert(key, (size, self.clock));
+    }
+    fn forget(&mut self, hfont: windef::HFONT) {
+        self.sizes.retain(|(font, _, _), _| *font != hfont as usize);
+    }
+}
+
+/// Font `hwnd` draws its text in, the message font at its DPI if it has not been given one.
+fn window_font(hwnd: windef::HWND) -> windef::HFONT {
+    let font = if hwnd.is_null() { ptr::null_mut() } else { backend::current().send_message(hwnd as usize, winuser::WM_GETFONT, 0, 0) as windef::HFONT };
+    if font.is_null() {
+        hfont(dpi(hwnd))
+    } else {
+        font
+    }
+}
+
+/// Size of `text` drawn in `hfont` on the DC of `hwnd`, a line per `\n`. With `max_width`, lines are also broken between words
+/// to fit into it, as static controls do. Empty text is as high as a line of the font and has no width.
+/// A null `hfont` stands for the font of `hwnd`.
+///
+/// The sizes are cached per font, so measuring the same text again on every layout pass is cheap.
+pub fn measure_text(hwnd: windef::HWND, hfont: windef::HFONT, text: &str, max_width: Option<i32>) -> windef::SIZE {
+    // resolved before keying the cache, as windows with different fonts all pass null
+    let hfont = if hfont.is_null() { window_font(hwnd) } else { hfont };
+    let key = (hfont as usize, max_width, text.to_owned());
+    if let Some(size) = TEXT_SIZES.lock().unwrap().get(&key) {
+        return size;
+    }
+    // UTF-16 units, which is what the length passed to GDI counts
+    let wide = OsStr::new(if text.is_empty() { " " } else { text }).encode_wide().collect::<Vec<_>>();
+    let (width, height) = backend::current().text_size(hwnd as usize, hfont as usize, wide.as_slice(), max_width);
+    let size = windef::SIZE {
+        cx: if text.is_empty() { 0 } else { width },
+        cy: height,
+    };
+    TEXT_SIZES.lock().unwrap().insert(key, size);
+    size
+}
+/// Drops the sizes measured in `hfont`, which is about to be deleted, so that a font created later with the same handle is measured anew.
+pub(crate) fn forget_text_sizes(hfont: windef::HFONT) {
+    TEXT_SIZES.lock().unwrap().forget(hfont);
+}
 
 #[inline]
 pub unsafe fn set_default_font(hwnd: windef::HWND) {
-    winuser::SendMessageW(hwnd, winuser::WM_SETFONT, hfont() as usize, minwindef::TRUE as isize);
+    backend::current().send_message(hwnd as usize, winuser::WM_SETFONT, hfont(dpi(hwnd)) as usize, minwindef::TRUE as isize);
+}
+/// Swaps the default font of every window under `hwnd` for the one at `dpi`, after the window was moved to another monitor.
+pub unsafe fn update_default_fonts(hwnd: windef::HWND, dpi: u32) {
+    let backend = backend::current();
+    for child in backend.descendants(hwnd as usize) {
+        let font = backend.send_message(child, winuser::WM_GETFONT, 0, 0) as windef::HFONT;
+        if !font.is_null() && is_default_font(font) {
+            backend.send_message(child, winuser::WM_SETFONT, hfont(dpi) as usize, minwindef::FALSE as isize);
+        }
+    }
 }
 
 pub fn destroy_hwnd(hwnd: windef::HWND, subclass_id: usize, handler: Option<unsafe extern "system" fn(windef::HWND, msg: minwindef::UINT, minwindef::WPARAM, minwindef::LPARAM, usize, usize) -> isize>) {
+    let backend = backend::current();
     unsafe {
         if subclass_id != 0 {
-            if minwindef::FALSE == commctrl::RemoveWindowSubclass(hwnd, handler, subclass_id) {
+            if !backend.remove_subclass(hwnd as usize, handler.map(|h| h as usize).unwrap_or(0), subclass_id) {
                 log_error();
             }
         }
-        if winuser::DestroyWindow(hwnd) == 0 && winuser::IsWindow(hwnd) != 0 {
+        if !backend.destroy_window(hwnd as usize) && backend.is_window(hwnd as usize) {
             log_error();
         }
     }
@@ -400,9 +645,7 @@ pub fn destroy_hwnd(hwnd: windef::HWND, subclass_id: usize, handler: Option<unsa
 #[inline]
 pub fn draw(hwnd: windef::HWND, coords: Option<(i32, i32)>, (width, height): (u16, u16)) -> bool {
     if let Some((x, y)) = coords {
-        unsafe {
-            winuser::SetWindowPos(hwnd, ptr::null_mut(), x, y, width as i32, height as i32, 0);
-        }
+        backend::current().set_window_pos(hwnd as usize, x, y, width as i32, height as i32, 0);
         true
     } else {
         false
@@ -411,9 +654,13 @@ pub fn draw(hwnd: windef::HWND, coords: Option<(i32, i32)>, (width, height): (u1
 
 #[inline]
 pub unsafe fn window_rect(hwnd: windef::HWND) -> windef::RECT {
-    let mut rect: windef::RECT = mem::zeroed();
-    winuser::GetClientRect(hwnd, &mut rect);
-    rect
+    let rect = backend::current().client_rect(hwnd as usize);
+    windef::RECT {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+    }
 }
 
 #[inline]
@@ -421,13 +668,35 @@ pub(crate) unsafe fn cast_hwnd<'a, T>(hwnd: windef::HWND) -> Option<&'a mut T>
 where
     T: Sized,
 {
-    let hwnd_ptr = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let hwnd_ptr = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if hwnd_ptr == 0 {
         None
     } else {
         Some(mem::transmute(hwnd_ptr as *mut c_void))
     }
 }
+/// Sends a notification of a child control (`WM_COMMAND`, `WM_NOTIFY`, `WM_HSCROLL` or `WM_VSCROLL`) back to that control as `OCM_BASE + msg`.
+/// Returns `None` for other messages, for menu and accelerator commands, and for controls not created by us.
+pub unsafe fn reflect_notification(msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> Option<minwindef::LRESULT> {
+    if lparam == 0 {
+        return None;
+    }
+    let from = match msg {
+        winuser::WM_COMMAND | winuser::WM_HSCROLL | winuser::WM_VSCROLL => lparam as windef::HWND,
+        winuser::WM_NOTIFY => (&*(lparam as winuser::LPNMHDR)).hwndFrom,
+        _ => return None,
+    };
+    if member_base_from_hwnd(from).is_none() {
+        return None;
+    }
+    Some(backend::current().send_message(from as usize, OCM_BASE + msg, wparam, lparam))
+}
+/// Answers `WM_CTLCOLORSTATIC` for the view of a selected item of a list or a tree, so that it is drawn in the colors of the selection.
+pub unsafe fn selected_item_colors(hdc: windef::HDC) -> minwindef::LRESULT {
+    wingdi::SetTextColor(hdc, winuser::GetSysColor(winuser::COLOR_HIGHLIGHTTEXT));
+    wingdi::SetBkColor(hdc, winuser::GetSysColor(winuser::COLOR_HIGHLIGHT));
+    winuser::GetSysColorBrush(winuser::COLOR_HIGHLIGHT) as minwindef::LRESULT
+}
 #[inline]
 pub fn member_from_hwnd<'a, T>(hwnd: windef::HWND) -> Option<&'a mut T>
 where
@@ -444,19 +713,20 @@ pub fn make_menu(menu: windef::HMENU, mut items: Vec<types::MenuItem>, storage:
     let mut options = Vec::new();
     let mut help = Vec::new();
 
-    let append_item = |menu, label, action, storage: &mut Vec<callbacks::Action>| {
+    let backend = backend::current();
+    let append_item = |menu: windef::HMENU, label, action, storage: &mut Vec<callbacks::Action>| {
         let wlabel = str_to_wchar(label);
         let id = storage.len();
         storage.push(action);
-        unsafe { winuser::AppendMenuW(menu, winuser::MF_STRING, id, wlabel.as_ptr()); }
+        backend.append_menu(menu as usize, winuser::MF_STRING, id, Some(wlabel.as_slice()));
     };
-    let append_level = |menu, label, items, storage: &mut Vec<callbacks::Action>| {
+    let append_level = |menu: windef::HMENU, label, items, storage: &mut Vec<callbacks::Action>| {
         let wlabel = str_to_wchar(label);
-        let submenu = unsafe { winuser::CreateMenu() };
+        let submenu = backend.create_menu(false) as windef::HMENU;
         make_menu(submenu, items, storage);
-        unsafe { winuser::AppendMenuW(menu, winuser::MF_POPUP, submenu as usize, wlabel.as_ptr()); }
+        backend.append_menu(menu as usize, winuser::MF_POPUP, submenu as usize, Some(wlabel.as_slice()));
     };
-    let make_special = |menu, mut special: Vec<types::MenuItem>, storage: &mut Vec<callbacks::Action>| {
+    let make_special = |menu: windef::HMENU, mut special: Vec<types::MenuItem>, storage: &mut Vec<callbacks::Action>| {
         for item in special.drain(..) {
             match item {
                 types::MenuItem::Action(label, action, _) => {
@@ -466,7 +736,7 @@ pub fn make_menu(menu: windef::HMENU, mut items: Vec<types::MenuItem>, storage:
                     append_level(menu, label, items, storage);
                 }
                 types::MenuItem::Delimiter => {
-                    unsafe { winuser::AppendMenuW(menu, winuser::MF_SEPARATOR, 0, ptr::null_mut()); }
+                    backend.append_menu(menu as usize, winuser::MF_SEPARATOR, 0, None);
                 }
             }
         }
@@ -497,7 +767,7 @@ pub fn make_menu(menu: windef::HMENU, mut items: Vec<types::MenuItem>, storage:
                 }
             },
             types::MenuItem::Delimiter => {
-                unsafe { winuser::AppendMenuW(menu, winuser::MF_SEPARATOR, 0, ptr::null_mut()); }
+                backend.append_menu(menu as usize, winuser::MF_SEPARATOR, 0, None);
             }
         }
     }
@@ -0,0 +1,109 @@
+//! Scaling of pixel values between monitor DPIs, free of any window handle.
+//!
+//! Sizes in the code are given for the 96 DPI of a 100% monitor, and scaled to the DPI of the monitor the window is on right before use.
+
+/// DPI of a monitor at 100% scale.
+pub const BASE_DPI: u32 = 96;
+
+/// Scale of a monitor with `dpi`, `1.0` being 100%. A `dpi` of 0, which Windows returns when it does not know, counts as `BASE_DPI`.
+#[inline]
+pub fn scale_factor(dpi: u32) -> f32 {
+    normalize(dpi) as f32 / BASE_DPI as f32
+}
+
+/// Scales `value`, given at `BASE_DPI`, to `dpi`, rounding to the nearest pixel as `MulDiv` does.
+#[inline]
+pub fn scale(value: i32, dpi: u32) -> i32 {
+    rescale(value, BASE_DPI, dpi)
+}
+
+/// Turns `value`, given at `dpi`, back into `BASE_DPI` pixels.
+#[inline]
+pub fn unscale(value: i32, dpi: u32) -> i32 {
+    rescale(value, dpi, BASE_DPI)
+}
+
+/// Moves `value` from one DPI to another, e.g. when a window is dragged to a monitor with a different scale.
+pub fn rescale(value: i32, from: u32, to: u32) -> i32 {
+    let (from, to) = (normalize(from) as i64, normalize(to) as i64);
+    if from == to {
+        return value;
+    }
+    let scaled = value as i64 * to;
+    // rounding half away from zero, so that scaling is symmetric around 0
+    let rounded = if scaled < 0 { (scaled - from / 2) / from } else { (scaled + from / 2) / from };
+    rounded.max(i32::MIN as i64).min(i32::MAX as i64) as i32
+}
+
+/// Scales a size, saturating at the `u16` bounds the layout works with.
+#[inline]
+pub fn scale_size(value: u16, dpi: u32) -> u16 {
+    scale(value as i32, dpi).max(0).min(u16::MAX as i32) as u16
+}
+
+#[inline]
+fn normalize(dpi: u32) -> u32 {
+    if dpi == 0 {
+        BASE_DPI
+    } else {
+        dpi
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    #[test]
+    fn rescale_rounds_negatives_away_from_zero() {
+        assert_eq!(rescale(1, 96, 144), 2);
+        assert_eq!(rescale(-1, 96, 144), -2);
+        assert_eq!(rescale(-3, 96, 120), -4);
+        assert_eq!(rescale(-5, 144, 96), -3);
+        assert_eq!(rescale(-7, 96, 96), -7);
+    }
+
+    #[test]
+    fn rescale_saturates_at_the_i32_bounds() {
+        assert_eq!(rescale(i32::MAX, 96, 192), i32::MAX);
+        assert_eq!(rescale(i32::MIN, 96, 192), i32::MIN);
+    }
+
+    #[test]
+    fn zero_dpi_counts_as_base() {
+        assert_eq!(scale_factor(0), 1.0);
+        assert_eq!(scale(10, 0), 10);
+        assert_eq!(unscale(10, 0), 10);
+        assert_eq!(rescale(10, 0, 192), 20);
+        assert_eq!(rescale(20, 192, 0), 10);
+    }
+
+    #[test]
+    fn scale_size_saturates_at_u16_max() {
+        assert_eq!(scale_size(100, 144), 150);
+        assert_eq!(scale_size(40000, 192), u16::MAX);
+        assert_eq!(scale_size(u16::MAX, 192), u16::MAX);
+        assert_eq!(scale_size(u16::MAX, 96), u16::MAX);
+    }
+
+    proptest! {
+        #[test]
+        fn rescale_is_symmetric_around_zero(value in -100_000i32..100_000, from in 0u32..400, to in 0u32..400) {
+            prop_assert_eq!(rescale(-value, from, to), -rescale(value, from, to));
+        }
+
+        #[test]
+        fn unscale_undoes_scale_from_base_up(value in -100_000i32..100_000, dpi in BASE_DPI..480) {
+            prop_assert_eq!(unscale(scale(value, dpi), dpi), value);
+        }
+
+        #[test]
+        fn unscale_of_scale_is_off_by_rounding_below_base(value in -100_000i32..100_000, dpi in 48u32..BASE_DPI) {
+            let back = unscale(scale(value, dpi), dpi);
+            // rounding at `dpi` loses up to half a pixel there, which is up to one at the base
+            prop_assert!((back - value).abs() <= 1);
+        }
+    }
+}
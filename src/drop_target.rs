@@ -0,0 +1,263 @@
+//! Files and text dropped onto a control from other applications, Explorer included.
+//!
+//! The control window gets an OLE drop target for text and files, and accepts `WM_DROPFILES` too, for the drag sources
+//! not speaking OLE and the threads where OLE could not be initialized. OLE is initialized once for the thread by the application.
+
+use crate::backend;
+use crate::common::{self, *};
+
+use winapi::shared::guiddef;
+use winapi::shared::wtypes;
+use winapi::um::{ole2, objidl, oleidl, shellapi, unknwnbase};
+use winapi::Interface;
+
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
+use std::path::PathBuf;
+
+lazy_static! {
+    static ref SUBCLASS_NAME: Vec<u16> = OsStr::new("PlyguiWin32DropTarget").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+}
+
+static VTBL: oleidl::IDropTargetVtbl = oleidl::IDropTargetVtbl {
+    parent: unknwnbase::IUnknownVtbl {
+        QueryInterface: query_interface,
+        AddRef: add_ref,
+        Release: release,
+    },
+    DragEnter: drag_enter,
+    DragOver: drag_over,
+    DragLeave: drag_leave,
+    Drop: drop_data,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dropped {
+    Files(Vec<PathBuf>),
+    Text(String),
+}
+
+/// Receives what was dropped, and where, in the client coordinates of the control.
+pub type OnDrop = Box<dyn FnMut(&mut dyn controls::Control, Dropped, (i32, i32))>;
+
+/// COM object behind the `IDropTarget` registered for a control window, also kept as the data of its drop subclass.
+#[repr(C)]
+struct DropTarget {
+    vtbl: *const oleidl::IDropTargetVtbl,
+    refs: u32,
+    hwnd: windef::HWND,
+    /// Format of the data being dragged over, if it is one to accept.
+    format: Option<u32>,
+    /// Taken out while it runs, so it may set another callback for the control or remove it.
+    on_drop: Option<OnDrop>,
+    /// Whether `on_drop` is running.
+    delivering: bool,
+    /// Detaching was asked for while delivering, and is done once the callback returns.
+    detach_pending: bool,
+}
+
+/// Makes `control` accept dropped files and text, or stop accepting them with `None`.
+///
+/// The drop target lives as long as the native window of the control, so returns `false`, doing nothing,
+/// for a control not added to a container yet, and has to be set again once the control is added anew.
+pub fn set_on_drop(control: &mut dyn controls::Control, callback: Option<OnDrop>) -> bool {
+    let hwnd = unsafe { control.native_id() } as windef::HWND;
+    if hwnd.is_null() {
+        return false;
+    }
+    let subclass_id = common::subclass_id(SUBCLASS_NAME.as_ptr()) as usize;
+    unsafe {
+        let mut data = 0;
+        if 0 != commctrl::GetWindowSubclass(hwnd, Some(handler), subclass_id, &mut data) {
+            let target = data as *mut DropTarget;
+            match callback {
+                Some(callback) => {
+                    (*target).on_drop = Some(callback);
+                    (*target).detach_pending = false;
+                }
+                None => detach(target),
+            }
+            return true;
+        }
+        if let Some(callback) = callback {
+            attach(hwnd, callback);
+        }
+    }
+    true
+}
+
+unsafe fn attach(hwnd: windef::HWND, on_drop: OnDrop) {
+    let target = Box::into_raw(Box::new(DropTarget {
+        vtbl: &VTBL,
+        refs: 1,
+        hwnd,
+        format: None,
+        on_drop: Some(on_drop),
+        delivering: false,
+        detach_pending: false,
+    }));
+    commctrl::SetWindowSubclass(hwnd, Some(handler), common::subclass_id(SUBCLASS_NAME.as_ptr()) as usize, target as usize);
+    shellapi::DragAcceptFiles(hwnd, minwindef::TRUE);
+    // fails without OLE on the thread, leaving `WM_DROPFILES` only
+    ole2::RegisterDragDrop(hwnd, target as oleidl::LPDROPTARGET);
+}
+/// Unregisters the drop target and drops the reference of the window, once the callback has returned if it is running.
+unsafe fn detach(target: *mut DropTarget) {
+    if (*target).delivering {
+        (*target).detach_pending = true;
+        return;
+    }
+    let hwnd = (*target).hwnd;
+    ole2::RevokeDragDrop(hwnd);
+    shellapi::DragAcceptFiles(hwnd, minwindef::FALSE);
+    commctrl::RemoveWindowSubclass(hwnd, Some(handler), common::subclass_id(SUBCLASS_NAME.as_ptr()) as usize);
+    release(target as *mut unknwnbase::IUnknown);
+}
+unsafe fn deliver(target: *mut DropTarget, dropped: Dropped, pos: (i32, i32)) {
+    if (*target).delivering {
+        return;
+    }
+    let mut on_drop = match (*target).on_drop.take() {
+        Some(on_drop) => on_drop,
+        None => return,
+    };
+    // the reference keeps the target alive whatever the callback does with the control
+    add_ref(target as *mut unknwnbase::IUnknown);
+    (*target).delivering = true;
+    if let Some(control) = common::member_base_from_hwnd((*target).hwnd).and_then(|member| member.as_member_mut().is_control_mut()) {
+        (on_drop.as_mut())(control, dropped, pos);
+    }
+    (*target).delivering = false;
+    if (*target).on_drop.is_none() {
+        (*target).on_drop = Some(on_drop);
+    }
+    if mem::replace(&mut (*target).detach_pending, false) {
+        detach(target);
+    }
+    release(target as *mut unknwnbase::IUnknown);
+}
+
+unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    match msg {
+        winuser::WM_DROPFILES => {
+            let hdrop = wparam as shellapi::HDROP;
+            let files = files(hdrop);
+            let mut pt: windef::POINT = Default::default();
+            shellapi::DragQueryPoint(hdrop, &mut pt);
+            shellapi::DragFinish(hdrop);
+            deliver(param as *mut DropTarget, Dropped::Files(files), (pt.x, pt.y));
+            return 0;
+        }
+        winuser::WM_NCDESTROY => {
+            detach(param as *mut DropTarget);
+        }
+        _ => {}
+    }
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
+
+unsafe extern "system" fn query_interface(this: *mut unknwnbase::IUnknown, riid: guiddef::REFIID, object: *mut *mut c_void) -> winerror::HRESULT {
+    if guiddef::IsEqualGUID(&*riid, &unknwnbase::IUnknown::uuidof()) || guiddef::IsEqualGUID(&*riid, &oleidl::IDropTarget::uuidof()) {
+        add_ref(this);
+        *object = this as *mut c_void;
+        winerror::S_OK
+    } else {
+        *object = ptr::null_mut();
+        winerror::E_NOINTERFACE
+    }
+}
+unsafe extern "system" fn add_ref(this: *mut unknwnbase::IUnknown) -> minwindef::ULONG {
+    let target = this as *mut DropTarget;
+    (*target).refs += 1;
+    (*target).refs
+}
+unsafe extern "system" fn release(this: *mut unknwnbase::IUnknown) -> minwindef::ULONG {
+    let target = this as *mut DropTarget;
+    (*target).refs -= 1;
+    let refs = (*target).refs;
+    if refs == 0 {
+        mem::drop(Box::from_raw(target));
+    }
+    refs
+}
+unsafe extern "system" fn drag_enter(this: *mut oleidl::IDropTarget, data: *const objidl::IDataObject, _: minwindef::DWORD, _: *const windef::POINTL, effect: *mut minwindef::DWORD) -> winerror::HRESULT {
+    let target = &mut *(this as *mut DropTarget);
+    target.format = [winuser::CF_HDROP, winuser::CF_UNICODETEXT].iter().cloned().find(|format| winerror::S_OK == (*data).QueryGetData(&format_etc(*format)));
+    drag_over(this, 0, ptr::null(), effect)
+}
+unsafe extern "system" fn drag_over(this: *mut oleidl::IDropTarget, _: minwindef::DWORD, _: *const windef::POINTL, effect: *mut minwindef::DWORD) -> winerror::HRESULT {
+    let target = &*(this as *mut DropTarget);
+    *effect = if target.format.is_some() { *effect & oleidl::DROPEFFECT_COPY } else { oleidl::DROPEFFECT_NONE };
+    winerror::S_OK
+}
+unsafe extern "system" fn drag_leave(this: *mut oleidl::IDropTarget) -> winerror::HRESULT {
+    (*(this as *mut DropTarget)).format = None;
+    winerror::S_OK
+}
+unsafe extern "system" fn drop_data(this: *mut oleidl::IDropTarget, data: *const objidl::IDataObject, _: minwindef::DWORD, pt: *const windef::POINTL, effect: *mut minwindef::DWORD) -> winerror::HRESULT {
+    let target = this as *mut DropTarget;
+    let format = match (*target).format.take() {
+        Some(format) => format,
+        None => {
+            *effect = oleidl::DROPEFFECT_NONE;
+            return winerror::S_OK;
+        }
+    };
+    let mut medium: objidl::STGMEDIUM = mem::zeroed();
+    if winerror::S_OK != (*data).GetData(&format_etc(format), &mut medium) {
+        *effect = oleidl::DROPEFFECT_NONE;
+        return winerror::S_OK;
+    }
+    // winapi declares the union of STGMEDIUM as a pointer to it, so the handle itself is where the pointer is
+    let global = medium.u as minwindef::HGLOBAL;
+    let dropped = if format == winuser::CF_HDROP {
+        Some(Dropped::Files(files(global as shellapi::HDROP)))
+    } else {
+        let text = winbase::GlobalLock(global) as *const u16;
+        if text.is_null() {
+            None
+        } else {
+            let dropped = Dropped::Text(common::wchar_to_str(text));
+            winbase::GlobalUnlock(global);
+            Some(dropped)
+        }
+    };
+    if medium.pUnkForRelease.is_null() {
+        winbase::GlobalFree(global);
+    } else {
+        (*medium.pUnkForRelease).Release();
+    }
+    let mut pt = windef::POINT { x: (*pt).x, y: (*pt).y };
+    winuser::ScreenToClient((*target).hwnd, &mut pt);
+    match dropped {
+        Some(dropped) => {
+            *effect &= oleidl::DROPEFFECT_COPY;
+            deliver(target, dropped, (pt.x, pt.y));
+        }
+        None => *effect = oleidl::DROPEFFECT_NONE,
+    }
+    winerror::S_OK
+}
+
+fn format_etc(format: u32) -> objidl::FORMATETC {
+    objidl::FORMATETC {
+        cfFormat: format as wtypes::CLIPFORMAT,
+        ptd: ptr::null(),
+        dwAspect: wtypes::DVASPECT_CONTENT,
+        lindex: -1,
+        tymed: objidl::TYMED_HGLOBAL,
+    }
+}
+fn files(hdrop: shellapi::HDROP) -> Vec<PathBuf> {
+    let count = unsafe { shellapi::DragQueryFileW(hdrop, 0xFFFF_FFFF, ptr::null_mut(), 0) };
+    (0..count)
+        .map(|i| {
+            let len = unsafe { shellapi::DragQueryFileW(hdrop, i, ptr::null_mut(), 0) };
+            let mut buffer = vec![0u16; len as usize + 1];
+            unsafe {
+                shellapi::DragQueryFileW(hdrop, i, buffer.as_mut_ptr(), len + 1);
+            }
+            PathBuf::from(OsString::from_wide(&buffer[..len as usize]))
+        })
+        .collect()
+}
@@ -0,0 +1,135 @@
+//! Fonts of the controls, created once per description and DPI and shared by all the controls using them.
+
+use crate::common::{self, *};
+use crate::dpi;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const POINTS_PER_INCH: u32 = 72;
+
+lazy_static! {
+    /// Fonts in use, with the number of `FontRef`s to each.
+    static ref FONTS: Mutex<HashMap<(Font, u32), (Hfont, usize)>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontWeight {
+    Thin,
+    Light,
+    Normal,
+    SemiBold,
+    Bold,
+    Black,
+}
+impl FontWeight {
+    fn to_native(self) -> i32 {
+        match self {
+            FontWeight::Thin => wingdi::FW_THIN,
+            FontWeight::Light => wingdi::FW_LIGHT,
+            FontWeight::Normal => wingdi::FW_NORMAL,
+            FontWeight::SemiBold => wingdi::FW_SEMIBOLD,
+            FontWeight::Bold => wingdi::FW_BOLD,
+            FontWeight::Black => wingdi::FW_BLACK,
+        }
+    }
+}
+impl Default for FontWeight {
+    fn default() -> FontWeight {
+        FontWeight::Normal
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+}
+impl Default for FontStyle {
+    fn default() -> FontStyle {
+        FontStyle::Normal
+    }
+}
+
+/// Font of a control. The family and size left unset are the ones of the system message font, which the controls use by default.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Font {
+    pub family: Option<String>,
+    /// In points, so the same on every monitor whatever its DPI.
+    pub size: Option<u16>,
+    pub weight: FontWeight,
+    pub style: FontStyle,
+}
+impl Font {
+    pub fn new<S: Into<String>>(family: S, size: u16) -> Font {
+        Font {
+            family: Some(family.into()),
+            size: Some(size),
+            ..Default::default()
+        }
+    }
+}
+
+/// Keeps the font of `font` at `dpi` alive, the font being deleted once the last reference to it is dropped.
+pub(crate) struct FontRef {
+    key: (Font, u32),
+    hfont: windef::HFONT,
+}
+impl FontRef {
+    pub fn new(font: &Font, dpi: u32) -> FontRef {
+        let key = (font.clone(), dpi);
+        let mut fonts = FONTS.lock().unwrap();
+        let entry = fonts.entry(key.clone()).or_insert_with(|| (unsafe { create_font(font, dpi) }, 0));
+        entry.1 += 1;
+        FontRef { hfont: *entry.0.as_ref(), key }
+    }
+    #[inline]
+    pub fn hfont(&self) -> windef::HFONT {
+        self.hfont
+    }
+    #[inline]
+    pub fn dpi(&self) -> u32 {
+        self.key.1
+    }
+}
+impl Clone for FontRef {
+    fn clone(&self) -> FontRef {
+        FontRef::new(&self.key.0, self.key.1)
+    }
+}
+impl Drop for FontRef {
+    fn drop(&mut self) {
+        let mut fonts = FONTS.lock().unwrap();
+        let unused = match fonts.get_mut(&self.key) {
+            Some(entry) => {
+                entry.1 -= 1;
+                entry.1 == 0
+            }
+            None => false,
+        };
+        if unused {
+            fonts.remove(&self.key);
+            common::forget_text_sizes(self.hfont);
+        }
+    }
+}
+
+unsafe fn create_font(font: &Font, dpi: u32) -> Hfont {
+    let mut lf = common::message_logfont(dpi);
+    if let Some(ref family) = font.family {
+        let family = OsStr::new(family.as_str()).encode_wide().take(lf.lfFaceName.len() - 1).collect::<Vec<_>>();
+        lf.lfFaceName = mem::zeroed();
+        lf.lfFaceName[..family.len()].copy_from_slice(family.as_slice());
+    }
+    if let Some(size) = font.size {
+        // negative for the height of the characters rather than of the cells
+        lf.lfHeight = -dpi::rescale(size as i32, POINTS_PER_INCH, dpi);
+    }
+    lf.lfWeight = font.weight.to_native();
+    lf.lfItalic = (font.style == FontStyle::Italic) as u8;
+    let hfont = wingdi::CreateFontIndirectW(&lf);
+    if hfont.is_null() {
+        log_error();
+    }
+    hfont.into()
+}
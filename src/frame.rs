@@ -1,4 +1,7 @@
+use crate::backend;
 use crate::common::{self, *};
+use crate::font::Font;
+use crate::layout_engine::{self, Padding};
 
 lazy_static! {
     pub static ref WINDOW_CLASS_GBOX: Vec<u16> = OsStr::new("Button").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
@@ -27,6 +30,38 @@ impl<O: controls::Frame> NewFrameInner<O> for WindowsFrame {
         }
     }
 }
+impl WindowsFrame {
+    /// Font of the label.
+    pub fn font(&self) -> Option<&Font> {
+        self.base.font()
+    }
+    /// `None` brings back the default font.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.base.set_font(font);
+        if !self.hwnd_gbox.is_null() {
+            self.base.apply_font(self.hwnd_gbox);
+            backend::current().redraw(self.hwnd_gbox as usize, false);
+        }
+    }
+
+    /// Space around the child within the client window, which starts `label_padding` below the top of the group box,
+    /// at the middle of its caption, so the rest of the caption is still to be stepped over.
+    fn child_padding(&self) -> Padding {
+        let padding = common::default_padding(self.base.hwnd);
+        Padding {
+            top: padding + self.label_padding,
+            ..Padding::all(padding)
+        }
+    }
+    /// Space around the child within the whole frame, the `child_padding` below the client window offset.
+    fn frame_padding(&self) -> Padding {
+        let padding = self.child_padding();
+        Padding {
+            top: padding.top + self.label_padding,
+            ..padding
+        }
+    }
+}
 impl FrameInner for WindowsFrame {
     fn with_label<S: AsRef<str>>(label: S) -> Box<dyn controls::Frame> {
         let mut b: Box<mem::MaybeUninit<Frame>> = Box::new_uninit();
@@ -53,12 +88,13 @@ impl HasLayoutInner for WindowsFrame {
     fn on_layout_changed(&mut self, _base: &mut MemberBase) {
         let hwnd = self.base.hwnd;
         if !hwnd.is_null() {
-            unsafe { winuser::RedrawWindow(self.hwnd_gbox, ptr::null_mut(), ptr::null_mut(), winuser::RDW_INVALIDATE | winuser::RDW_UPDATENOW) };
+            backend::current().redraw(self.hwnd_gbox as usize, false);
             self.base.invalidate();
         }
     }
     fn layout_margin(&self, _member: &MemberBase) -> layout::BoundarySize {
-        layout::BoundarySize::Distinct(DEFAULT_PADDING, DEFAULT_PADDING + self.label_padding, DEFAULT_PADDING, DEFAULT_PADDING)
+        let padding = self.child_padding();
+        layout::BoundarySize::Distinct(padding.left, padding.top, padding.right, padding.bottom)
     }
 }
 
@@ -71,9 +107,7 @@ impl HasLabelInner for WindowsFrame {
         let hwnd = self.base.hwnd;
         if !hwnd.is_null() {
             let control_name = OsStr::new(&self.label).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-            unsafe {
-                winuser::SetWindowTextW(self.base.hwnd, control_name.as_ptr());
-            }
+            backend::current().set_window_text(self.base.hwnd as usize, control_name.as_slice());
         }
         self.on_layout_changed(base);
     }
@@ -91,15 +125,11 @@ impl SingleContainerInner for WindowsFrame {
 
         if self.child.is_some() {
             if !self.base.hwnd.is_null() {
-                let (w, h) = base.as_any().downcast_ref::<Frame>().unwrap().inner().base.measured;
+                let measured = base.as_any().downcast_ref::<Frame>().unwrap().inner().base.measured;
+                let padding = self.child_padding();
+                let (w, h) = self.frame_padding().inner(measured);
                 if let Some(new) = self.child.as_mut() {
-                    new.as_mut().on_added_to_container(
-                        self.base.as_outer_mut(),
-                        DEFAULT_PADDING,
-                        DEFAULT_PADDING + self.label_padding,
-                        utils::coord_to_size(w as i32 - DEFAULT_PADDING - DEFAULT_PADDING),
-                        utils::coord_to_size(h as i32 - DEFAULT_PADDING - DEFAULT_PADDING - self.label_padding),
-                    );
+                    new.as_mut().on_added_to_container(self.base.as_outer_mut(), padding.left, padding.top, w, h);
                 }
             }
         }
@@ -191,36 +221,28 @@ impl ControlInner for WindowsFrame {
                 selfptr,
                 None,
             );
-            let hwnd_gbox = winuser::CreateWindowExW(
+            let hwnd_gbox = common::create_part_hwnd(
+                (px, py, width as i32, height as i32),
+                self.base.hwnd,
                 0,
                 WINDOW_CLASS_GBOX.as_ptr(),
-                OsStr::new(self.label.as_str()).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>().as_ptr(),
-                winuser::BS_GROUPBOX | winuser::WS_CHILD | winuser::WS_VISIBLE,
-                px,
-                py,
-                width as i32,
-                height as i32,
-                self.base.hwnd,
-                ptr::null_mut(),
-                common::hinstance(),
+                common::str_to_wchar(self.label.as_str()).as_slice(),
+                winuser::BS_GROUPBOX,
                 ptr::null_mut(),
+                None,
             );
-            common::set_default_font(hwnd_gbox);
             (hwnd, hwnd_gbox, id)
         };
         self.base.hwnd = hwnd;
         self.hwnd_gbox = hwnd_gbox;
         self.base.subclass_id = id;
+        self.base.apply_font(hwnd_gbox);
         control.coords = Some((px, py));
+        let padding = self.child_padding();
+        let (w, h) = self.frame_padding().inner(control.measured);
         if let Some(ref mut child) = self.child {
             let self2: &mut Frame = unsafe { utils::base_to_impl_mut(member) };
-            child.on_added_to_container(
-                self2,
-                DEFAULT_PADDING,
-                DEFAULT_PADDING + self.label_padding,
-                utils::coord_to_size(control.measured.0 as i32 - DEFAULT_PADDING - DEFAULT_PADDING),
-                utils::coord_to_size(control.measured.1 as i32 - DEFAULT_PADDING - DEFAULT_PADDING - self.label_padding - self.label_padding),
-            );
+            child.on_added_to_container(self2, padding.left, padding.top, w, h);
         }
     }
     fn on_removed_from_container(&mut self, member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
@@ -274,10 +296,8 @@ impl HasVisibilityInner for WindowsFrame {
     fn on_visibility_set(&mut self, base: &mut MemberBase, visibility: types::Visibility) -> bool {
         let hwnd = self.base.hwnd;
         if !hwnd.is_null() {
-            unsafe {
-                winuser::ShowWindow(self.hwnd_gbox, if visibility == types::Visibility::Visible { winuser::SW_SHOW } else { winuser::SW_HIDE });
-                winuser::ShowWindow(self.base.hwnd, if visibility == types::Visibility::Visible { winuser::SW_SHOW } else { winuser::SW_HIDE });
-            }
+            backend::current().show_window(self.hwnd_gbox as usize, visibility == types::Visibility::Visible);
+            backend::current().show_window(self.base.hwnd as usize, visibility == types::Visibility::Visible);
             self.on_layout_changed(base);
             true
         } else {
@@ -290,62 +310,37 @@ impl MemberInner for WindowsFrame {}
 
 impl Drawable for WindowsFrame {
     fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        if self.base.font().is_some() && !self.hwnd_gbox.is_null() {
+            // the window may have moved to a monitor with another DPI
+            self.base.apply_font(self.hwnd_gbox);
+        }
         if let Some((x, y)) = control.coords {
-            unsafe {
-                winuser::SetWindowPos(self.base.hwnd, ptr::null_mut(), x, y + self.label_padding, control.measured.0 as i32, control.measured.1 as i32 - self.label_padding, 0);
-                winuser::SetWindowPos(self.hwnd_gbox, ptr::null_mut(), x, y, control.measured.0 as i32, control.measured.1 as i32, 0);
-            }
+            backend::current().set_window_pos(self.base.hwnd as usize, x, y + self.label_padding, control.measured.0 as i32, control.measured.1 as i32 - self.label_padding, 0);
+            backend::current().set_window_pos(self.hwnd_gbox as usize, x, y, control.measured.0 as i32, control.measured.1 as i32, 0);
         }
     }
     fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
-        use std::cmp::max;
-
         let old_size = control.measured;
-        self.label_padding = update_label_size(self.label.as_str(), self.base.hwnd);
-        let hp = DEFAULT_PADDING + DEFAULT_PADDING;
-        let vp = DEFAULT_PADDING + DEFAULT_PADDING + self.label_padding;
+        self.label_padding = update_label_size(self.label.as_str(), self.base.hwnd, self.base.hfont());
+        let padding = self.frame_padding();
         control.measured = match control.visibility {
             types::Visibility::Gone => (0, 0),
-            _ => {
-                let mut measured = false;
-                let w = match control.layout.width {
-                    layout::Size::Exact(w) => w,
-                    layout::Size::MatchParent => parent_width,
-                    layout::Size::WrapContent => {
-                        let mut w = 0;
-                        if let Some(ref mut child) = self.child {
-                            let (cw, _, _) = child.measure(max(0, parent_width as i32 - hp) as u16, max(0, parent_height as i32 - vp - self.label_padding) as u16);
-                            w += cw as i32;
-                            measured = true;
-                        }
-                        max(0, w as i32 + hp) as u16
-                    }
-                };
-                let h = match control.layout.height {
-                    layout::Size::Exact(h) => h,
-                    layout::Size::MatchParent => parent_height,
-                    layout::Size::WrapContent => {
-                        let mut h = 0;
-                        if let Some(ref mut child) = self.child {
-                            let ch = if measured {
-                                child.size().1
-                            } else {
-                                let (_, ch, _) = child.measure(max(0, parent_width as i32 - hp) as u16, max(0, parent_height as i32 - vp - self.label_padding) as u16);
-                                ch
-                            };
-                            h += ch as i32;
-                            h += self.label_padding;
-                        }
-                        max(0, h as i32 + vp) as u16
+            _ => layout_engine::measure_single(
+                (control.layout.width, control.layout.height),
+                (parent_width, parent_height),
+                padding,
+                self.child.as_mut().map(|child| {
+                    move |w, h| {
+                        let (cw, ch, _) = child.measure(w, h);
+                        (cw, ch)
                     }
-                };
-                (w, h)
-            }
+                }),
+            ),
         };
         (control.measured.0, control.measured.1, control.measured != old_size)
     }
     fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
-        unsafe { winuser::RedrawWindow(self.hwnd_gbox, ptr::null_mut(), ptr::null_mut(), winuser::RDW_INVALIDATE | winuser::RDW_UPDATENOW) };
+        backend::current().redraw(self.hwnd_gbox as usize, false);
         self.base.invalidate();
     }
 }
@@ -356,43 +351,31 @@ impl Spawnable for WindowsFrame {
     }
 }
 
-fn update_label_size(label: &str, hwnd: windef::HWND) -> i32 {
-    let mut label_size: windef::SIZE = unsafe { mem::zeroed() };
-    let label = OsStr::new(label).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-    unsafe {
-        wingdi::GetTextExtentPointW(winuser::GetDC(hwnd), label.as_ptr(), label.len() as i32, &mut label_size);
-    }
+fn update_label_size(label: &str, hwnd: windef::HWND, hfont: windef::HFONT) -> i32 {
+    let label_size = common::measure_text(hwnd, hfont, label, None);
     (label_size.cy) as i32 / 2
 }
 
 unsafe fn register_window_class() -> Vec<u16> {
     let class_name = OsStr::new("PlyguiWin32Frame").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-    let class = winuser::WNDCLASSEXW {
-        cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as minwindef::UINT,
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
         style: winuser::CS_DBLCLKS,
-        lpfnWndProc: Some(window_handler),
-        cbClsExtra: 0,
-        cbWndExtra: 0,
-        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
-        hIcon: winuser::LoadIconW(ptr::null_mut(), winuser::IDI_APPLICATION),
-        hCursor: winuser::LoadCursorW(ptr::null_mut(), winuser::IDC_ARROW),
-        hbrBackground: ptr::null_mut(),
-        lpszMenuName: ptr::null(),
-        lpszClassName: class_name.as_ptr(),
-        hIconSm: ptr::null_mut(),
-    };
-    winuser::RegisterClassExW(&class);
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
     class_name
 }
 
 unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
         if winuser::WM_CREATE == msg {
             let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
-            winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, cs.lpCreateParams as WinPtr);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
         }
-        return winuser::DefWindowProcW(hwnd, msg, wparam, lparam);
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
     }
     
     let frame: &mut Frame = mem::transmute(ww);
@@ -405,21 +388,28 @@ unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UIN
 
 unsafe extern "system" fn handler<T: controls::Frame>(this: &mut Frame, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
     match msg {
-        winuser::WM_SIZE => {
+        winuser::WM_SIZE | common::WM_UPDATE_INNER => {
             let width = lparam as u16;
             let height = (lparam >> 16) as u16;
-            let label_padding = this.inner().inner().inner().inner().inner().label_padding;
-            let hp = DEFAULT_PADDING + DEFAULT_PADDING;
-            let vp = DEFAULT_PADDING + DEFAULT_PADDING + label_padding;
-            
-            this.call_on_size::<T>(width, height);
+            // the size is the one of the client window, so is the padding
+            let padding = this.inner().inner().inner().inner().inner().child_padding();
+
+            if msg != common::WM_UPDATE_INNER {
+                this.call_on_size::<T>(width, height);
+            }
             
             if let Some(ref mut child) = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().child {
-                child.measure(utils::coord_to_size(width as i32 - hp), utils::coord_to_size(height as i32 - vp));
-                child.draw(Some((DEFAULT_PADDING, DEFAULT_PADDING + label_padding)));
+                let (w, h) = padding.inner((width, height));
+                child.measure(w, h);
+                child.draw(Some((padding.left, padding.top)));
             }
             return 0;
         }
+        winuser::WM_COMMAND | winuser::WM_NOTIFY | winuser::WM_HSCROLL | winuser::WM_VSCROLL => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
+        }
         winuser::WM_CTLCOLORLISTBOX | winuser::WM_CTLCOLORSTATIC => {
             let hdc = wparam as windef::HDC; 
             //wingdi::SetTextColor(hdc, wingdi::RGB(0,0,0));    
@@ -430,5 +420,5 @@ unsafe extern "system" fn handler<T: controls::Frame>(this: &mut Frame, msg: min
         _ => {}
     }
 
-    winuser::DefWindowProcW(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().native_id().into(), msg, wparam, lparam)
+    backend::current().def_window_proc(usize::from(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().native_id()), msg, wparam, lparam)
 }
@@ -1,3 +1,4 @@
+use crate::backend;
 use crate::common::{self, *};
 
 lazy_static! {
@@ -219,9 +220,9 @@ impl Spawnable for WindowsImage {
 }
 
 unsafe extern "system" fn handler<T: controls::Image>(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
-        winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, param as WinPtr);
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
     }
     match msg {
         winuser::WM_SIZE => {
@@ -288,7 +289,7 @@ unsafe extern "system" fn handler<T: controls::Image>(hwnd: windef::HWND, msg: m
         _ => {}
     }
 
-    commctrl::DefSubclassProc(hwnd, msg, wparam, lparam)
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
 }
 
 fn fmin(a: f32, b: f32) -> f32 {
@@ -0,0 +1,910 @@
+//! Size arithmetic of the containers, free of any window handle.
+//!
+//! Children are reached only through callbacks that get the child index together with the space offered to it, and return the size
+//! the child took. The containers pass closures calling `Control::measure`/`draw` on their children, anything else may pass plain numbers.
+
+use plygui_api::layout;
+
+use std::cmp::{max, min};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Padding {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Padding {
+    pub fn all(value: i32) -> Padding {
+        Padding {
+            left: value,
+            top: value,
+            right: value,
+            bottom: value,
+        }
+    }
+    #[inline]
+    pub fn horizontal(&self) -> i32 {
+        self.left + self.right
+    }
+    #[inline]
+    pub fn vertical(&self) -> i32 {
+        self.top + self.bottom
+    }
+    /// What is left of `(width, height)` inside the padding.
+    #[inline]
+    pub fn inner(&self, (width, height): (u16, u16)) -> (u16, u16) {
+        (to_size(width as i32 - self.horizontal()), to_size(height as i32 - self.vertical()))
+    }
+}
+
+/// Clamps a coordinate into a size, same as `utils::coord_to_size`, but saturating at the top as well.
+#[inline]
+pub fn to_size(value: i32) -> u16 {
+    max(0, min(value, u16::MAX as i32)) as u16
+}
+
+/// Resolves one dimension of a control, calling `content` only for `WrapContent`.
+#[inline]
+pub fn resolve<F: FnOnce() -> u16>(size: layout::Size, parent: u16, content: F) -> u16 {
+    match size {
+        layout::Size::Exact(value) => value,
+        layout::Size::MatchParent => parent,
+        layout::Size::WrapContent => content(),
+    }
+}
+
+/// Measures a container stacking children along `orientation`, one `params` entry per child.
+///
+/// Children are measured only if a dimension wraps the content, and then once each, the way `layout_linear` measures them:
+/// weighted children share what the others leave of the size the container is going to have, or of the parent along a
+/// dimension that wraps.
+pub fn measure_linear<F>(orientation: layout::Orientation, size: (layout::Size, layout::Size), parent: (u16, u16), padding: Padding, params: &[LinearParams], measure: F) -> (u16, u16)
+where
+    F: FnMut(usize, u16, u16) -> (u16, u16),
+{
+    let horizontal = orientation == layout::Orientation::Horizontal;
+    let exact = |size: layout::Size, parent: u16| if let layout::Size::Exact(value) = size { value } else { parent };
+    let mut measure = Some(measure);
+    let mut content = None;
+    let mut measure_content = || {
+        *content.get_or_insert_with(|| {
+            let available = padding.inner((exact(size.0, parent.0), exact(size.1, parent.1)));
+            let (extents, sizes) = measure_children(horizontal, available, params, measure.take().unwrap());
+            // children take their extents along the main axis, the largest of them all the space across it
+            let along: i32 = extents.iter().sum();
+            let across = stack(false, sizes.iter().map(|(w, h)| if horizontal { *h } else { *w }));
+            if horizontal {
+                (along, across)
+            } else {
+                (across, along)
+            }
+        })
+    };
+    let w = resolve(size.0, parent.0, || to_size(measure_content().0 + padding.horizontal()));
+    let h = resolve(size.1, parent.1, || to_size(measure_content().1 + padding.vertical()));
+    (w, h)
+}
+
+/// Places `count` children one after another inside `size`, each one offered what the previous ones left.
+///
+/// `place` gets the child index, its origin and the space available from there, and returns the size the child took.
+pub fn arrange_linear<F>(orientation: layout::Orientation, size: (u16, u16), padding: Padding, count: usize, mut place: F)
+where
+    F: FnMut(usize, (i32, i32), (u16, u16)) -> (u16, u16),
+{
+    let (mut x, mut y) = (padding.left, padding.top);
+    let (mut width, mut height) = (size.0 as i32 - padding.horizontal(), size.1 as i32 - padding.vertical());
+    for i in 0..count {
+        let (cw, ch) = place(i, (x, y), (to_size(width), to_size(height)));
+        match orientation {
+            layout::Orientation::Horizontal => {
+                x += cw as i32;
+                width -= cw as i32;
+            }
+            layout::Orientation::Vertical => {
+                y += ch as i32;
+                height -= ch as i32;
+            }
+        }
+    }
+}
+
+/// Where a child sits along the cross axis of its container, the one its siblings are not stacked along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Alignment {
+    Start,
+    Center,
+    End,
+    /// Stretches the child over the whole cross extent, whatever size it measures to.
+    Fill,
+}
+
+impl Default for Alignment {
+    fn default() -> Alignment {
+        Alignment::Start
+    }
+}
+
+/// Offset and size of a `size`-big child within `available` space. Only `Fill` changes the size.
+#[inline]
+pub fn align(alignment: Alignment, available: u16, size: u16) -> (i32, u16) {
+    let free = max(0, available as i32 - size as i32);
+    match alignment {
+        Alignment::Start => (0, size),
+        Alignment::Center => (free / 2, size),
+        Alignment::End => (free, size),
+        Alignment::Fill => (0, available),
+    }
+}
+
+/// How a child of a linear container shares the space with its siblings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearParams {
+    /// Share of the space left after all children without weight took theirs. `0.0` means the child takes what it measures to.
+    pub weight: f32,
+    pub alignment: Alignment,
+}
+
+impl Default for LinearParams {
+    fn default() -> LinearParams {
+        LinearParams { weight: 0.0, alignment: Alignment::Start }
+    }
+}
+
+/// Splits `leftover` proportionally to `weights`. Rounding remainders go to the last weighted entry, so the shares always add up
+/// to `leftover` unless no weight is positive.
+pub fn distribute(leftover: i32, weights: &[f32]) -> Vec<i32> {
+    let leftover = max(0, leftover);
+    let total: f32 = weights.iter().filter(|w| **w > 0.0).sum();
+    let mut shares: Vec<i32> = weights.iter().map(|w| if *w > 0.0 { (leftover as f32 * *w / total) as i32 } else { 0 }).collect();
+    if let Some(last) = weights.iter().rposition(|w| *w > 0.0) {
+        let given: i32 = shares.iter().sum();
+        shares[last] += leftover - given;
+    }
+    shares
+}
+
+/// Lays out children of a linear container of `size`, one `params` entry per child.
+///
+/// Children without weight are measured first, in order, each offered what the previous ones left along the main axis.
+/// Weighted children then share the remaining space according to `distribute` and are measured with their share.
+/// Returns the origin of each child and the size it is to be shown at: the one it measured to, except along the main axis for
+/// weighted children, which span their whole share, and across it for `Fill`.
+pub fn layout_linear<M>(orientation: layout::Orientation, size: (u16, u16), padding: Padding, params: &[LinearParams], measure: M) -> Vec<((i32, i32), (u16, u16))>
+where
+    M: FnMut(usize, u16, u16) -> (u16, u16),
+{
+    let horizontal = orientation == layout::Orientation::Horizontal;
+    let inner = padding.inner(size);
+    let cross = if horizontal { inner.1 } else { inner.0 };
+    let (extents, sizes) = measure_children(horizontal, inner, params, measure);
+
+    let mut cursor = if horizontal { padding.left } else { padding.top };
+    params
+        .iter()
+        .zip(extents.into_iter().zip(sizes))
+        .map(|(p, (extent, (w, h)))| {
+            let (offset, across) = align(p.alignment, cross, if horizontal { h } else { w });
+            let placed = if horizontal {
+                ((cursor, padding.top + offset), (to_size(extent), across))
+            } else {
+                ((padding.left + offset, cursor), (across, to_size(extent)))
+            };
+            cursor += extent;
+            placed
+        })
+        .collect()
+}
+
+/// Measures the children of a linear container with `inner` space inside its padding, as `layout_linear` describes.
+/// Returns the extent each child takes along the main axis, and the size it measured to.
+fn measure_children<M>(horizontal: bool, (inner_w, inner_h): (u16, u16), params: &[LinearParams], mut measure: M) -> (Vec<i32>, Vec<(u16, u16)>)
+where
+    M: FnMut(usize, u16, u16) -> (u16, u16),
+{
+    let (main, cross) = if horizontal { (inner_w, inner_h) } else { (inner_h, inner_w) };
+    let offer = |along: i32| if horizontal { (to_size(along), cross) } else { (cross, to_size(along)) };
+    let main_of = |(w, h): (u16, u16)| if horizontal { w } else { h };
+
+    let mut sizes = vec![(0u16, 0u16); params.len()];
+    let mut consumed = 0i32;
+    for (i, p) in params.iter().enumerate() {
+        if p.weight <= 0.0 {
+            let (w, h) = offer(main as i32 - consumed);
+            sizes[i] = measure(i, w, h);
+            consumed += main_of(sizes[i]) as i32;
+        }
+    }
+    let shares = distribute(main as i32 - consumed, params.iter().map(|p| p.weight).collect::<Vec<_>>().as_slice());
+    for (i, p) in params.iter().enumerate() {
+        if p.weight > 0.0 {
+            let (w, h) = offer(shares[i]);
+            sizes[i] = measure(i, w, h);
+        }
+    }
+    let extents = params.iter().enumerate().map(|(i, p)| if p.weight > 0.0 { shares[i] } else { main_of(sizes[i]) as i32 }).collect();
+    (extents, sizes)
+}
+
+/// Origin of the child that follows children of `preceding` sizes.
+pub fn linear_origin<I: IntoIterator<Item = (u16, u16)>>(orientation: layout::Orientation, padding: Padding, preceding: I) -> (i32, i32) {
+    let (x, y) = (padding.left, padding.top);
+    let (w, h) = preceding.into_iter().fold((0i32, 0i32), |(w, h), (cw, ch)| (w + cw as i32, h + ch as i32));
+    match orientation {
+        layout::Orientation::Horizontal => (x + w, y),
+        layout::Orientation::Vertical => (x, y + h),
+    }
+}
+
+/// Measures a container with at most one child.
+pub fn measure_single<F>(size: (layout::Size, layout::Size), parent: (u16, u16), padding: Padding, child: Option<F>) -> (u16, u16)
+where
+    F: FnMut(u16, u16) -> (u16, u16),
+{
+    let mut child = child;
+    let available = padding.inner(parent);
+    let mut measured = None;
+    let w = resolve(size.0, parent.0, || {
+        let child_size = child.as_mut().map(|measure| measure(available.0, available.1));
+        measured = child_size;
+        to_size(child_size.map(|s| s.0).unwrap_or(0) as i32 + padding.horizontal())
+    });
+    let h = resolve(size.1, parent.1, || {
+        let child_size = measured.or_else(|| child.as_mut().map(|measure| measure(available.0, available.1)));
+        to_size(child_size.map(|s| s.1).unwrap_or(0) as i32 + padding.vertical())
+    });
+    (w, h)
+}
+
+/// Splits `total` between two panes at `splitter` (`0.0..=1.0`), leaving `padding` at the outer sides and `bound` between the panes.
+pub fn split(total: u16, splitter: f32, padding: i32, bound: i32) -> (u16, u16) {
+    (
+        to_size((total as f32 * splitter) as i32 - padding - bound / 2),
+        to_size((total as f32 * (1.0 - splitter)) as i32 - padding - bound / 2),
+    )
+}
+
+/// Measures a two-pane container, each pane being limited to its share from `split` along `orientation`.
+pub fn measure_split<F>(orientation: layout::Orientation, size: (layout::Size, layout::Size), parent: (u16, u16), padding: i32, bound: i32, shares: (u16, u16), mut measure: F) -> (u16, u16)
+where
+    F: FnMut(usize, u16, u16) -> (u16, u16),
+{
+    let horizontal = orientation == layout::Orientation::Horizontal;
+    let hp = padding + padding + if horizontal { bound } else { 0 };
+    let vp = padding + padding + if horizontal { 0 } else { bound };
+    let measure_all = |measure: &mut F| {
+        [shares.0, shares.1]
+            .iter()
+            .enumerate()
+            .map(|(i, share)| if horizontal { measure(i, *share, to_size(parent.1 as i32 - vp)) } else { measure(i, to_size(parent.0 as i32 - hp), *share) })
+            .collect::<Vec<_>>()
+    };
+    let mut measured = None;
+    let w = resolve(size.0, parent.0, || {
+        let sizes = measure_all(&mut measure);
+        let w = stack(horizontal, sizes.iter().map(|s| s.0));
+        measured = Some(sizes);
+        to_size(w + hp)
+    });
+    let h = resolve(size.1, parent.1, || {
+        let sizes = measured.take().unwrap_or_else(|| measure_all(&mut measure));
+        to_size(stack(!horizontal, sizes.iter().map(|s| s.1)) + vp)
+    });
+    (w, h)
+}
+
+/// Origins of both panes, the first one being `first` big.
+pub fn split_origins(orientation: layout::Orientation, padding: i32, bound: i32, first: (u16, u16)) -> [(i32, i32); 2] {
+    match orientation {
+        layout::Orientation::Horizontal => [(padding, padding), (padding + first.0 as i32 + bound, padding)],
+        layout::Orientation::Vertical => [(padding, padding), (padding, padding + first.1 as i32 + bound)],
+    }
+}
+
+/// Sum of the sizes along the main axis, the largest one across it.
+fn stack<I: Iterator<Item = u16>>(along: bool, sizes: I) -> i32 {
+    if along {
+        sizes.map(|s| s as i32).sum()
+    } else {
+        sizes.map(|s| s as i32).max().unwrap_or(0)
+    }
+}
+
+/// Scroll state along one axis: `content` shown through a `view`, scrolled by `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Viewport {
+    pub content: u16,
+    pub view: u16,
+    pub offset: i32,
+}
+
+/// What a scroll bar, a key or the mouse wheel asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrollAction {
+    LineBack,
+    LineForward,
+    PageBack,
+    PageForward,
+    Start,
+    End,
+    To(i32),
+    By(i32),
+}
+
+impl Viewport {
+    #[inline]
+    pub fn max_offset(&self) -> i32 {
+        max(0, self.content as i32 - self.view as i32)
+    }
+    #[inline]
+    pub fn is_scrollable(&self) -> bool {
+        self.max_offset() > 0
+    }
+    #[inline]
+    pub fn clamp(&self, offset: i32) -> i32 {
+        max(0, min(offset, self.max_offset()))
+    }
+    /// Offset `action` leads to, `line` being the size of a line step. Pages keep one line of the previous page visible.
+    pub fn target(&self, action: ScrollAction, line: i32) -> i32 {
+        let page = max(line, self.view as i32 - line);
+        self.clamp(match action {
+            ScrollAction::LineBack => self.offset - line,
+            ScrollAction::LineForward => self.offset + line,
+            ScrollAction::PageBack => self.offset - page,
+            ScrollAction::PageForward => self.offset + page,
+            ScrollAction::Start => 0,
+            ScrollAction::End => self.max_offset(),
+            ScrollAction::To(offset) => offset,
+            ScrollAction::By(delta) => self.offset + delta,
+        })
+    }
+    /// Applies `action`, returning whether the offset changed.
+    pub fn scroll(&mut self, action: ScrollAction, line: i32) -> bool {
+        let offset = self.target(action, line);
+        let changed = offset != self.offset;
+        self.offset = offset;
+        changed
+    }
+    /// Updates the sizes, keeping the offset within the new bounds. Returns whether the offset changed.
+    pub fn resize(&mut self, content: u16, view: u16) -> bool {
+        self.content = content;
+        self.view = view;
+        let offset = self.clamp(self.offset);
+        let changed = offset != self.offset;
+        self.offset = offset;
+        changed
+    }
+}
+
+/// Whether the mouse wheel and the keys without a direction of their own, Page Up/Down and Home/End, scroll along the vertical
+/// axis, given the viewports of the axes a container scrolls along. They do unless only the horizontal one can be scrolled.
+pub fn scrolls_vertically(horizontal: Option<&Viewport>, vertical: Option<&Viewport>) -> bool {
+    match (horizontal, vertical) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(h), Some(v)) => v.is_scrollable() || !h.is_scrollable(),
+    }
+}
+
+/// Lines per notch the system reports when the wheel is set to scroll a page at a time.
+pub const WHEEL_PAGESCROLL: u32 = u32::MAX;
+
+/// Distance to scroll for a mouse wheel rotation of `delta` (`120` per notch, positive away from the user),
+/// `lines` being the system setting of lines per notch, or `WHEEL_PAGESCROLL` for a `page` per notch.
+pub fn wheel_distance(delta: i32, lines: u32, line: i32, page: i32) -> i32 {
+    const WHEEL_DELTA: i64 = 120;
+    let notch = if lines == WHEEL_PAGESCROLL { page as i64 } else { lines as i64 * line as i64 };
+    let distance = -(delta as i64) * notch / WHEEL_DELTA;
+    distance.max(i32::MIN as i64).min(i32::MAX as i64) as i32
+}
+
+/// Rows of the same `row` height shown through a `view` when the first visible one is `top`, widened by `overscan` rows at both ends.
+pub fn visible_rows(top: usize, view: u16, row: u16, count: usize, overscan: usize) -> Range<usize> {
+    if count == 0 || row == 0 {
+        return 0..0;
+    }
+    // the last row may be cut in half, and so may the first one
+    let shown = (view as usize).div_ceil(row as usize) + 1;
+    let start = min(count, top.saturating_sub(overscan));
+    let end = min(count, top + shown + overscan);
+    start..end
+}
+
+/// Sizing policy of a grid row or column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    /// As big as the largest child in it. `MatchParent` children take all they are offered, so they belong into other tracks.
+    Auto,
+    Fixed(u16),
+    /// A share of what is left after all other tracks, proportional to the value. Behaves as `Auto` when the grid wraps its content.
+    Star(f32),
+}
+
+impl Default for Track {
+    fn default() -> Track {
+        Track::Auto
+    }
+}
+
+/// Cell of a grid child. Spans of `0` are taken as `1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct GridCell {
+    pub row: usize,
+    pub column: usize,
+    pub row_span: usize,
+    pub column_span: usize,
+}
+
+impl GridCell {
+    pub fn at(row: usize, column: usize) -> GridCell {
+        GridCell { row, column, row_span: 1, column_span: 1 }
+    }
+    pub fn spanning(row: usize, column: usize, row_span: usize, column_span: usize) -> GridCell {
+        GridCell { row, column, row_span, column_span }
+    }
+    pub fn covers(&self, row: usize, column: usize) -> bool {
+        (self.row..self.row + max(1, self.row_span)).contains(&row) && (self.column..self.column + max(1, self.column_span)).contains(&column)
+    }
+}
+
+/// First cell, row by row, that none of `cells` covers in a grid of `columns` columns (at least one).
+pub fn next_free_cell(cells: &[GridCell], columns: usize) -> GridCell {
+    let columns = max(1, columns);
+    (0..)
+        .map(|index| GridCell::at(index / columns, index % columns))
+        .find(|free| !cells.iter().any(|cell| cell.covers(free.row, free.column)))
+        .unwrap()
+}
+
+/// Result of `layout_grid`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GridLayout {
+    pub columns: Vec<u16>,
+    pub rows: Vec<u16>,
+    /// Origin and size of each child's cell area, in child order.
+    pub cells: Vec<((i32, i32), (u16, u16))>,
+    /// Space taken by all tracks, spacing and padding.
+    pub extent: (u16, u16),
+}
+
+/// Sizes of `tracks` holding `items` of `(first track, span, desired size)`. Tracks referenced by items beyond `tracks` are `Auto`.
+///
+/// Fixed tracks come first, then `Auto` tracks grow to their single-track items, then multi-track items extend the last
+/// content-sized track they cover if they still do not fit. With `available`, star tracks share what is left of it,
+/// and items spanning a star track do not grow other tracks.
+pub fn solve_tracks(tracks: &[Track], available: Option<u16>, spacing: i32, items: &[(usize, usize, u16)]) -> Vec<u16> {
+    let count = max(tracks.len(), items.iter().map(|(start, span, _)| start + max(1, *span)).max().unwrap_or(0));
+    let track = |i: usize| tracks.get(i).cloned().unwrap_or_default();
+    let is_content = |t: Track| match t {
+        Track::Auto => true,
+        Track::Star(_) => available.is_none(),
+        Track::Fixed(_) => false,
+    };
+
+    let mut sizes: Vec<u16> = (0..count).map(|i| if let Track::Fixed(size) = track(i) { size } else { 0 }).collect();
+    for (start, _, desired) in items.iter().filter(|(_, span, _)| *span <= 1) {
+        if is_content(track(*start)) {
+            sizes[*start] = max(sizes[*start], *desired);
+        }
+    }
+    for (start, span, desired) in items.iter().filter(|(_, span, _)| *span > 1) {
+        let end = min(start + span, count);
+        if available.is_some() && (*start..end).any(|i| matches!(track(i), Track::Star(_))) {
+            // stretched star tracks take whatever is left, there is no point in growing anything else
+            continue;
+        }
+        let covered = sizes[*start..end].iter().map(|s| *s as i32).sum::<i32>() + spacing * (end - start - 1) as i32;
+        if (*desired as i32) > covered {
+            if let Some(last) = (*start..end).rev().find(|i| is_content(track(*i))) {
+                sizes[last] = to_size(sizes[last] as i32 + *desired as i32 - covered);
+            }
+        }
+    }
+    if let Some(available) = available {
+        let weights: Vec<f32> = (0..count).map(|i| if let Track::Star(weight) = track(i) { weight } else { 0.0 }).collect();
+        let used = (0..count).filter(|i| weights[*i] <= 0.0).map(|i| sizes[i] as i32).sum::<i32>() + spacing * max(0, count as i32 - 1);
+        let shares = distribute(available as i32 - used, weights.as_slice());
+        for i in (0..count).filter(|i| weights[*i] > 0.0) {
+            sizes[i] = to_size(shares[i]);
+        }
+    }
+    sizes
+}
+
+/// Size covered by `span` tracks from `start`, including the spacing between them.
+pub fn span_size(sizes: &[u16], start: usize, span: usize, spacing: i32) -> u16 {
+    let end = min(start + max(1, span), sizes.len());
+    if start >= end {
+        return 0;
+    }
+    to_size(sizes[start..end].iter().map(|s| *s as i32).sum::<i32>() + spacing * (end - start - 1) as i32)
+}
+
+/// Offset of each track from the first one.
+pub fn track_offsets(sizes: &[u16], spacing: i32) -> Vec<i32> {
+    let mut offset = 0;
+    sizes
+        .iter()
+        .map(|size| {
+            let current = offset;
+            offset += *size as i32 + spacing;
+            current
+        })
+        .collect()
+}
+
+/// Lays out a grid within `parent`. Along the axes where `stretch` is set, star tracks fill the parent, otherwise the grid takes its content size.
+///
+/// Each child is measured twice: with the whole inner area to learn its desired size, then with the area of its cell.
+pub fn layout_grid<M>(columns: &[Track], rows: &[Track], cells: &[GridCell], parent: (u16, u16), stretch: (bool, bool), padding: Padding, spacing: i32, mut measure: M) -> GridLayout
+where
+    M: FnMut(usize, u16, u16) -> (u16, u16),
+{
+    let (inner_w, inner_h) = padding.inner(parent);
+    let desired: Vec<(u16, u16)> = (0..cells.len()).map(|i| measure(i, inner_w, inner_h)).collect();
+
+    let column_items: Vec<(usize, usize, u16)> = cells.iter().zip(desired.iter()).map(|(c, d)| (c.column, c.column_span, d.0)).collect();
+    let row_items: Vec<(usize, usize, u16)> = cells.iter().zip(desired.iter()).map(|(c, d)| (c.row, c.row_span, d.1)).collect();
+    let column_sizes = solve_tracks(columns, if stretch.0 { Some(inner_w) } else { None }, spacing, column_items.as_slice());
+    let row_sizes = solve_tracks(rows, if stretch.1 { Some(inner_h) } else { None }, spacing, row_items.as_slice());
+    let column_offsets = track_offsets(column_sizes.as_slice(), spacing);
+    let row_offsets = track_offsets(row_sizes.as_slice(), spacing);
+
+    let cells = cells
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let size = (span_size(column_sizes.as_slice(), c.column, c.column_span, spacing), span_size(row_sizes.as_slice(), c.row, c.row_span, spacing));
+            measure(i, size.0, size.1);
+            ((padding.left + column_offsets[c.column], padding.top + row_offsets[c.row]), size)
+        })
+        .collect();
+    let extent = (
+        to_size(span_size(column_sizes.as_slice(), 0, column_sizes.len(), spacing) as i32 + padding.horizontal()),
+        to_size(span_size(row_sizes.as_slice(), 0, row_sizes.len(), spacing) as i32 + padding.vertical()),
+    );
+    GridLayout {
+        columns: column_sizes,
+        rows: row_sizes,
+        cells,
+        extent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    fn orientation() -> impl Strategy<Value = layout::Orientation> {
+        prop_oneof![Just(layout::Orientation::Horizontal), Just(layout::Orientation::Vertical)]
+    }
+    fn padding() -> impl Strategy<Value = Padding> {
+        (0..40i32, 0..40i32, 0..40i32, 0..40i32).prop_map(|(left, top, right, bottom)| Padding { left, top, right, bottom })
+    }
+    fn wants() -> impl Strategy<Value = Vec<(u16, u16)>> {
+        prop::collection::vec((0..1500u16, 0..1500u16), 0..8)
+    }
+    fn main_of(orientation: layout::Orientation, (w, h): (u16, u16)) -> u16 {
+        if orientation == layout::Orientation::Horizontal {
+            w
+        } else {
+            h
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn arranged_children_stay_within_the_parent(orientation in orientation(), size in (0..2000u16, 0..2000u16), padding in padding(), wants in wants()) {
+            let (inner_w, inner_h) = padding.inner(size);
+            arrange_linear(orientation, size, padding, wants.len(), |i, (x, y), (w, h)| {
+                let taken = (min(wants[i].0, w), min(wants[i].1, h));
+                assert!(x >= padding.left && y >= padding.top);
+                assert!(x + taken.0 as i32 <= padding.left + inner_w as i32);
+                assert!(y + taken.1 as i32 <= padding.top + inner_h as i32);
+                taken
+            });
+        }
+
+        #[test]
+        fn match_parent_child_takes_exactly_the_remainder(orientation in orientation(), size in (0..2000u16, 0..2000u16), padding in padding(), wants in wants()) {
+            let inner = padding.inner(size);
+            let consumed: i32 = wants.iter().map(|want| main_of(orientation, *want) as i32).sum();
+            let mut last = None;
+            arrange_linear(orientation, size, padding, wants.len() + 1, |i, origin, offered| {
+                if i < wants.len() {
+                    wants[i]
+                } else {
+                    last = Some((origin, offered));
+                    offered
+                }
+            });
+            let ((x, y), offered) = last.unwrap();
+            let start = if orientation == layout::Orientation::Horizontal { x - padding.left } else { y - padding.top };
+            prop_assert_eq!(start, consumed);
+            prop_assert_eq!(main_of(orientation, offered) as i32, max(0, main_of(orientation, inner) as i32 - consumed));
+        }
+
+        #[test]
+        fn weighted_children_share_exactly_the_remainder(
+            orientation in orientation(),
+            size in (0..2000u16, 0..2000u16),
+            padding in padding(),
+            children in prop::collection::vec(((0..600u16, 0..600u16), prop_oneof![Just(0.0f32), 0.1f32..5.0]), 1..8),
+        ) {
+            let params: Vec<LinearParams> = children.iter().map(|(_, weight)| LinearParams { weight: *weight, ..Default::default() }).collect();
+            let mut sizes = vec![(0u16, 0u16); children.len()];
+            layout_linear(orientation, size, padding, params.as_slice(), |i, w, h| {
+                // unweighted children wrap their content, weighted ones match the parent
+                sizes[i] = if params[i].weight > 0.0 { (w, h) } else { (min(children[i].0 .0, w), min(children[i].0 .1, h)) };
+                sizes[i]
+            });
+            let main = main_of(orientation, padding.inner(size)) as i32;
+            let fixed: i32 = (0..sizes.len()).filter(|i| params[*i].weight <= 0.0).map(|i| main_of(orientation, sizes[i]) as i32).sum();
+            let weighted: i32 = (0..sizes.len()).filter(|i| params[*i].weight > 0.0).map(|i| main_of(orientation, sizes[i]) as i32).sum();
+            if params.iter().any(|p| p.weight > 0.0) {
+                prop_assert_eq!(weighted, max(0, main - fixed));
+            } else {
+                prop_assert_eq!(weighted, 0);
+            }
+        }
+
+        #[test]
+        fn padding_is_subtracted_once(orientation in orientation(), parent in (0..2000u16, 0..2000u16), padding in padding(), wants in wants()) {
+            let mut offered = Vec::new();
+            let params = vec![LinearParams::default(); wants.len()];
+            let measured = measure_linear(orientation, (layout::Size::WrapContent, layout::Size::WrapContent), parent, padding, params.as_slice(), |i, w, h| {
+                offered.push((w, h));
+                (min(wants[i].0, w), min(wants[i].1, h))
+            });
+            // each child is measured once although both dimensions wrap
+            prop_assert_eq!(offered.len(), wants.len());
+            if let Some(first) = offered.first() {
+                prop_assert_eq!(*first, padding.inner(parent));
+            }
+            let taken: Vec<(u16, u16)> = wants.iter().zip(offered.iter()).map(|(want, offer)| (min(want.0, offer.0), min(want.1, offer.1))).collect();
+            let horizontal = orientation == layout::Orientation::Horizontal;
+            let content = (stack(horizontal, taken.iter().map(|s| s.0)), stack(!horizontal, taken.iter().map(|s| s.1)));
+            prop_assert_eq!(measured, (to_size(content.0 + padding.horizontal()), to_size(content.1 + padding.vertical())));
+        }
+
+        #[test]
+        fn distribute_adds_up_proportionally(leftover in -100..5000i32, weights in prop::collection::vec(prop_oneof![Just(0.0f32), Just(-1.0f32), 0.1f32..10.0], 0..10)) {
+            let shares = distribute(leftover, weights.as_slice());
+            prop_assert_eq!(shares.len(), weights.len());
+            let total: f32 = weights.iter().filter(|w| **w > 0.0).sum();
+            let weighted = weights.iter().filter(|w| **w > 0.0).count() as i32;
+            for (share, weight) in shares.iter().zip(weights.iter()) {
+                prop_assert!(*share >= 0);
+                if *weight <= 0.0 {
+                    prop_assert_eq!(*share, 0);
+                } else {
+                    // the rounding remainders, less than one pixel per weighted entry, all end up in one share
+                    let exact = max(0, leftover) as f32 * *weight / total;
+                    prop_assert!((*share as f32 - exact).abs() <= weighted as f32 + 0.01 * exact);
+                }
+            }
+            let sum: i32 = shares.iter().sum();
+            prop_assert_eq!(sum, if weighted > 0 { max(0, leftover) } else { 0 });
+        }
+    }
+
+    #[test]
+    fn align_offsets_and_fill_stretches() {
+        assert_eq!(align(Alignment::Start, 100, 30), (0, 30));
+        assert_eq!(align(Alignment::Center, 100, 30), (35, 30));
+        assert_eq!(align(Alignment::End, 100, 30), (70, 30));
+        assert_eq!(align(Alignment::Fill, 100, 30), (0, 100));
+        // too big to fit, so starting where it can be seen
+        assert_eq!(align(Alignment::End, 20, 30), (0, 30));
+        assert_eq!(align(Alignment::Center, 20, 30), (0, 30));
+    }
+
+    #[test]
+    fn distribute_splits_by_weight() {
+        assert_eq!(distribute(100, &[1.0, 2.0, 1.0]), vec![25, 50, 25]);
+        assert_eq!(distribute(10, &[1.0, 1.0, 1.0]), vec![3, 3, 4]);
+        assert_eq!(distribute(10, &[1.0, 0.0, 1.0, 0.0]), vec![5, 0, 5, 0]);
+        assert_eq!(distribute(10, &[0.0, 0.0]), vec![0, 0]);
+        assert_eq!(distribute(-10, &[1.0, 1.0]), vec![0, 0]);
+        assert_eq!(distribute(10, &[]), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn layout_linear_weights_and_alignments() {
+        let params = [
+            LinearParams { weight: 0.0, alignment: Alignment::Center },
+            LinearParams { weight: 1.0, alignment: Alignment::Fill },
+            LinearParams { weight: 3.0, alignment: Alignment::End },
+        ];
+        // every child wraps a 10x10 content
+        let mut offered = vec![(0, 0); params.len()];
+        let placed = layout_linear(layout::Orientation::Horizontal, (110, 60), Padding::all(5), &params, |i, w, h| {
+            offered[i] = (w, h);
+            (min(10, w), min(10, h))
+        });
+        assert_eq!(offered, vec![(100, 50), (22, 50), (68, 50)]);
+        assert_eq!(placed, vec![((5, 25), (10, 10)), ((15, 5), (22, 50)), ((37, 45), (68, 10))]);
+    }
+
+    #[test]
+    fn measure_linear_honors_weights() {
+        let params = [LinearParams::default(), LinearParams { weight: 1.0, ..Default::default() }, LinearParams { weight: 1.0, ..Default::default() }];
+        let mut offered = vec![(0, 0); params.len()];
+        let measured = measure_linear(layout::Orientation::Vertical, (layout::Size::Exact(80), layout::Size::WrapContent), (200, 100), Padding::all(0), &params, |i, w, h| {
+            offered[i] = (w, h);
+            (w, min(h, 20))
+        });
+        // the weighted children share the parent height the first one left, as that is the height the layout wraps into
+        assert_eq!(offered, vec![(80, 100), (80, 40), (80, 40)]);
+        assert_eq!(measured, (80, 100));
+
+        // nothing is measured when the size does not depend on the content
+        let measured = measure_linear(layout::Orientation::Vertical, (layout::Size::Exact(80), layout::Size::MatchParent), (200, 100), Padding::all(0), &params, |_, _, _| unreachable!());
+        assert_eq!(measured, (80, 100));
+    }
+
+    #[test]
+    fn later_match_parent_children_get_nothing() {
+        // the first match-parent child takes everything, leaving nothing to the ones after it
+        let mut taken = Vec::new();
+        arrange_linear(layout::Orientation::Horizontal, (100, 50), Padding::all(5), 3, |_, origin, offered| {
+            taken.push((origin, offered));
+            offered
+        });
+        assert_eq!(taken, vec![((5, 5), (90, 40)), ((95, 5), (0, 40)), ((95, 5), (0, 40))]);
+    }
+
+    #[test]
+    fn viewport_bounds_and_clamp() {
+        let viewport = Viewport { content: 300, view: 100, offset: 0 };
+        assert_eq!(viewport.max_offset(), 200);
+        assert!(viewport.is_scrollable());
+        assert_eq!(viewport.clamp(-5), 0);
+        assert_eq!(viewport.clamp(150), 150);
+        assert_eq!(viewport.clamp(500), 200);
+
+        // content smaller than the view cannot be scrolled at all
+        let viewport = Viewport { content: 50, view: 100, offset: 0 };
+        assert_eq!(viewport.max_offset(), 0);
+        assert!(!viewport.is_scrollable());
+        assert_eq!(viewport.clamp(10), 0);
+    }
+
+    #[test]
+    fn viewport_targets() {
+        let viewport = Viewport { content: 300, view: 100, offset: 100 };
+        assert_eq!(viewport.target(ScrollAction::LineBack, 20), 80);
+        assert_eq!(viewport.target(ScrollAction::LineForward, 20), 120);
+        // a page keeps one line of the previous one visible
+        assert_eq!(viewport.target(ScrollAction::PageBack, 20), 20);
+        assert_eq!(viewport.target(ScrollAction::PageForward, 20), 180);
+        assert_eq!(viewport.target(ScrollAction::Start, 20), 0);
+        assert_eq!(viewport.target(ScrollAction::End, 20), 200);
+        assert_eq!(viewport.target(ScrollAction::To(500), 20), 200);
+        assert_eq!(viewport.target(ScrollAction::By(-150), 20), 0);
+
+        // a view smaller than a line still pages by at least a line
+        let viewport = Viewport { content: 300, view: 10, offset: 0 };
+        assert_eq!(viewport.target(ScrollAction::PageForward, 20), 20);
+    }
+
+    #[test]
+    fn viewport_scroll_and_resize() {
+        let mut viewport = Viewport { content: 300, view: 100, offset: 0 };
+        assert!(!viewport.scroll(ScrollAction::LineBack, 20));
+        assert!(viewport.scroll(ScrollAction::End, 20));
+        assert_eq!(viewport.offset, 200);
+        assert!(!viewport.scroll(ScrollAction::LineForward, 20));
+
+        // growing the view pulls the offset back within the new bounds
+        assert!(viewport.resize(300, 200));
+        assert_eq!(viewport.offset, 100);
+        assert!(!viewport.resize(400, 200));
+        assert_eq!(viewport.offset, 100);
+        assert!(viewport.resize(100, 200));
+        assert_eq!(viewport.offset, 0);
+    }
+
+    #[test]
+    fn wheel_distance_follows_the_rotation() {
+        // rotating away from the user scrolls back
+        assert_eq!(wheel_distance(120, 3, 16, 200), -48);
+        assert_eq!(wheel_distance(-120, 3, 16, 200), 48);
+        assert_eq!(wheel_distance(60, 3, 16, 200), -24);
+        assert_eq!(wheel_distance(240, 1, 10, 200), -20);
+        assert_eq!(wheel_distance(120, 0, 16, 200), 0);
+    }
+
+    #[test]
+    fn wheel_distance_by_pages_and_without_overflow() {
+        assert_eq!(wheel_distance(120, WHEEL_PAGESCROLL, 16, 200), -200);
+        assert_eq!(wheel_distance(-240, WHEEL_PAGESCROLL, 16, 200), 400);
+        assert_eq!(wheel_distance(-60, WHEEL_PAGESCROLL, 16, 200), 100);
+        // a huge setting saturates rather than wrapping around
+        assert_eq!(wheel_distance(-120 * 100, u32::MAX - 1, 16, 200), i32::MAX);
+        assert_eq!(wheel_distance(120 * 100, u32::MAX - 1, 16, 200), i32::MIN);
+    }
+
+    #[test]
+    fn scrolls_vertically_unless_only_horizontal_can() {
+        let scrollable = Viewport { content: 300, view: 100, offset: 0 };
+        let fixed = Viewport { content: 50, view: 100, offset: 0 };
+        assert!(scrolls_vertically(None, Some(&fixed)));
+        assert!(!scrolls_vertically(Some(&fixed), None));
+        assert!(!scrolls_vertically(None, None));
+        assert!(scrolls_vertically(Some(&scrollable), Some(&scrollable)));
+        assert!(scrolls_vertically(Some(&fixed), Some(&fixed)));
+        assert!(!scrolls_vertically(Some(&scrollable), Some(&fixed)));
+    }
+
+    #[test]
+    fn visible_rows_with_overscan() {
+        // nothing to show without rows, or without a height to place them at
+        assert_eq!(visible_rows(0, 100, 20, 0, 2), 0..0);
+        assert_eq!(visible_rows(5, 100, 0, 50, 2), 0..0);
+        // a view of five rows shows parts of six once scrolled by less than a row
+        assert_eq!(visible_rows(10, 100, 20, 50, 0), 10..16);
+        assert_eq!(visible_rows(10, 90, 20, 50, 0), 10..16);
+        assert_eq!(visible_rows(10, 80, 20, 50, 0), 10..15);
+        assert_eq!(visible_rows(10, 100, 20, 50, 2), 8..18);
+        // the overscan stops at both ends of the list
+        assert_eq!(visible_rows(0, 100, 20, 50, 2), 0..8);
+        assert_eq!(visible_rows(1, 100, 20, 50, 2), 0..9);
+        assert_eq!(visible_rows(45, 100, 20, 50, 2), 43..50);
+        assert_eq!(visible_rows(60, 100, 20, 50, 2), 50..50);
+        // fewer rows than fit into the view
+        assert_eq!(visible_rows(0, 100, 20, 3, 2), 0..3);
+    }
+
+    #[test]
+    fn next_free_cell_skips_placed_and_spanned_cells() {
+        assert_eq!(next_free_cell(&[], 3), GridCell::at(0, 0));
+        assert_eq!(next_free_cell(&[GridCell::at(0, 0), GridCell::at(0, 1)], 3), GridCell::at(0, 2));
+        assert_eq!(next_free_cell(&[GridCell::at(0, 0), GridCell::at(0, 1), GridCell::at(0, 2)], 3), GridCell::at(1, 0));
+        // an explicit placement further on is stepped over, the gap before it is filled
+        assert_eq!(next_free_cell(&[GridCell::at(0, 1)], 3), GridCell::at(0, 0));
+        assert_eq!(next_free_cell(&[GridCell::at(0, 1), GridCell::at(0, 0)], 3), GridCell::at(0, 2));
+        // spans cover every cell they reach
+        assert_eq!(next_free_cell(&[GridCell::spanning(0, 0, 2, 2)], 3), GridCell::at(0, 2));
+        assert_eq!(next_free_cell(&[GridCell::spanning(0, 0, 2, 2), GridCell::at(0, 2)], 3), GridCell::at(1, 2));
+        assert_eq!(next_free_cell(&[GridCell::spanning(0, 0, 2, 2), GridCell::at(0, 2), GridCell::at(1, 2)], 3), GridCell::at(2, 0));
+        // zero spans count as one, and no columns as a single one
+        assert_eq!(next_free_cell(&[GridCell::spanning(0, 0, 0, 0)], 0), GridCell::at(1, 0));
+    }
+
+    #[test]
+    fn solve_tracks_fixed_auto_and_star() {
+        let tracks = [Track::Fixed(50), Track::Auto, Track::Star(1.0), Track::Star(3.0)];
+        let items = [(0, 1, 80), (1, 1, 30), (1, 1, 40), (2, 1, 10), (3, 1, 10)];
+        // fixed tracks ignore their content, auto ones take the largest item, stars share the rest
+        assert_eq!(solve_tracks(&tracks, Some(200), 0, &items), vec![50, 40, 27, 83]);
+        // the spacing between the tracks is taken from what the stars share
+        assert_eq!(solve_tracks(&tracks, Some(200), 10, &items), vec![50, 40, 20, 60]);
+        // without an available size the stars wrap their content like auto tracks
+        assert_eq!(solve_tracks(&tracks, None, 10, &items), vec![50, 40, 10, 10]);
+        // stars never go below zero when the rest takes it all
+        assert_eq!(solve_tracks(&tracks, Some(50), 0, &items), vec![50, 40, 0, 0]);
+    }
+
+    #[test]
+    fn solve_tracks_spans() {
+        // a spanning item grows the last content-sized track it covers, counting the spacing it spans
+        let tracks = [Track::Auto, Track::Auto, Track::Fixed(20)];
+        assert_eq!(solve_tracks(&tracks, None, 5, &[(0, 1, 30), (0, 3, 100)]), vec![30, 40, 20]);
+        // it does not grow anything when it fits already
+        assert_eq!(solve_tracks(&tracks, None, 5, &[(0, 1, 30), (1, 1, 50), (0, 2, 60)]), vec![30, 50, 20]);
+        // over fixed tracks only, it is cut
+        assert_eq!(solve_tracks(&[Track::Fixed(10), Track::Fixed(10)], None, 0, &[(0, 2, 100)]), vec![10, 10]);
+        // spanning a stretched star track leaves the growing to the star
+        let tracks = [Track::Auto, Track::Star(1.0)];
+        assert_eq!(solve_tracks(&tracks, Some(100), 0, &[(0, 1, 20), (0, 2, 90)]), vec![20, 80]);
+        assert_eq!(solve_tracks(&tracks, None, 0, &[(0, 1, 20), (0, 2, 90)]), vec![20, 70]);
+        // items beyond the tracks get auto tracks of their own
+        assert_eq!(solve_tracks(&[], None, 0, &[(1, 2, 30), (0, 1, 5)]), vec![5, 0, 30]);
+    }
+}
@@ -0,0 +1,375 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::layout_engine::{self, GridCell, Padding, Track};
+
+lazy_static! {
+    pub static ref WINDOW_CLASS: Vec<u16> = unsafe { register_window_class() };
+}
+
+pub type GridLayout = AMember<AControl<AContainer<AMultiContainer<WindowsGridLayout>>>>;
+
+#[repr(C)]
+pub struct WindowsGridLayout {
+    base: WindowsControlBase<GridLayout>,
+    columns: Vec<Track>,
+    rows: Vec<Track>,
+    /// At `dpi::BASE_DPI`.
+    spacing: i32,
+    children: Vec<Box<dyn controls::Control>>,
+    cells: Vec<GridCell>,
+}
+impl WindowsGridLayout {
+    fn with_uninit(_: &mut mem::MaybeUninit<GridLayout>, columns: Vec<Track>, rows: Vec<Track>) -> Self {
+        WindowsGridLayout {
+            base: WindowsControlBase::with_wndproc(Some(handler)),
+            columns,
+            rows,
+            spacing: DEFAULT_PADDING,
+            children: Vec::new(),
+            cells: Vec::new(),
+        }
+    }
+    /// Children placed beyond the given tracks get `Track::Auto` ones.
+    pub fn with_tracks(columns: Vec<Track>, rows: Vec<Track>) -> Box<GridLayout> {
+        let mut b: Box<mem::MaybeUninit<GridLayout>> = Box::new_uninit();
+        let ab = AMember::with_inner(AControl::with_inner(AContainer::with_inner(AMultiContainer::with_inner(Self::with_uninit(b.as_mut(), columns, rows)))));
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+    pub fn columns(&self) -> &[Track] {
+        self.columns.as_slice()
+    }
+    pub fn set_columns(&mut self, columns: Vec<Track>) {
+        self.columns = columns;
+        self.update_children_layout();
+    }
+    pub fn rows(&self) -> &[Track] {
+        self.rows.as_slice()
+    }
+    pub fn set_rows(&mut self, rows: Vec<Track>) {
+        self.rows = rows;
+        self.update_children_layout();
+    }
+    /// Space between the tracks, at `dpi::BASE_DPI`, scaled to the monitor the grid is on.
+    pub fn spacing(&self) -> i32 {
+        self.spacing
+    }
+    pub fn set_spacing(&mut self, spacing: i32) {
+        self.spacing = cmp::max(0, spacing);
+        self.update_children_layout();
+    }
+    pub fn cell(&self, index: usize) -> Option<GridCell> {
+        self.cells.get(index).cloned()
+    }
+    pub fn set_cell(&mut self, index: usize, cell: GridCell) {
+        if let Some(c) = self.cells.get_mut(index) {
+            *c = cell;
+            self.update_children_layout();
+        }
+    }
+
+    fn next_cell(&self) -> GridCell {
+        layout_engine::next_free_cell(self.cells.as_slice(), self.columns.len())
+    }
+    fn grid(&mut self, parent: (u16, u16), stretch: (bool, bool)) -> layout_engine::GridLayout {
+        let children = &mut self.children;
+        layout_engine::layout_grid(
+            self.columns.as_slice(),
+            self.rows.as_slice(),
+            self.cells.as_slice(),
+            parent,
+            stretch,
+            Padding::all(common::default_padding(self.base.hwnd)),
+            common::scaled(self.base.hwnd, self.spacing),
+            |i, w, h| {
+                let (cw, ch, _) = children[i].measure(w, h);
+                (cw, ch)
+            },
+        )
+    }
+    fn update_children_layout(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let (width, height) = common::size_hwnd(self.base.hwnd);
+            let packed = ((height as i32) << 16) + width as i32;
+            backend::current().send_message(self.base.hwnd as usize, common::WM_UPDATE_INNER, 0, packed as isize);
+        }
+    }
+    fn layout_children(&mut self, width: u16, height: u16) {
+        let grid = self.grid((width, height), (true, true));
+        for (child, (origin, _)) in self.children.iter_mut().zip(grid.cells.into_iter()) {
+            child.draw(Some(origin));
+        }
+    }
+}
+
+impl MultiContainerInner for WindowsGridLayout {
+    fn len(&self) -> usize {
+        self.children.len()
+    }
+    fn set_child_to(&mut self, base: &mut MemberBase, index: usize, child: Box<dyn controls::Control>) -> Option<Box<dyn controls::Control>> {
+        let cell = self.cells.get(index).cloned().unwrap_or_else(|| self.next_cell());
+        let old = self.remove_child_from(base, index);
+
+        self.children.insert(index, child);
+        self.cells.insert(index, cell);
+        if !self.base.hwnd.is_null() {
+            let (w, h) = base.as_any().downcast_ref::<GridLayout>().unwrap().inner().base.measured;
+            let padding = common::default_padding(self.base.hwnd);
+            self.children.get_mut(index).unwrap().on_added_to_container(self.base.as_outer_mut(), padding, padding, w, h);
+            self.layout_children(w, h);
+        }
+        old
+    }
+    fn remove_child_from(&mut self, _base: &mut MemberBase, index: usize) -> Option<Box<dyn controls::Control>> {
+        if index < self.children.len() {
+            let mut old = self.children.remove(index);
+            self.cells.remove(index);
+            if !self.base.hwnd.is_null() {
+                old.on_removed_from_container(self.base.as_outer_mut());
+                self.update_children_layout();
+            }
+            Some(old)
+        } else {
+            None
+        }
+    }
+    fn child_at(&self, index: usize) -> Option<&dyn controls::Control> {
+        self.children.get(index).map(|c| c.as_ref())
+    }
+    fn child_at_mut(&mut self, index: usize) -> Option<&mut dyn controls::Control> {
+        if let Some(c) = self.children.get_mut(index) {
+            Some(c.as_mut())
+        } else {
+            None
+        }
+    }
+}
+impl ControlInner for WindowsGridLayout {
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, px: i32, py: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (width, height, _) = self.measure(member, control, pw, ph);
+        let (hwnd, id) = unsafe {
+            common::create_control_hwnd(
+                px as i32,
+                py as i32,
+                width as i32,
+                height as i32,
+                parent.native_id() as windef::HWND,
+                winuser::WS_EX_CONTROLPARENT | winuser::WS_CLIPCHILDREN,
+                WINDOW_CLASS.as_ptr(),
+                "",
+                0,
+                selfptr,
+                None,
+            )
+        };
+        self.base.hwnd = hwnd;
+        self.base.subclass_id = id;
+        control.coords = Some((px as i32, py as i32));
+        let grid = self.grid((width, height), (true, true));
+        for (child, (origin, size)) in self.children.iter_mut().zip(grid.cells.into_iter()) {
+            let self2: &mut GridLayout = unsafe { utils::base_to_impl_mut(member) };
+            child.on_added_to_container(self2, origin.0, origin.1, size.0, size.1);
+        }
+    }
+    fn on_removed_from_container(&mut self, member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        for ref mut child in self.children.as_mut_slice() {
+            let self2: &mut GridLayout = unsafe { utils::base_to_impl_mut(member) };
+            child.on_removed_from_container(self2);
+        }
+        self.base.destroy_control_hwnd();
+    }
+}
+impl HasLayoutInner for WindowsGridLayout {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        let hwnd = self.base.hwnd;
+        if !hwnd.is_null() {
+            self.base.invalidate();
+        }
+    }
+    fn layout_margin(&self, _member: &MemberBase) -> layout::BoundarySize {
+        layout::BoundarySize::AllTheSame(common::default_padding(self.base.hwnd))
+    }
+}
+impl HasNativeIdInner for WindowsGridLayout {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+impl MemberInner for WindowsGridLayout {}
+
+impl HasSizeInner for WindowsGridLayout {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsGridLayout {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl ContainerInner for WindowsGridLayout {
+    fn find_control_mut<'a>(&'a mut self, arg: types::FindBy<'a>) -> Option<&'a mut dyn controls::Control> {
+        for child in self.children.as_mut_slice() {
+            match arg {
+                types::FindBy::Id(ref id) => {
+                    if child.as_member_mut().id() == *id {
+                        return Some(child.as_mut());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member_mut().tag() {
+                        if tag == mytag {
+                            return Some(child.as_mut());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container_mut() {
+                let ret = c.find_control_mut(arg.clone());
+                if ret.is_none() {
+                    continue;
+                }
+                return ret;
+            }
+        }
+        None
+    }
+    fn find_control<'a>(&'a self, arg: types::FindBy<'a>) -> Option<&'a dyn controls::Control> {
+        for child in self.children.as_slice() {
+            match arg {
+                types::FindBy::Id(ref id) => {
+                    if child.as_member().id() == *id {
+                        return Some(child.as_ref());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member().tag() {
+                        if tag == mytag {
+                            return Some(child.as_ref());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container() {
+                let ret = c.find_control(arg.clone());
+                if ret.is_none() {
+                    continue;
+                }
+                return ret;
+            }
+        }
+        None
+    }
+}
+
+impl Drawable for WindowsGridLayout {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let wraps = |size: layout::Size| if let layout::Size::WrapContent = size { true } else { false };
+                let (wrap_w, wrap_h) = (wraps(control.layout.width), wraps(control.layout.height));
+                // an axis that does not wrap stretches its star tracks to the size it resolves to
+                let bounds = (
+                    layout_engine::resolve(control.layout.width, parent_width, || parent_width),
+                    layout_engine::resolve(control.layout.height, parent_height, || parent_height),
+                );
+                let extent = if wrap_w || wrap_h { self.grid(bounds, (!wrap_w, !wrap_h)).extent } else { (0, 0) };
+                (
+                    layout_engine::resolve(control.layout.width, parent_width, || extent.0),
+                    layout_engine::resolve(control.layout.height, parent_height, || extent.1),
+                )
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+impl Spawnable for WindowsGridLayout {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_tracks(vec![Track::Auto, Track::Star(1.0)], Vec::new()).into_control()
+    }
+}
+
+unsafe fn register_window_class() -> Vec<u16> {
+    let class_name = OsStr::new("PlyguiWin32GridLayout").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
+        style: winuser::CS_DBLCLKS,
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
+    class_name
+}
+
+unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        if winuser::WM_CREATE == msg {
+            let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
+        }
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
+    }
+
+    let grid: &mut GridLayout = mem::transmute(ww);
+    let grid2: &mut GridLayout = mem::transmute(ww);
+    grid.inner().inner().inner().inner().base.proc_handler.as_proc().unwrap()(grid2, msg, wparam, lparam)
+}
+
+unsafe extern "system" fn handler(this: &mut GridLayout, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    match msg {
+        winuser::WM_SIZE | common::WM_UPDATE_INNER => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+            this.inner_mut().inner_mut().inner_mut().inner_mut().layout_children(width, height);
+
+            if msg != common::WM_UPDATE_INNER {
+                this.call_on_size::<GridLayout>(width, height);
+            }
+            return 0;
+        }
+        winuser::WM_COMMAND | winuser::WM_NOTIFY | winuser::WM_HSCROLL | winuser::WM_VSCROLL => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
+        }
+        winuser::WM_CTLCOLORLISTBOX | winuser::WM_CTLCOLORSTATIC => {
+            let hdc = wparam as windef::HDC;
+            wingdi::SetBkMode(hdc, wingdi::TRANSPARENT as i32);
+
+            return wingdi::GetStockObject(wingdi::NULL_BRUSH as i32) as isize;
+        }
+        _ => {}
+    }
+
+    backend::current().def_window_proc(usize::from(this.inner_mut().inner_mut().inner_mut().inner_mut().native_id()), msg, wparam, lparam)
+}
@@ -1,4 +1,6 @@
+use crate::backend;
 use crate::common::{self, *};
+use crate::layout_engine::{self, Alignment, LinearParams, Padding};
 
 lazy_static! {
     pub static ref WINDOW_CLASS: Vec<u16> = unsafe { register_window_class() };
@@ -12,6 +14,76 @@ pub struct WindowsLinearLayout {
     base: WindowsControlBase<LinearLayout>,
     orientation: layout::Orientation,
     children: Vec<Box<dyn controls::Control>>,
+    params: Vec<LinearParams>,
+}
+impl WindowsLinearLayout {
+    pub fn child_weight(&self, index: usize) -> Option<f32> {
+        self.params.get(index).map(|p| p.weight)
+    }
+    pub fn set_child_weight(&mut self, index: usize, weight: f32) {
+        if let Some(p) = self.params.get_mut(index) {
+            p.weight = weight.max(0.0);
+            self.update_children_layout();
+        }
+    }
+    pub fn child_alignment(&self, index: usize) -> Option<Alignment> {
+        self.params.get(index).map(|p| p.alignment)
+    }
+    pub fn set_child_alignment(&mut self, index: usize, alignment: Alignment) {
+        if let Some(p) = self.params.get_mut(index) {
+            p.alignment = alignment;
+            self.update_children_layout();
+        }
+    }
+    fn update_children_layout(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let (width, height) = common::size_hwnd(self.base.hwnd);
+            let packed = ((height as i32) << 16) + width as i32;
+            backend::current().send_message(self.base.hwnd as usize, common::WM_UPDATE_INNER, 0, packed as isize);
+        }
+    }
+    fn layout_children(&mut self, width: u16, height: u16) {
+        let padding = Padding::all(common::default_padding(self.base.hwnd));
+        let children = &mut self.children;
+        let placed = layout_engine::layout_linear(self.orientation, (width, height), padding, self.params.as_slice(), |i, w, h| {
+            let (cw, ch, _) = children[i].measure(w, h);
+            (cw, ch)
+        });
+        for (child, (origin, size)) in self.children.iter_mut().zip(placed.into_iter()) {
+            if child.size() != size {
+                // stretched by its weight or by `Alignment::Fill` beyond what it measured to
+                child.measure(size.0, size.1);
+            }
+            child.draw(Some(origin));
+        }
+    }
+}
+/// Weight and alignment of the children of a linear layout, as `LinearLayout::with_orientation` returns it. Indexes past the
+/// last child are ignored, and so is a layout of another backend.
+pub trait LinearLayoutParams {
+    fn child_weight(&self, index: usize) -> Option<f32>;
+    /// Share of the space left by the children without weight, see `LinearParams::weight`.
+    fn set_child_weight(&mut self, index: usize, weight: f32);
+    fn child_alignment(&self, index: usize) -> Option<Alignment>;
+    fn set_child_alignment(&mut self, index: usize, alignment: Alignment);
+}
+impl LinearLayoutParams for dyn controls::LinearLayout {
+    fn child_weight(&self, index: usize) -> Option<f32> {
+        self.as_any().downcast_ref::<LinearLayout>().and_then(|ll| ll.inner().inner().inner().inner().inner().child_weight(index))
+    }
+    fn set_child_weight(&mut self, index: usize, weight: f32) {
+        if let Some(ll) = self.as_any_mut().downcast_mut::<LinearLayout>() {
+            ll.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().set_child_weight(index, weight);
+        }
+    }
+    fn child_alignment(&self, index: usize) -> Option<Alignment> {
+        self.as_any().downcast_ref::<LinearLayout>().and_then(|ll| ll.inner().inner().inner().inner().inner().child_alignment(index))
+    }
+    fn set_child_alignment(&mut self, index: usize, alignment: Alignment) {
+        if let Some(ll) = self.as_any_mut().downcast_mut::<LinearLayout>() {
+            ll.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().set_child_alignment(index, alignment);
+        }
+    }
 }
 impl<O: controls::LinearLayout> NewLinearLayoutInner<O> for WindowsLinearLayout {
     fn with_uninit_params(_: &mut mem::MaybeUninit<O>, orientation: layout::Orientation) -> Self {
@@ -19,6 +91,7 @@ impl<O: controls::LinearLayout> NewLinearLayoutInner<O> for WindowsLinearLayout
             base: WindowsControlBase::with_wndproc(Some(handler::<O>)),
             orientation: orientation,
             children: Vec::new(),
+            params: Vec::new(),
         }
     }
 }
@@ -62,45 +135,25 @@ impl MultiContainerInner for WindowsLinearLayout {
         let old = self.remove_child_from(base, index);
 
         self.children.insert(index, child);
+        self.params.insert(index, LinearParams::default());
         if !self.base.hwnd.is_null() {
             let (w, h) = base.as_any().downcast_ref::<LinearLayout>().unwrap().inner().base.measured;
-            let (cw, ch) = {
-                let mut w = 0;
-                let mut h = 0;
-                for i in 0..index {
-                    let (cw, ch) = self.children[i].size();
-                    w += cw;
-                    h += ch;
-                }
-                (w as i32, h as i32)
-            };
-            match self.orientation {
-                layout::Orientation::Vertical => {
-                    self.children.get_mut(index).unwrap().on_added_to_container(
-                        self.base.as_outer_mut(),
-                        DEFAULT_PADDING,
-                        ch + DEFAULT_PADDING,
-                        utils::coord_to_size(w as i32 - DEFAULT_PADDING - DEFAULT_PADDING),
-                        utils::coord_to_size(h as i32 - ch - DEFAULT_PADDING - DEFAULT_PADDING),
-                    );
-                },
-                layout::Orientation::Horizontal => {
-                    self.children.get_mut(index).unwrap().on_added_to_container(
-                        self.base.as_outer_mut(),
-                        cw + DEFAULT_PADDING,
-                        DEFAULT_PADDING,
-                        utils::coord_to_size(w as i32 - cw - DEFAULT_PADDING - DEFAULT_PADDING),
-                        utils::coord_to_size(h as i32 - DEFAULT_PADDING - DEFAULT_PADDING),
-                    );                
-                }
-            }
-            
+            let padding = common::default_padding(self.base.hwnd);
+            let (x, y) = layout_engine::linear_origin(self.orientation, Padding::all(padding), self.children[..index].iter().map(|child| child.size()));
+            self.children.get_mut(index).unwrap().on_added_to_container(
+                self.base.as_outer_mut(),
+                x,
+                y,
+                utils::coord_to_size(w as i32 - x - padding),
+                utils::coord_to_size(h as i32 - y - padding),
+            );
         }
         old
     }
     fn remove_child_from(&mut self, _base: &mut MemberBase, index: usize) -> Option<Box<dyn controls::Control>> {
         if index < self.children.len() {
             let mut old = self.children.remove(index);
+            self.params.remove(index);
             if !self.base.hwnd.is_null() {
                 old.on_removed_from_container(self.base.as_outer_mut());
                 self.base.invalidate();
@@ -137,9 +190,9 @@ impl ControlInner for WindowsLinearLayout {
     }
     fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, px: i32, py: i32, pw: u16, ph: u16) {
         let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
         let (width, height, _) = self.measure(member, control, pw, ph);
         let (hwnd, id) = unsafe {
-            self.base.hwnd = parent.native_container_id() as windef::HWND; // required for measure, as we don't have own hwnd yet
             common::create_control_hwnd(
                 px as i32,
                 py as i32,
@@ -157,30 +210,15 @@ impl ControlInner for WindowsLinearLayout {
         self.base.hwnd = hwnd;
         self.base.subclass_id = id;
         control.coords = Some((px as i32, py as i32));
-        let mut x = DEFAULT_PADDING;
-        let mut y = DEFAULT_PADDING;
-        let mut pw = pw as i32 - DEFAULT_PADDING - DEFAULT_PADDING;
-        let mut ph = ph as i32 - DEFAULT_PADDING - DEFAULT_PADDING;
-        for ref mut child in self.children.as_mut_slice() {
+        let padding = Padding::all(common::default_padding(self.base.hwnd));
+        let children = &mut self.children;
+        let placed = layout_engine::layout_linear(self.orientation, (width, height), padding, self.params.as_slice(), |i, w, h| {
+            let (cw, ch, _) = children[i].measure(w, h);
+            (cw, ch)
+        });
+        for (child, ((x, y), (w, h))) in self.children.iter_mut().zip(placed.into_iter()) {
             let self2: &mut LinearLayout = unsafe { utils::base_to_impl_mut(member) };
-            child.on_added_to_container(
-                self2,
-                x,
-                y,
-                utils::coord_to_size(pw) as u16,
-                utils::coord_to_size(ph) as u16,
-            );
-            let (xx, yy) = child.size();
-            match self.orientation {
-                layout::Orientation::Horizontal => {
-                    x += xx as i32;
-                    pw -= xx as i32;
-                },
-                layout::Orientation::Vertical => {
-                    y += yy as i32;
-                    ph -= yy as i32;
-                },
-            }
+            child.on_added_to_container(self2, x, y, w, h);
         }
     }
     fn on_removed_from_container(&mut self, member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
@@ -207,7 +245,7 @@ impl HasLayoutInner for WindowsLinearLayout {
         }
     }
     fn layout_margin(&self, _member: &MemberBase) -> layout::BoundarySize {
-        layout::BoundarySize::AllTheSame(DEFAULT_PADDING)
+        layout::BoundarySize::AllTheSame(common::default_padding(self.base.hwnd))
     }
 }
 impl HasNativeIdInner for WindowsLinearLayout {
@@ -292,61 +330,23 @@ impl Drawable for WindowsLinearLayout {
         self.base.draw(control.coords, control.measured);
     }
     fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
-        use std::cmp::max;
-
-        let orientation = self.orientation;
         let old_size = control.measured;
-        let hp = DEFAULT_PADDING + DEFAULT_PADDING;
-        let vp = DEFAULT_PADDING + DEFAULT_PADDING;
         control.measured = match control.visibility {
             types::Visibility::Gone => (0, 0),
             _ => {
-                let mut measured = false;
-                let w = match control.layout.width {
-                    layout::Size::Exact(w) => w,
-                    layout::Size::MatchParent => parent_width,
-                    layout::Size::WrapContent => {
-                        let mut w = 0;
-                        for child in self.children.as_mut_slice() {
-                            let (cw, _, _) = child.measure(max(0, parent_width as i32 - w as i32 - hp) as u16, max(0, parent_height as i32 - vp) as u16);
-                            match orientation {
-                                layout::Orientation::Horizontal => {
-                                    w += cw;
-                                }
-                                layout::Orientation::Vertical => {
-                                    w = max(w, cw);
-                                }
-                            }
-                        }
-                        measured = true;
-                        max(0, w as i32 + hp) as u16
-                    }
-                };
-                let h = match control.layout.height {
-                    layout::Size::Exact(h) => h,
-                    layout::Size::MatchParent => parent_height,
-                    layout::Size::WrapContent => {
-                        let mut h = 0;
-                        for child in self.children.as_mut_slice() {
-                            let ch = if measured {
-                                child.size().1
-                            } else {
-                                let (_, ch, _) = child.measure(max(0, parent_width as i32 - hp) as u16, max(0, parent_height as i32 - h as i32 - vp) as u16);
-                                ch
-                            };
-                            match orientation {
-                                layout::Orientation::Horizontal => {
-                                    h = max(h, ch);
-                                }
-                                layout::Orientation::Vertical => {
-                                    h += ch;
-                                }
-                            }
-                        }
-                        max(0, h as i32 + vp) as u16
-                    }
-                };
-                (w, h)
+                let padding = Padding::all(common::default_padding(self.base.hwnd));
+                let children = &mut self.children;
+                layout_engine::measure_linear(
+                    self.orientation,
+                    (control.layout.width, control.layout.height),
+                    (parent_width, parent_height),
+                    padding,
+                    self.params.as_slice(),
+                    |i, w, h| {
+                        let (cw, ch, _) = children[i].measure(w, h);
+                        (cw, ch)
+                    },
+                )
             }
         };
         (control.measured.0, control.measured.1, control.measured != old_size)
@@ -363,30 +363,24 @@ impl Spawnable for WindowsLinearLayout {
 
 unsafe fn register_window_class() -> Vec<u16> {
     let class_name = OsStr::new("PlyguiWin32LinearLayout").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-    let class = winuser::WNDCLASSW {
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
         style: winuser::CS_DBLCLKS,
-        lpfnWndProc: Some(window_handler),
-        cbClsExtra: 0,
-        cbWndExtra: 0,
-        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
-        hIcon: winuser::LoadIconW(ptr::null_mut(), winuser::IDI_APPLICATION),
-        hCursor: winuser::LoadCursorW(ptr::null_mut(), winuser::IDC_ARROW),
-        hbrBackground: ptr::null_mut(),
-        lpszMenuName: ptr::null(),
-        lpszClassName: class_name.as_ptr(),
-    };
-    winuser::RegisterClassW(&class);
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
     class_name
 }
 
 unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
         if winuser::WM_CREATE == msg {
             let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
-            winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, cs.lpCreateParams as WinPtr);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
         }
-        return winuser::DefWindowProcW(hwnd, msg, wparam, lparam);
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
     }
     
     let frame: &mut LinearLayout = mem::transmute(ww);
@@ -396,34 +390,21 @@ unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UIN
 
 unsafe extern "system" fn handler<T: controls::LinearLayout>(this: &mut LinearLayout, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
     match msg {
-        winuser::WM_SIZE => {
-            let mut width = lparam as u16;
-            let mut height = (lparam >> 16) as u16;
-            let o = this.inner().inner().inner().inner().inner().orientation;
-            let hp = DEFAULT_PADDING + DEFAULT_PADDING;
-            let vp = DEFAULT_PADDING + DEFAULT_PADDING;
+        winuser::WM_SIZE | common::WM_UPDATE_INNER => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+            this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().layout_children(width, height);
 
-            let mut x = 0;
-            let mut y = 0;
-            for child in this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().children.as_mut_slice() {
-                let (cw, ch, _) = child.measure(cmp::max(0, width as i32 - hp) as u16, cmp::max(0, height as i32 - vp) as u16);
-                child.draw(Some((x + DEFAULT_PADDING, y + DEFAULT_PADDING)));
-                match o {
-                    layout::Orientation::Horizontal if width >= cw => {
-                        x += cw as i32;
-                        width -= cw;
-                    }
-                    layout::Orientation::Vertical if height >= ch => {
-                        y += ch as i32;
-                        height -= ch;
-                    }
-                    _ => {}
-                }
+            if msg != common::WM_UPDATE_INNER {
+                this.call_on_size::<T>(width, height);
             }
-
-            this.call_on_size::<T>(width, height);
             return 0;
         }
+        winuser::WM_COMMAND | winuser::WM_NOTIFY | winuser::WM_HSCROLL | winuser::WM_VSCROLL => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
+        }
         winuser::WM_CTLCOLORLISTBOX | winuser::WM_CTLCOLORSTATIC => {
             let hdc = wparam as windef::HDC; 
             //wingdi::SetTextColor(hdc, wingdi::RGB(0,0,0));    
@@ -435,5 +416,5 @@ unsafe extern "system" fn handler<T: controls::LinearLayout>(this: &mut LinearLa
         _ => {}
     }
 
-    winuser::DefWindowProcW(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().native_id().into(), msg, wparam, lparam)
+    backend::current().def_window_proc(usize::from(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().native_id()), msg, wparam, lparam)
 }
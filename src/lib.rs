@@ -1,4 +1,3 @@
-#![cfg(target_os = "windows")]
 #![windows_subsystem = "windows"]
 #![cfg_attr(feature = "cargo-clippy", allow(cast_lossless))]
 #![cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
@@ -14,22 +13,102 @@ extern crate lazy_static;
 #[macro_use]
 extern crate plygui_api;
 
+// built on every host, see `backend` for where the seam to the OS is
+pub mod backend;
+pub mod dpi;
+pub mod layout_engine;
+pub mod navigation;
+pub mod numeric;
+pub mod selection;
+
+#[cfg(target_os = "windows")]
 #[macro_use]
 pub mod common;
 
+#[cfg(target_os = "windows")]
 mod application;
+#[cfg(target_os = "windows")]
 mod button;
+#[cfg(target_os = "windows")]
 mod frame;
+#[cfg(target_os = "windows")]
 mod image;
+#[cfg(target_os = "windows")]
 mod layout_linear;
+#[cfg(target_os = "windows")]
 mod message;
+#[cfg(target_os = "windows")]
 mod splitted;
+#[cfg(target_os = "windows")]
 mod text;
+#[cfg(target_os = "windows")]
 mod tray;
+#[cfg(target_os = "windows")]
 mod window;
+#[cfg(target_os = "windows")]
 mod progress_bar;
+#[cfg(target_os = "windows")]
 mod list;
+#[cfg(target_os = "windows")]
 mod tree;
+#[cfg(target_os = "windows")]
+mod scroll;
+#[cfg(target_os = "windows")]
+mod layout_grid;
+#[cfg(target_os = "windows")]
+mod text_input;
+#[cfg(target_os = "windows")]
+mod combo_box;
+#[cfg(target_os = "windows")]
+mod tabs;
+#[cfg(target_os = "windows")]
+mod slider;
+#[cfg(target_os = "windows")]
+mod number_input;
+#[cfg(target_os = "windows")]
+mod table;
+#[cfg(target_os = "windows")]
+mod drop_target;
+#[cfg(target_os = "windows")]
+mod font;
+#[cfg(target_os = "windows")]
+mod link;
 
+#[cfg(target_os = "windows")]
 default_markup_register_members!();
+#[cfg(target_os = "windows")]
 default_pub_use!();
+
+#[cfg(target_os = "windows")]
+pub use crate::button::{CheckBox, CheckState, OnToggle, RadioButton, RadioGroup};
+#[cfg(target_os = "windows")]
+pub use crate::layout_linear::LinearLayoutParams;
+pub use crate::layout_engine::Alignment;
+#[cfg(target_os = "windows")]
+pub use crate::scroll::{Scroll, WindowsScroll};
+#[cfg(target_os = "windows")]
+pub use crate::layout_grid::{GridLayout, WindowsGridLayout};
+#[cfg(target_os = "windows")]
+pub use crate::text_input::{OnTextChange, OnTextSubmit, TextInput, WindowsTextInput};
+#[cfg(target_os = "windows")]
+pub use crate::combo_box::{ComboBox, OnComboTextChange, OnSelect, WindowsComboBox};
+#[cfg(target_os = "windows")]
+pub use crate::tabs::{OnTabChange, OnTabClose, Tabs, WindowsTabs};
+#[cfg(target_os = "windows")]
+pub use crate::slider::{OnValueChange, Slider, WindowsSlider};
+#[cfg(target_os = "windows")]
+pub use crate::number_input::{NumberInput, OnNumberChange, WindowsNumberInput};
+#[cfg(target_os = "windows")]
+pub use crate::table::{OnRowCheck, OnTableSelectionChange, SortOrder, Table, TableAdapter, TableColumn, WindowsTable};
+#[cfg(target_os = "windows")]
+pub use crate::list::{OnItemBind, OnListItemMove, OnListSelectionChange};
+#[cfg(target_os = "windows")]
+pub use crate::tree::{OnTreeItemMove, OnTreeLabelEdit, OnTreeSelectionChange};
+#[cfg(target_os = "windows")]
+pub use crate::drop_target::{set_on_drop, Dropped, OnDrop};
+#[cfg(target_os = "windows")]
+pub use crate::font::{Font, FontStyle, FontWeight};
+#[cfg(target_os = "windows")]
+pub use crate::text::{HorizontalAlignment, TextOverflow, VerticalAlignment};
+#[cfg(target_os = "windows")]
+pub use crate::link::{Link, OnLinkClick, WindowsLink};
@@ -0,0 +1,259 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::font::Font;
+
+use winapi::um::shellapi;
+
+const CLASS_ID: &str = commctrl::WC_LINK;
+
+lazy_static! {
+    pub static ref WINDOW_CLASS: Vec<u16> = OsStr::new(CLASS_ID).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+}
+
+pub type Link = AMember<AControl<WindowsLink>>;
+
+/// Receives the `id` and the `href` of the link clicked or activated with Enter, either being empty if the `<a>` has none.
+pub type OnLinkClick = Box<dyn FnMut(&mut Link, &str, &str)>;
+
+/// Text with links, given as markup where `<a href="https://example.com">` or `<a id="help">` spans are the links.
+/// Everything else is shown as is, lines wrapping at the width of the control.
+#[repr(C)]
+pub struct WindowsLink {
+    base: common::WindowsControlBase<Link>,
+    markup: String,
+    open_urls: bool,
+    on_link_click: Option<OnLinkClick>,
+}
+
+impl WindowsLink {
+    fn with_uninit(_: &mut mem::MaybeUninit<Link>) -> Self {
+        WindowsLink {
+            base: common::WindowsControlBase::with_handler(Some(handler)),
+            markup: String::new(),
+            open_urls: false,
+            on_link_click: None,
+        }
+    }
+    pub fn with_markup<S: AsRef<str>>(markup: S) -> Box<Link> {
+        let mut b: Box<mem::MaybeUninit<Link>> = Box::new_uninit();
+        let mut ab = AMember::with_inner(AControl::with_inner(Self::with_uninit(b.as_mut())));
+        ab.inner_mut().inner_mut().set_markup(markup);
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+    pub fn markup(&self) -> &str {
+        self.markup.as_str()
+    }
+    pub fn set_markup<S: AsRef<str>>(&mut self, markup: S) {
+        self.markup = markup.as_ref().into();
+        if !self.base.hwnd.is_null() {
+            let markup = common::str_to_wchar(self.markup.as_str());
+            backend::current().set_window_text(self.base.hwnd as usize, markup.as_slice());
+            self.base.invalidate();
+        }
+    }
+    pub fn opens_urls(&self) -> bool {
+        self.open_urls
+    }
+    /// With `open_urls`, a clicked link with an `http`, `https` or `mailto` `href` is opened by the shell after `on_link_click`.
+    /// Any other `href`, be it a file, a share or a program, is left to `on_link_click`, as the markup may come from anywhere.
+    pub fn set_open_urls(&mut self, open_urls: bool) {
+        self.open_urls = open_urls;
+    }
+    pub fn on_link_click(&mut self, callback: Option<OnLinkClick>) {
+        self.on_link_click = callback;
+    }
+    pub fn font(&self) -> Option<&Font> {
+        self.base.font()
+    }
+    /// `None` brings back the default font.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.base.set_font(font);
+    }
+
+    fn open_url(&self, url: &str) {
+        let operation = common::str_to_wchar("open");
+        let url = common::str_to_wchar(url);
+        // anything up to 32 is an error code rather than an instance
+        let result = unsafe { shellapi::ShellExecuteW(self.base.hwnd, operation.as_ptr(), url.as_ptr(), ptr::null(), ptr::null(), winuser::SW_SHOWNORMAL) };
+        if result as usize <= 32 {
+            common::log_error();
+        }
+    }
+}
+
+/// Whether `url` is of a scheme the shell hands to the browser or the mail client, rather than one that may start a program.
+fn is_web_url(url: &str) -> bool {
+    if url.trim() != url || url.chars().any(char::is_control) {
+        return false;
+    }
+    match url.find(':') {
+        Some(end) => ["http", "https", "mailto"].iter().any(|scheme| url[..end].eq_ignore_ascii_case(scheme)),
+        None => false,
+    }
+}
+
+impl ControlInner for WindowsLink {
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, x: i32, y: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (w, h, _) = self.measure(member, control, pw, ph);
+        let markup = self.markup.clone();
+        self.base.create_control_hwnd(
+            x as i32,
+            y as i32,
+            w as i32,
+            h as i32,
+            self.base.hwnd,
+            0,
+            WINDOW_CLASS.as_ptr(),
+            markup.as_str(),
+            winuser::WS_TABSTOP | commctrl::LWS_TRANSPARENT | commctrl::LWS_NOPREFIX,
+            selfptr,
+        );
+    }
+    fn on_removed_from_container(&mut self, _member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        self.base.destroy_control_hwnd();
+    }
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+}
+
+impl HasLayoutInner for WindowsLink {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        self.base.invalidate();
+    }
+}
+
+impl HasNativeIdInner for WindowsLink {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+
+impl HasSizeInner for WindowsLink {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsLink {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl MemberInner for WindowsLink {}
+
+impl Drawable for WindowsLink {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let hfont = self.base.hfont();
+                let text = display_text(self.markup.as_str());
+                let w = match control.layout.width {
+                    layout::Size::MatchParent => parent_width as i32,
+                    layout::Size::Exact(w) => w as i32,
+                    layout::Size::WrapContent => cmp::min(common::measure_text(self.base.hwnd, hfont, text.as_str(), None).cx, parent_width as i32),
+                };
+                let h = match control.layout.height {
+                    layout::Size::MatchParent => parent_height as i32,
+                    layout::Size::Exact(h) => h as i32,
+                    layout::Size::WrapContent => common::measure_text(self.base.hwnd, hfont, text.as_str(), Some(w)).cy,
+                };
+                (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+
+impl Spawnable for WindowsLink {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_markup("").into_control()
+    }
+}
+
+/// Text of `markup` as the control shows it, without the `<a>` tags.
+fn display_text(markup: &str) -> String {
+    fn is_link_tag(tag: &str) -> bool {
+        let tag = tag.to_ascii_lowercase();
+        tag == "<a>" || tag == "</a>" || tag.starts_with("<a ")
+    }
+    let mut text = String::with_capacity(markup.len());
+    let mut rest = markup;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        match tail.find('>') {
+            Some(end) if is_link_tag(&tail[..=end]) => rest = &tail[end + 1..],
+            _ => {
+                text.push('<');
+                rest = &tail[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
+    }
+    match msg {
+        m if m == common::OCM_BASE + winuser::WM_NOTIFY => match (&*(lparam as winuser::LPNMHDR)).code {
+            commctrl::NM_CLICK | commctrl::NM_RETURN => {
+                let item = &(&*(lparam as commctrl::PNMLINK)).item;
+                let id = common::wchar_to_str(item.szID.as_ptr());
+                let url = common::wchar_to_str(item.szUrl.as_ptr());
+                let link: &mut Link = mem::transmute(param);
+                let inner = link.inner_mut().inner_mut();
+                if let Some(ref mut cb) = inner.on_link_click {
+                    let link2: &mut Link = mem::transmute(param);
+                    (cb.as_mut())(link2, id.as_str(), url.as_str());
+                }
+                if inner.open_urls && is_web_url(url.as_str()) {
+                    inner.open_url(url.as_str());
+                }
+                return 0;
+            }
+            _ => {}
+        },
+        winuser::WM_SIZE => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+
+            let link: &mut Link = mem::transmute(param);
+            link.call_on_size::<Link>(width, height);
+        }
+        _ => {}
+    }
+
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
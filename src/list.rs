@@ -1,4 +1,8 @@
+use crate::backend;
 use crate::common::{self, *};
+use crate::layout_engine;
+use crate::navigation::{self, Step, TypeAhead};
+use crate::selection::{Modifiers, Selection, SelectionMode};
 
 const CLASS_ID: &str = commctrl::WC_LISTBOX;
 
@@ -8,22 +12,62 @@ lazy_static! {
 
 pub type List = AMember<AControl<AContainer<AAdapted<AList<WindowsList>>>>>;
 
+/// Puts the adapter data of the row at `indexes` into a view that showed another row before.
+pub type OnItemBind = Box<dyn FnMut(&mut dyn controls::Control, &[usize])>;
+
+pub type OnListSelectionChange = Box<dyn FnMut(&mut List, &[Vec<usize>])>;
+
+/// Reports an item dragged from the first index path to the second one. The list itself stays as it is until the adapter follows.
+pub type OnListItemMove = Box<dyn FnMut(&mut List, &[usize], &[usize])>;
+
+/// Item pressed in a reorderable list, becoming a drag once the mouse moves far enough.
+struct Drag {
+    from: usize,
+    origin: (i32, i32),
+    started: bool,
+    /// Row the item would be inserted before, the row count for the end of the list.
+    slot: Option<usize>,
+}
+
+/// Rows spared around the visible ones, so a short scroll does not spawn anything.
+const OVERSCAN: usize = 2;
+
+/// State of a list spawning item views only for the visible rows.
+struct VirtualRows {
+    item_height: u16,
+    count: usize,
+    /// Row shown by each entry of `WindowsList::items`.
+    rows: Vec<usize>,
+    /// Hidden views waiting to be bound to another row, kept only with a binder.
+    recycled: Vec<Box<dyn controls::Control>>,
+    binder: Option<OnItemBind>,
+}
+
 #[repr(C)]
 pub struct WindowsList {
     base: WindowsControlBase<List>,
     items: Vec<Box<dyn controls::Control>>,
+    virtual_rows: Option<VirtualRows>,
+    selection: Selection,
+    /// Row with the focus rectangle, moved by the keyboard.
+    focused: Option<usize>,
+    type_ahead: TypeAhead,
+    reorderable: bool,
+    drag: Option<Drag>,
     on_item_click: Option<callbacks::OnItemClick>,
+    on_selection_change: Option<OnListSelectionChange>,
+    on_item_move: Option<OnListItemMove>,
 }
 
 impl WindowsList {
     fn add_item_inner(&mut self, base: &mut MemberBase, indexes: &[usize], y: &mut i32) {
         let (member, control, adapter, _) = unsafe { List::adapter_base_parts_mut(base) };
         let (pw, ph) = control.measured;
-        let scroll_width = unsafe { winuser::GetSystemMetrics(winuser::SM_CXVSCROLL) };
+        let scroll_width = common::system_metric(self.base.hwnd, winuser::SM_CXVSCROLL);
         let this: &mut List = unsafe { utils::base_to_impl_mut(member) };
         
         let mut item = adapter.adapter.spawn_item_view(indexes, this).unwrap();
-        item.on_added_to_container(this, 0, *y, utils::coord_to_size(pw as i32 - scroll_width - 14 /*TODO: WHY???*/ - DEFAULT_PADDING) as u16, utils::coord_to_size(ph as i32) as u16);
+        item.on_added_to_container(this, 0, *y, utils::coord_to_size(pw as i32 - scroll_width - common::scaled(self.base.hwnd, 14) /*TODO: WHY???*/ - common::default_padding(self.base.hwnd)) as u16, utils::coord_to_size(ph as i32) as u16);
                 
         let (_, yy) = item.size();
         self.items.push(item);
@@ -32,10 +76,10 @@ impl WindowsList {
         let i = indexes[0];
         
         unsafe {
-            if i as isize != winuser::SendMessageW(self.base.hwnd, winuser::LB_INSERTSTRING, i, WINDOW_CLASS.as_ptr() as isize) {
+            if i as isize != backend::current().send_message(self.base.hwnd as usize, winuser::LB_INSERTSTRING, i, WINDOW_CLASS.as_ptr() as isize) {
                 common::log_error();
             }
-            if winuser::LB_ERR == winuser::SendMessageW(self.base.hwnd, winuser::LB_SETITEMHEIGHT, i, yy as isize) {
+            if winuser::LB_ERR == backend::current().send_message(self.base.hwnd as usize, winuser::LB_SETITEMHEIGHT, i, yy as isize) {
                 common::log_error();
             }
         }
@@ -45,23 +89,447 @@ impl WindowsList {
         self.items.remove(indexes[0]).on_removed_from_container(this); 
         let i = indexes[0];
         unsafe {
-            if i as isize != winuser::SendMessageW(self.base.hwnd, winuser::LB_DELETESTRING, i, WINDOW_CLASS.as_ptr() as isize) {
+            if i as isize != backend::current().send_message(self.base.hwnd as usize, winuser::LB_DELETESTRING, i, WINDOW_CLASS.as_ptr() as isize) {
                 common::log_error();
             }
         }
     }
-    fn force_scrollbar(&mut self) {
+    /// A list of rows of the same `item_height`, spawning item views only for the visible ones.
+    ///
+    /// Views scrolled away are dropped, or recycled for the rows coming into view once a binder is set with `set_item_binder`.
+    /// Type-ahead matches the text of the spawned views, so it only finds rows that are visible or within the overscan.
+    pub fn virtualized(adapter: Box<dyn types::Adapter>, item_height: u16) -> Box<List> {
+        let mut b: Box<mem::MaybeUninit<List>> = Box::new_uninit();
+        let mut inner = <Self as NewListInner<List>>::with_uninit(b.as_mut());
+        inner.virtual_rows = Some(VirtualRows {
+            item_height: cmp::max(1, item_height),
+            count: 0,
+            rows: vec![],
+            recycled: vec![],
+            binder: None,
+        });
+        let ab = AMember::with_inner(
+            AControl::with_inner(
+                AContainer::with_inner(
+                    AAdapted::with_inner(
+                        AList::with_inner(inner),
+                        adapter,
+                        &mut b,
+                    ),
+                )
+            ),
+        );
         unsafe {
-            winuser::ShowScrollBar(self.base.hwnd, winuser::SB_VERT as i32, minwindef::TRUE);
+	        b.as_mut_ptr().write(ab);
+	        b.assume_init()
+        }
+    }
+    pub fn is_virtualized(&self) -> bool {
+        self.virtual_rows.is_some()
+    }
+    /// Has no effect unless the list is virtualized.
+    pub fn set_item_binder(&mut self, binder: Option<OnItemBind>) {
+        if let Some(ref mut virtual_rows) = self.virtual_rows {
+            virtual_rows.binder = binder;
+        }
+    }
+    pub fn selection_mode(&self) -> SelectionMode {
+        self.selection.mode()
+    }
+    /// Calls `on_selection_change` when the new mode can not hold all the selected items.
+    pub fn set_selection_mode(&mut self, mode: SelectionMode) {
+        if self.selection.set_mode(mode) {
+            self.notify_selection();
+        }
+    }
+    /// Selected items in the order they were selected, kept in step with the adapter inserting and removing items.
+    pub fn selected(&self) -> &[Vec<usize>] {
+        self.selection.selected()
+    }
+    /// Does not call `on_selection_change`.
+    pub fn select(&mut self, indexes: &[usize]) {
+        if self.selection.select(indexes) {
+            self.redraw_selection();
+        }
+    }
+    /// Does not call `on_selection_change`.
+    pub fn deselect(&mut self, indexes: &[usize]) {
+        if self.selection.deselect(indexes) {
+            self.redraw_selection();
+        }
+    }
+    /// Does not call `on_selection_change`.
+    pub fn clear_selection(&mut self) {
+        if self.selection.clear() {
+            self.redraw_selection();
+        }
+    }
+    pub fn on_selection_change(&mut self, callback: Option<OnListSelectionChange>) {
+        self.on_selection_change = callback;
+    }
+    fn notify_selection(&mut self) {
+        self.redraw_selection();
+        let this = self.base.as_outer_mut();
+        if let Some(ref mut callback) = self.on_selection_change {
+            (callback.as_mut())(this, self.selection.selected());
+        }
+    }
+    /// Repaints the item views as well, as the selected ones are drawn in the colors of the selection.
+    fn redraw_selection(&self) {
+        if !self.base.hwnd.is_null() {
+            backend::current().redraw(self.base.hwnd as usize, true);
+        }
+    }
+    /// Whether `hwnd` is the view of a selected row.
+    fn is_view_selected(&self, hwnd: windef::HWND) -> bool {
+        let index = match self.items.iter().position(|item| unsafe { item.native_id() } as windef::HWND == hwnd) {
+            Some(index) => index,
+            None => return false,
+        };
+        let row = match self.virtual_rows {
+            Some(ref virtual_rows) => virtual_rows.rows[index],
+            None => index,
+        };
+        self.selection.is_selected(&[row])
+    }
+    fn row_count(&self) -> usize {
+        match self.virtual_rows {
+            Some(ref virtual_rows) => virtual_rows.count,
+            None => self.items.len(),
+        }
+    }
+    /// Rows fitting into the list, at least one.
+    fn page_rows(&self) -> usize {
+        let (_, height) = common::size_hwnd(self.base.hwnd);
+        let row = backend::current().send_message(self.base.hwnd as usize, winuser::LB_GETITEMHEIGHT, 0, 0);
+        if row > 0 {
+            cmp::max(1, height as usize / row as usize)
+        } else {
+            1
+        }
+    }
+    /// Window text of the view showing `row`, if it is spawned.
+    ///
+    /// In a virtualized list only the visible rows and the overscan have views, so type-ahead skips over the rest.
+    fn item_text(&self, row: usize) -> Option<String> {
+        self.item_index(row).map(|index| common::window_text(unsafe { self.items[index].native_id() as windef::HWND }))
+    }
+    /// Moves the focus rectangle to `row`, scrolling it into view, and selects it unless Ctrl is held.
+    fn focus_row(&mut self, base: &mut MemberBase, row: usize, modifiers: Modifiers) {
+        self.focused = Some(row);
+        let top = cmp::max(0, backend::current().send_message(self.base.hwnd as usize, winuser::LB_GETTOPINDEX, 0, 0)) as usize;
+        let page = self.page_rows();
+        let new_top = if row < top {
+            Some(row)
+        } else if row >= top + page {
+            Some(row + 1 - page)
+        } else {
+            None
+        };
+        if let Some(new_top) = new_top {
+            backend::current().send_message(self.base.hwnd as usize, winuser::LB_SETTOPINDEX, new_top, 0);
+            self.update_visible(base);
+        }
+        if !modifiers.ctrl && self.selection.click(&[row], modifiers) {
+            self.notify_selection();
+        }
+        backend::current().invalidate(self.base.hwnd as usize, false);
+    }
+    /// Calls `on_item_click` for `row`, as a mouse click on it does.
+    fn activate(&mut self, row: usize) {
+        if let Some(index) = self.item_index(row) {
+            let this = self.base.as_outer_mut();
+            let item_view = self.items.get_mut(index).unwrap();
+            if let Some(ref mut callback) = self.on_item_click {
+                (callback.as_mut())(this, &[row], item_view.as_mut());
+            }
         }
     }
+    /// Handles a navigation, activation or type-ahead key, returning whether it was one.
+    fn on_key(&mut self, base: &mut MemberBase, msg: minwindef::UINT, wparam: minwindef::WPARAM) -> bool {
+        let count = self.row_count();
+        if msg == winuser::WM_CHAR {
+            let c = match std::char::from_u32(wparam as u32) {
+                Some(c) if !c.is_control() => c,
+                _ => return false,
+            };
+            let time = unsafe { winuser::GetMessageTime() } as u32;
+            let mut type_ahead = mem::take(&mut self.type_ahead);
+            let found = type_ahead.type_char(c, time, self.focused, count, |row| self.item_text(row));
+            self.type_ahead = type_ahead;
+            if let Some(row) = found {
+                self.focus_row(base, row, Modifiers::default());
+            }
+            return true;
+        }
+        let step = match wparam as i32 {
+            winuser::VK_UP => Step::Previous,
+            winuser::VK_DOWN => Step::Next,
+            winuser::VK_PRIOR => Step::PagePrevious,
+            winuser::VK_NEXT => Step::PageNext,
+            winuser::VK_HOME => Step::First,
+            winuser::VK_END => Step::Last,
+            winuser::VK_RETURN => {
+                if let Some(row) = self.focused {
+                    self.activate(row);
+                }
+                return true;
+            }
+            winuser::VK_SPACE => {
+                if let Some(row) = self.focused {
+                    let modifiers = Modifiers { ctrl: true, shift: false };
+                    if self.selection.click(&[row], modifiers) {
+                        self.notify_selection();
+                    }
+                }
+                return true;
+            }
+            _ => return false,
+        };
+        self.type_ahead.reset();
+        if let Some(row) = navigation::step(self.focused, step, count, self.page_rows()) {
+            let modifiers = unsafe {
+                Modifiers {
+                    ctrl: winuser::GetKeyState(winuser::VK_CONTROL) < 0,
+                    shift: winuser::GetKeyState(winuser::VK_SHIFT) < 0,
+                }
+            };
+            self.focus_row(base, row, modifiers);
+        }
+        true
+    }
+    pub fn is_reorderable(&self) -> bool {
+        self.reorderable
+    }
+    /// Lets the user drag items around, reported through `on_item_move`.
+    pub fn set_reorderable(&mut self, reorderable: bool) {
+        self.reorderable = reorderable;
+        if !reorderable {
+            self.cancel_drag();
+        }
+    }
+    pub fn on_item_move(&mut self, callback: Option<OnListItemMove>) {
+        self.on_item_move = callback;
+    }
+    /// Follows the mouse at `(x, y)` with the left button held, returning whether the press has become a drag.
+    fn drag_to(&mut self, x: i32, y: i32) -> bool {
+        let count = self.row_count();
+        let hwnd = self.base.hwnd;
+        let drag = match self.drag {
+            Some(ref mut drag) => drag,
+            None => return false,
+        };
+        if !drag.started {
+            let (dx, dy) = unsafe { (winuser::GetSystemMetrics(winuser::SM_CXDRAG), winuser::GetSystemMetrics(winuser::SM_CYDRAG)) };
+            if (x - drag.origin.0).abs() <= dx && (y - drag.origin.1).abs() <= dy {
+                return false;
+            }
+            drag.started = true;
+            backend::current().set_capture(hwnd as usize);
+        }
+        let hit = backend::current().send_message(hwnd as usize, winuser::LB_ITEMFROMPOINT, 0, minwindef::MAKELONG(x as u16, y as u16) as isize) as u32;
+        let row = minwindef::LOWORD(hit) as usize;
+        let mut rc: windef::RECT = unsafe { mem::zeroed() };
+        let slot = if count == 0 {
+            0
+        } else if y < 0 {
+            row
+        } else if minwindef::HIWORD(hit) != 0 || winuser::LB_ERR == backend::current().send_message(hwnd as usize, winuser::LB_GETITEMRECT, row, &mut rc as *mut _ as isize) {
+            // below the last row
+            count
+        } else if y > (rc.top + rc.bottom) / 2 {
+            row + 1
+        } else {
+            row
+        };
+        if drag.slot != Some(slot) {
+            drag.slot = Some(slot);
+            backend::current().invalidate(hwnd as usize, false);
+        }
+        true
+    }
+    /// Ends a drag, returning whether there was one.
+    fn drop_drag(&mut self) -> bool {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return false,
+        };
+        if !drag.started {
+            return false;
+        }
+        backend::current().release_capture();
+        backend::current().invalidate(self.base.hwnd as usize, false);
+        if let Some(to) = drag.slot.and_then(|slot| navigation::move_target(&[drag.from], &[slot])) {
+            let this = self.base.as_outer_mut();
+            if let Some(ref mut callback) = self.on_item_move {
+                (callback.as_mut())(this, &[drag.from], to.as_slice());
+            }
+        }
+        true
+    }
+    fn cancel_drag(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            if drag.started {
+                backend::current().release_capture();
+                backend::current().invalidate(self.base.hwnd as usize, false);
+            }
+        }
+    }
+    /// Line between the rows a dragged item would be dropped between.
+    fn draw_drop_mark(&mut self) {
+        let slot = match self.drag {
+            Some(Drag { started: true, slot: Some(slot), .. }) => slot,
+            _ => return,
+        };
+        let count = self.row_count();
+        let mut rc: windef::RECT = unsafe { mem::zeroed() };
+        let row = if slot < count { slot } else { count.saturating_sub(1) };
+        unsafe {
+            if count > 0 && winuser::LB_ERR != backend::current().send_message(self.base.hwnd as usize, winuser::LB_GETITEMRECT, row, &mut rc as *mut _ as isize) {
+                let y = if slot < count { rc.top } else { rc.bottom };
+                rc.top = cmp::max(0, y - 1);
+                rc.bottom = rc.top + 2;
+                let dc = winuser::GetDCEx(self.base.hwnd, ptr::null_mut(), winuser::DCX_CACHE | winuser::DCX_CLIPSIBLINGS);
+                winuser::FillRect(dc, &rc, (winuser::COLOR_HIGHLIGHT + 1) as windef::HBRUSH);
+                winuser::ReleaseDC(self.base.hwnd, dc);
+            }
+        }
+    }
+    fn draw_focus(&mut self) {
+        let row = match self.focused {
+            Some(row) if backend::current().focus() == self.base.hwnd as usize => row,
+            _ => return,
+        };
+        let mut rc: windef::RECT = unsafe { mem::zeroed() };
+        unsafe {
+            if winuser::LB_ERR != backend::current().send_message(self.base.hwnd as usize, winuser::LB_GETITEMRECT, row, &mut rc as *mut _ as isize) {
+                // drawn over the item views, which the list clips away otherwise
+                let dc = winuser::GetDCEx(self.base.hwnd, ptr::null_mut(), winuser::DCX_CACHE | winuser::DCX_CLIPSIBLINGS);
+                winuser::DrawFocusRect(dc, &rc);
+                winuser::ReleaseDC(self.base.hwnd, dc);
+            }
+        }
+    }
+    /// Index of the view showing `row` in `self.items`.
+    fn item_index(&self, row: usize) -> Option<usize> {
+        match self.virtual_rows {
+            Some(ref virtual_rows) => virtual_rows.rows.iter().position(|r| *r == row),
+            None => Some(row).filter(|row| *row < self.items.len()),
+        }
+    }
+    /// Spawns, recycles and positions the item views of the visible rows.
+    fn update_visible(&mut self, base: &mut MemberBase) {
+        let (member, control, adapter, _) = unsafe { List::adapter_base_parts_mut(base) };
+        let (pw, ph) = control.measured;
+        let scroll_width = common::system_metric(self.base.hwnd, winuser::SM_CXVSCROLL);
+        let width = utils::coord_to_size(pw as i32 - scroll_width - common::default_padding(self.base.hwnd)) as u16;
+        let virtual_rows = match self.virtual_rows {
+            Some(ref mut virtual_rows) => virtual_rows,
+            None => return,
+        };
+        let top = cmp::max(0, backend::current().send_message(self.base.hwnd as usize, winuser::LB_GETTOPINDEX, 0, 0)) as usize;
+        let visible = layout_engine::visible_rows(top, ph, virtual_rows.item_height, virtual_rows.count, OVERSCAN);
+
+        let mut i = 0;
+        while i < self.items.len() {
+            if visible.contains(&virtual_rows.rows[i]) {
+                i += 1;
+                continue;
+            }
+            virtual_rows.rows.remove(i);
+            let mut item = self.items.remove(i);
+            if virtual_rows.binder.is_some() {
+                unsafe {
+                    backend::current().show_window(item.native_id(), false);
+                }
+                virtual_rows.recycled.push(item);
+            } else {
+                let this: &mut List = unsafe { utils::base_to_impl_mut(member) };
+                item.on_removed_from_container(this);
+            }
+        }
+        for row in visible {
+            if virtual_rows.rows.contains(&row) {
+                continue;
+            }
+            let item = match (virtual_rows.recycled.pop(), virtual_rows.binder.as_mut()) {
+                (Some(mut item), Some(binder)) => {
+                    (binder.as_mut())(item.as_mut(), &[row]);
+                    unsafe {
+                        backend::current().show_window(item.native_id(), true);
+                    }
+                    Some(item)
+                }
+                _ => {
+                    let this: &mut List = unsafe { utils::base_to_impl_mut(member) };
+                    adapter.adapter.spawn_item_view(&[row], this).map(|mut item| {
+                        let this: &mut List = unsafe { utils::base_to_impl_mut(member) };
+                        item.on_added_to_container(this, 0, 0, width, virtual_rows.item_height);
+                        item
+                    })
+                }
+            };
+            if let Some(item) = item {
+                self.items.push(item);
+                virtual_rows.rows.push(row);
+            }
+        }
+        for (item, row) in self.items.iter_mut().zip(virtual_rows.rows.iter()) {
+            item.measure(width, virtual_rows.item_height);
+            item.draw(Some((0, (*row as i32 - top as i32) * virtual_rows.item_height as i32)));
+        }
+        backend::current().invalidate(self.base.hwnd as usize, false);
+    }
+    fn on_virtual_item_change(&mut self, base: &mut MemberBase, value: adapter::Change) {
+        let member: &mut List = unsafe { utils::base_to_impl_mut(base) };
+        let virtual_rows = self.virtual_rows.as_mut().unwrap();
+        match value {
+            adapter::Change::Added(at, _) if at.len() == 1 => {
+                virtual_rows.count += 1;
+                for row in virtual_rows.rows.iter_mut().filter(|row| **row >= at[0]) {
+                    *row += 1;
+                }
+            }
+            adapter::Change::Removed(at) if at.len() == 1 => {
+                virtual_rows.count = virtual_rows.count.saturating_sub(1);
+                if let Some(i) = virtual_rows.rows.iter().position(|row| *row == at[0]) {
+                    virtual_rows.rows.remove(i);
+                    self.items.remove(i).on_removed_from_container(member);
+                }
+                for row in virtual_rows.rows.iter_mut().filter(|row| **row > at[0]) {
+                    *row -= 1;
+                }
+            }
+            adapter::Change::Edited(at, _) if at.len() == 1 => {
+                // the view is spawned or bound anew by `update_visible`
+                if let Some(i) = virtual_rows.rows.iter().position(|row| *row == at[0]) {
+                    virtual_rows.rows.remove(i);
+                    self.items.remove(i).on_removed_from_container(member);
+                }
+            }
+            _ => {}
+        }
+        backend::current().send_message(self.base.hwnd as usize, winuser::LB_SETCOUNT, virtual_rows.count, 0);
+        self.update_visible(base);
+    }
+    fn force_scrollbar(&mut self) {
+        backend::current().show_scroll_bar(self.base.hwnd as usize, winuser::SB_VERT as i32, true);
+    }
 }
 impl<O: controls::List> NewListInner<O> for WindowsList {
     fn with_uninit(_: &mut mem::MaybeUninit<O>) -> Self {
         WindowsList {
             base: WindowsControlBase::with_handler(Some(handler::<O>)),
             items: vec![],
+            virtual_rows: None,
+            selection: Selection::new(SelectionMode::Single),
+            focused: None,
+            type_ahead: TypeAhead::default(),
+            reorderable: false,
+            drag: None,
             on_item_click: None,
+            on_selection_change: None,
+            on_item_move: None,
         }
     }
 }
@@ -102,7 +570,21 @@ impl ItemClickableInner for WindowsList {
 }
 impl AdaptedInner for WindowsList {
     fn on_item_change(&mut self, base: &mut MemberBase, value: adapter::Change) {
-        if !self.base.hwnd.is_null() {
+        let selection_changed = match value {
+            adapter::Change::Added(ref at, _) => {
+                self.selection.inserted(at);
+                false
+            }
+            adapter::Change::Removed(ref at) => self.selection.removed(at),
+            adapter::Change::Edited(_, _) => false,
+        };
+        if selection_changed {
+            self.notify_selection();
+        }
+        if !self.base.hwnd.is_null() && self.virtual_rows.is_some() {
+            self.on_virtual_item_change(base, value);
+            self.force_scrollbar();
+        } else if !self.base.hwnd.is_null() {
             let mut y = 0;
             {
                 for item in self.items.as_slice() {
@@ -143,6 +625,7 @@ impl ControlInner for WindowsList {
         let selfptr = member as *mut _ as *mut c_void;
         self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
         let (w, h, _) = self.measure(member, control, pw, ph);
+        let rows_style = if self.virtual_rows.is_some() { winuser::LBS_OWNERDRAWFIXED | winuser::LBS_NODATA } else { winuser::LBS_OWNERDRAWVARIABLE };
         self.base.create_control_hwnd(
             px as i32,
             py as i32,
@@ -152,11 +635,29 @@ impl ControlInner for WindowsList {
             0,
             WINDOW_CLASS.as_ptr(),
             "",
-            winuser::WS_EX_CONTROLPARENT | winuser::WS_CLIPCHILDREN | winuser::LBS_OWNERDRAWVARIABLE | winuser::WS_BORDER | winuser::WS_VSCROLL | winuser::WS_EX_RIGHTSCROLLBAR,
+            winuser::WS_EX_CONTROLPARENT | winuser::WS_CLIPCHILDREN | rows_style | winuser::WS_BORDER | winuser::WS_VSCROLL | winuser::WS_EX_RIGHTSCROLLBAR,
             selfptr,
         );
         control.coords = Some((px as i32, py as i32));
         
+        if self.virtual_rows.is_some() {
+            let (_, _, adapter, _) = unsafe { List::adapter_base_parts_mut(member) };
+            let mut count = 0;
+            adapter.adapter.for_each(&mut (|indexes, _node| {
+                if indexes.len() == 1 {
+                    count += 1;
+                }
+            }));
+            if let Some(ref mut virtual_rows) = self.virtual_rows {
+                virtual_rows.count = count;
+                backend::current().send_message(self.base.hwnd as usize, winuser::LB_SETCOUNT, count, 0);
+                backend::current().send_message(self.base.hwnd as usize, winuser::LB_SETITEMHEIGHT, 0, virtual_rows.item_height as isize);
+            }
+            self.update_visible(member);
+            self.force_scrollbar();
+            return;
+        }
+
         let (member, _, adapter, _) = unsafe { List::adapter_base_parts_mut(member) };
 
         let mut y = 0;
@@ -170,6 +671,14 @@ impl ControlInner for WindowsList {
             let self2: &mut List = unsafe { utils::base_to_impl_mut(member) };
             child.on_removed_from_container(self2);
         }
+        if let Some(ref mut virtual_rows) = self.virtual_rows {
+            for mut child in virtual_rows.recycled.drain(..) {
+                let self2: &mut List = unsafe { utils::base_to_impl_mut(member) };
+                child.on_removed_from_container(self2);
+            }
+            virtual_rows.rows.clear();
+            self.items.clear();
+        }
         common::destroy_hwnd(self.base.hwnd, self.base.subclass_id, None);
         self.base.hwnd = 0 as windef::HWND;
         self.base.subclass_id = 0;
@@ -302,21 +811,85 @@ impl Spawnable for WindowsList {
 }
 
 unsafe extern "system" fn handler<T: controls::List>(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
-        winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, param as WinPtr);
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
     }
     match msg {
+        winuser::WM_LBUTTONDOWN => {
+            let list: &mut List = mem::transmute(param);
+            let list_inner = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            if list_inner.reorderable {
+                let hit = backend::current().send_message(hwnd as usize, winuser::LB_ITEMFROMPOINT, 0, lparam) as u32;
+                if minwindef::HIWORD(hit) == 0 && (minwindef::LOWORD(hit) as usize) < list_inner.row_count() {
+                    list_inner.drag = Some(Drag {
+                        from: minwindef::LOWORD(hit) as usize,
+                        origin: (minwindef::LOWORD(lparam as u32) as i16 as i32, minwindef::HIWORD(lparam as u32) as i16 as i32),
+                        started: false,
+                        slot: None,
+                    });
+                }
+            }
+        }
+        winuser::WM_MOUSEMOVE if wparam & winuser::MK_LBUTTON != 0 => {
+            let list: &mut List = mem::transmute(param);
+            let list_inner = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            if list_inner.drag_to(minwindef::LOWORD(lparam as u32) as i16 as i32, minwindef::HIWORD(lparam as u32) as i16 as i32) {
+                return 0;
+            }
+        }
+        winuser::WM_CAPTURECHANGED => {
+            let list: &mut List = mem::transmute(param);
+            list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cancel_drag();
+        }
         winuser::WM_LBUTTONUP => {
-            let i = winuser::SendMessageW(hwnd, winuser::LB_ITEMFROMPOINT, 0, lparam);
+            let hit = backend::current().send_message(hwnd as usize, winuser::LB_ITEMFROMPOINT, 0, lparam) as u32;
+            let i = minwindef::LOWORD(hit) as usize;
             let list: &mut List = mem::transmute(param);
             let list_inner = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
-            let item_view = list_inner.items.get_mut(i as usize).unwrap();
-            if let Some(ref mut callback) = list_inner.on_item_click {
-                let list: &mut List = mem::transmute(param);
-                (callback.as_mut())(list, &[i as usize], item_view.as_mut());
+            if list_inner.drop_drag() {
+                return 0;
+            }
+            list_inner.drag = None;
+            // the high word is set for a click below the last row, which still reports the nearest one
+            if minwindef::HIWORD(hit) == 0 && list_inner.item_index(i).is_some() {
+                list_inner.activate(i);
+                let modifiers = Modifiers {
+                    ctrl: wparam & winuser::MK_CONTROL != 0,
+                    shift: wparam & winuser::MK_SHIFT != 0,
+                };
+                list_inner.focused = Some(i);
+                if list_inner.selection.click(&[i], modifiers) {
+                    list_inner.notify_selection();
+                }
+                backend::current().invalidate(hwnd as usize, false);
             }
         }
+        winuser::WM_KEYDOWN | winuser::WM_CHAR => {
+            let list: &mut List = mem::transmute(param);
+            let list_inner = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            if msg == winuser::WM_KEYDOWN && wparam as i32 == winuser::VK_ESCAPE && list_inner.drag.is_some() {
+                list_inner.cancel_drag();
+                return 0;
+            }
+            if list_inner.on_key(common::member_base_from_hwnd(hwnd).unwrap(), msg, wparam) {
+                return 0;
+            }
+        }
+        winuser::WM_GETDLGCODE => {
+            return winuser::DLGC_WANTARROWS | winuser::DLGC_WANTCHARS | backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam);
+        }
+        winuser::WM_PAINT => {
+            let result = backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam);
+            let list: &mut List = mem::transmute(param);
+            let list_inner = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            list_inner.draw_focus();
+            list_inner.draw_drop_mark();
+            return result;
+        }
+        winuser::WM_SETFOCUS | winuser::WM_KILLFOCUS => {
+            backend::current().invalidate(hwnd as usize, false);
+        }
         winuser::WM_SIZE => {
             let width = lparam as u16;
             let height = (lparam >> 16) as u16;
@@ -324,30 +897,48 @@ unsafe extern "system" fn handler<T: controls::List>(hwnd: windef::HWND, msg: mi
             let list: &mut List = mem::transmute(param);
             list.call_on_size::<T>(width, height);
             
+            if list.inner().inner().inner().inner().inner().is_virtualized() {
+                let list = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+                list.update_visible(common::member_base_from_hwnd(hwnd).unwrap());
+                list.force_scrollbar();
+                return backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam);
+            }
             let mut y = 0;
-            let i = cmp::max(0, winuser::SendMessageW(hwnd, winuser::LB_GETTOPINDEX, 0, 0)) as usize;
+            let i = cmp::max(0, backend::current().send_message(hwnd as usize, winuser::LB_GETTOPINDEX, 0, 0)) as usize;
             let list = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
             for i in i..list.items.len() {
                 let item = &mut list.items[i];
-                let (_, ch, _) = item.measure(cmp::max(0, width as i32 - DEFAULT_PADDING) as u16, cmp::max(0, height as i32) as u16);
+                let (_, ch, _) = item.measure(cmp::max(0, width as i32 - common::default_padding(hwnd)) as u16, cmp::max(0, height as i32) as u16);
                 item.draw(Some((0, y)));
                 y += ch as i32;
             }
-            winuser::InvalidateRect(hwnd, ptr::null_mut(), minwindef::FALSE);
+            backend::current().invalidate(hwnd as usize, false);
             list.force_scrollbar();
         }
         winuser::WM_CTLCOLORSTATIC => {
             let hdc = wparam as windef::HDC;
+            let list: &mut List = mem::transmute(param);
+            if list.inner().inner().inner().inner().inner().is_view_selected(lparam as windef::HWND) {
+                return common::selected_item_colors(hdc);
+            }
             wingdi::SetTextColor(hdc, wingdi::RGB(0, 0, 0));
             wingdi::SetBkMode(hdc, wingdi::TRANSPARENT as i32);
 
             return wingdi::GetStockObject(wingdi::NULL_BRUSH as i32) as isize;
         }
         winuser::WM_VSCROLL | winuser::WM_MOUSEWHEEL => {
-            winuser::InvalidateRect(hwnd, ptr::null_mut(), minwindef::FALSE);
+            let list: &mut List = mem::transmute(param);
+            let list = list.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            if list.is_virtualized() {
+                // scroll first, then fill the rows that came into view
+                let result = backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam);
+                list.update_visible(common::member_base_from_hwnd(hwnd).unwrap());
+                return result;
+            }
+            backend::current().invalidate(hwnd as usize, false);
         }
         _ => {}
     }
 
-    commctrl::DefSubclassProc(hwnd, msg, wparam, lparam)
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
 }
@@ -1,3 +1,4 @@
+use crate::backend;
 use crate::common::{self, *};
 
 struct WindowsMessageAction {
@@ -41,9 +42,7 @@ impl HasLabelInner for WindowsMessage {
         self.label = label.into();
         if !self.hwnd.is_null() {
             let control_name = common::str_to_wchar(&self.label);
-            unsafe {
-                winuser::SetWindowTextW(self.hwnd, control_name.as_ptr());
-            }
+            backend::current().set_window_text(self.hwnd as usize, control_name.as_slice());
         }
     }
 }
@@ -0,0 +1,208 @@
+//! Keyboard navigation over a run of items shown one after another, free of any window handle.
+
+use std::cmp::min;
+
+/// Where a key moves the focused item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Step {
+    Previous,
+    Next,
+    PagePrevious,
+    PageNext,
+    First,
+    Last,
+}
+
+/// Item focused after `step` from `current` among `count` items, `page` of them fitting into the view.
+/// With nothing focused yet, any step lands on the first item.
+pub fn step(current: Option<usize>, step: Step, count: usize, page: usize) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    let last = count - 1;
+    let page = page.max(1);
+    let current = match current {
+        Some(current) => min(current, last),
+        None => return Some(0),
+    };
+    Some(match step {
+        Step::Previous => current.saturating_sub(1),
+        Step::Next => min(last, current + 1),
+        Step::PagePrevious => current.saturating_sub(page),
+        Step::PageNext => min(last, current + page),
+        Step::First => 0,
+        Step::Last => last,
+    })
+}
+
+/// Type-ahead search: characters typed shortly one after another make a prefix the item texts are matched against.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TypeAhead {
+    typed: String,
+    last: u32,
+}
+
+impl TypeAhead {
+    /// Pause, in milliseconds, after which typing starts a new prefix.
+    pub const TIMEOUT: u32 = 1000;
+
+    /// Adds `c`, typed at `time` milliseconds, to the prefix and returns the item to focus.
+    ///
+    /// The search starts at `current`, wrapping around, and compares ignoring case. Typing the same character over and over
+    /// cycles through the items starting with it. `text` gives the text of an item, `None` for items that can not be searched.
+    pub fn type_char<F>(&mut self, c: char, time: u32, current: Option<usize>, count: usize, text: F) -> Option<usize>
+    where
+        F: Fn(usize) -> Option<String>,
+    {
+        if time.wrapping_sub(self.last) > Self::TIMEOUT {
+            self.typed.clear();
+        }
+        self.last = time;
+        self.typed.extend(c.to_lowercase());
+
+        let mut chars = self.typed.chars();
+        let first = chars.next();
+        let repeated = chars.all(|typed| Some(typed) == first);
+        let (prefix, skip) = if repeated {
+            // the current item already matches, so the next one is wanted
+            (first.map(|c| c.to_string()).unwrap_or_default(), 1)
+        } else {
+            (self.typed.clone(), 0)
+        };
+        let start = current.unwrap_or(0);
+        (0..count)
+            .map(|i| (start + skip + i) % count)
+            .find(|i| text(*i).map(|text| text.to_lowercase().starts_with(prefix.as_str())).unwrap_or(false))
+    }
+    pub fn reset(&mut self) {
+        self.typed.clear();
+    }
+}
+
+/// Index path an item dragged from `from` ends up at when dropped into `slot`, the path it is inserted at while still in place.
+/// `None` if the drop leaves it where it is, or puts it into its own subtree.
+pub fn move_target(from: &[usize], slot: &[usize]) -> Option<Vec<usize>> {
+    if from.is_empty() || slot.is_empty() || slot.starts_with(from) {
+        return None;
+    }
+    let level = from.len() - 1;
+    let mut target = slot.to_vec();
+    // taking the item out shifts its later siblings, and everything under them, up by one
+    if target.len() > level && target[..level] == from[..level] && target[level] > from[level] {
+        target[level] -= 1;
+    }
+    if target.as_slice() == from {
+        None
+    } else {
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: &[&str] = &["apple", "Banana", "blueberry", "cherry", "Bilberry"];
+
+    fn name(i: usize) -> Option<String> {
+        NAMES.get(i).map(|name| name.to_string())
+    }
+
+    #[test]
+    fn step_over_nothing() {
+        assert_eq!(step(None, Step::Next, 0, 10), None);
+        assert_eq!(step(Some(3), Step::Last, 0, 10), None);
+    }
+
+    #[test]
+    fn step_without_focus_lands_on_the_first() {
+        assert_eq!(step(None, Step::Last, 5, 2), Some(0));
+        assert_eq!(step(None, Step::Previous, 5, 2), Some(0));
+    }
+
+    #[test]
+    fn step_clamps_at_both_ends() {
+        assert_eq!(step(Some(0), Step::Previous, 5, 2), Some(0));
+        assert_eq!(step(Some(4), Step::Next, 5, 2), Some(4));
+        assert_eq!(step(Some(2), Step::Next, 5, 2), Some(3));
+        assert_eq!(step(Some(2), Step::First, 5, 2), Some(0));
+        assert_eq!(step(Some(2), Step::Last, 5, 2), Some(4));
+        // focus left beyond the items after they shrank
+        assert_eq!(step(Some(9), Step::Previous, 5, 2), Some(3));
+    }
+
+    #[test]
+    fn step_by_pages() {
+        assert_eq!(step(Some(1), Step::PageNext, 10, 4), Some(5));
+        assert_eq!(step(Some(8), Step::PageNext, 10, 4), Some(9));
+        assert_eq!(step(Some(5), Step::PagePrevious, 10, 4), Some(1));
+        assert_eq!(step(Some(2), Step::PagePrevious, 10, 4), Some(0));
+        // an empty page still moves by one
+        assert_eq!(step(Some(2), Step::PageNext, 10, 0), Some(3));
+    }
+
+    #[test]
+    fn type_ahead_matches_a_prefix_ignoring_case() {
+        let mut type_ahead = TypeAhead::default();
+        assert_eq!(type_ahead.type_char('B', 100, None, NAMES.len(), name), Some(1));
+        assert_eq!(type_ahead.type_char('l', 200, Some(1), NAMES.len(), name), Some(2));
+        assert_eq!(type_ahead.type_char('x', 300, Some(2), NAMES.len(), name), None);
+    }
+
+    #[test]
+    fn type_ahead_cycles_on_the_same_character() {
+        let mut type_ahead = TypeAhead::default();
+        assert_eq!(type_ahead.type_char('b', 100, Some(0), NAMES.len(), name), Some(1));
+        assert_eq!(type_ahead.type_char('b', 200, Some(1), NAMES.len(), name), Some(2));
+        assert_eq!(type_ahead.type_char('b', 300, Some(2), NAMES.len(), name), Some(4));
+        // wraps around past the end
+        assert_eq!(type_ahead.type_char('b', 400, Some(4), NAMES.len(), name), Some(1));
+    }
+
+    #[test]
+    fn type_ahead_starts_over_after_a_pause() {
+        let mut type_ahead = TypeAhead::default();
+        assert_eq!(type_ahead.type_char('c', 100, None, NAMES.len(), name), Some(3));
+        assert_eq!(type_ahead.type_char('a', 100 + TypeAhead::TIMEOUT + 1, Some(3), NAMES.len(), name), Some(0));
+        type_ahead.reset();
+        assert_eq!(type_ahead.type_char('b', 100 + TypeAhead::TIMEOUT + 2, Some(0), NAMES.len(), name), Some(1));
+    }
+
+    #[test]
+    fn type_ahead_skips_items_without_text() {
+        let mut type_ahead = TypeAhead::default();
+        let text = |i: usize| if i == 1 { None } else { name(i) };
+        assert_eq!(type_ahead.type_char('b', 100, Some(0), NAMES.len(), text), Some(2));
+        assert_eq!(type_ahead.type_char('x', 100, Some(0), 0, text), None);
+    }
+
+    #[test]
+    fn move_target_after_its_own_position() {
+        // the slot counts the item itself, which is gone by the time it is inserted
+        assert_eq!(move_target(&[1], &[3]), Some(vec![2]));
+        assert_eq!(move_target(&[1], &[0]), Some(vec![0]));
+        assert_eq!(move_target(&[0, 1], &[0, 4]), Some(vec![0, 3]));
+        // right before or right after itself
+        assert_eq!(move_target(&[1], &[1]), None);
+        assert_eq!(move_target(&[1], &[2]), None);
+    }
+
+    #[test]
+    fn move_target_into_a_sibling_subtree() {
+        // later siblings move up, and everything under them with them
+        assert_eq!(move_target(&[0], &[2, 0]), Some(vec![1, 0]));
+        assert_eq!(move_target(&[0, 1], &[0, 3, 2]), Some(vec![0, 2, 2]));
+        // earlier siblings and other branches stay where they are
+        assert_eq!(move_target(&[2], &[0, 1]), Some(vec![0, 1]));
+        assert_eq!(move_target(&[0, 1], &[1]), Some(vec![1]));
+        assert_eq!(move_target(&[0, 1], &[1, 3]), Some(vec![1, 3]));
+    }
+
+    #[test]
+    fn move_target_into_its_own_subtree() {
+        assert_eq!(move_target(&[1], &[1, 0]), None);
+        assert_eq!(move_target(&[1], &[1, 2, 3]), None);
+        assert_eq!(move_target(&[], &[0]), None);
+        assert_eq!(move_target(&[0], &[]), None);
+    }
+}
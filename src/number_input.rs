@@ -0,0 +1,398 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::numeric::NumberFormat;
+
+const LOCALE_SDECIMAL: winnls::LCTYPE = 0x0000000E;
+const LOCALE_STHOUSAND: winnls::LCTYPE = 0x0000000F;
+const LOCALE_SNEGATIVESIGN: winnls::LCTYPE = 0x00000051;
+const MAX_PRECISION: usize = 10;
+
+lazy_static! {
+    pub static ref WINDOW_CLASS_EDIT: Vec<u16> = OsStr::new("Edit").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    pub static ref WINDOW_CLASS_UPDOWN: Vec<u16> = OsStr::new(commctrl::UPDOWN_CLASS).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    pub static ref WINDOW_CLASS: Vec<u16> = unsafe { register_window_class() };
+}
+
+pub type NumberInput = AMember<AControl<WindowsNumberInput>>;
+
+pub type OnNumberChange = Box<dyn FnMut(&mut NumberInput, f64)>;
+
+/// Separators of the user locale.
+fn user_format() -> NumberFormat {
+    NumberFormat {
+        decimal: locale_info(LOCALE_SDECIMAL).unwrap_or_else(|| ".".into()),
+        thousand: locale_info(LOCALE_STHOUSAND).unwrap_or_else(|| ",".into()),
+        negative: locale_info(LOCALE_SNEGATIVESIGN).unwrap_or_else(|| "-".into()),
+    }
+}
+
+#[repr(C)]
+pub struct WindowsNumberInput {
+    base: common::WindowsControlBase<NumberInput>,
+    hwnd_edit: windef::HWND,
+    hwnd_updown: windef::HWND,
+    format: NumberFormat,
+    value: f64,
+    range: (f64, f64),
+    step: f64,
+    precision: usize,
+    valid: bool,
+    on_value_change: Option<OnNumberChange>,
+    skip_callbacks: bool,
+}
+
+impl WindowsNumberInput {
+    fn with_uninit(_: &mut mem::MaybeUninit<NumberInput>, min: f64, max: f64) -> Self {
+        WindowsNumberInput {
+            base: common::WindowsControlBase::with_wndproc(Some(handler)),
+            hwnd_edit: ptr::null_mut(),
+            hwnd_updown: ptr::null_mut(),
+            format: user_format(),
+            value: min.min(max),
+            range: (min.min(max), min.max(max)),
+            step: 1.0,
+            precision: 0,
+            valid: true,
+            on_value_change: None,
+            skip_callbacks: false,
+        }
+    }
+    /// An integer input over the range, stepping by `1`.
+    pub fn with_range(min: f64, max: f64) -> Box<NumberInput> {
+        let mut b: Box<mem::MaybeUninit<NumberInput>> = Box::new_uninit();
+        let ab = AMember::with_inner(AControl::with_inner(Self::with_uninit(b.as_mut(), min, max)));
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+    /// Clamped to the range and rounded to the precision. Does not call `on_value_change`.
+    pub fn set_value(&mut self, value: f64) {
+        self.value = self.normalize(value);
+        self.valid = true;
+        self.apply_text();
+    }
+    pub fn range(&self) -> (f64, f64) {
+        self.range
+    }
+    pub fn set_range(&mut self, min: f64, max: f64) {
+        self.range = (min.min(max), min.max(max));
+        let value = self.value;
+        self.set_value(value);
+    }
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+    /// Distance of a single arrow click or Up/Down key press.
+    pub fn set_step(&mut self, step: f64) {
+        if step > 0.0 {
+            self.step = step;
+        }
+    }
+    /// Digits after the decimal separator, `0` for integers.
+    pub fn precision(&self) -> usize {
+        self.precision
+    }
+    pub fn set_precision(&mut self, precision: usize) {
+        self.precision = cmp::min(precision, MAX_PRECISION);
+        let value = self.value;
+        self.set_value(value);
+    }
+    /// Whether the typed text is a number within the range. While it is not, `value` keeps the last valid one.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+    pub fn on_value_change(&mut self, callback: Option<OnNumberChange>) {
+        self.on_value_change = callback;
+    }
+
+    fn normalize(&self, value: f64) -> f64 {
+        let factor = 10f64.powi(self.precision as i32);
+        let value = (value * factor).round() / factor;
+        value.max(self.range.0).min(self.range.1)
+    }
+    fn apply_text(&mut self) {
+        if !self.hwnd_edit.is_null() {
+            let text = common::str_to_wchar(self.format.format(self.value, self.precision));
+            self.skip_callbacks = true;
+            backend::current().set_window_text(self.hwnd_edit as usize, text.as_slice());
+            self.skip_callbacks = false;
+        }
+    }
+    /// Returns whether the value has changed.
+    fn on_text_changed(&mut self) -> bool {
+        let parsed = self.format.parse_within(common::window_text(self.hwnd_edit).as_str(), self.range);
+        self.valid = parsed.is_some();
+        match parsed {
+            Some(value) => {
+                let value = self.normalize(value);
+                let changed = value != self.value;
+                self.value = value;
+                changed
+            }
+            None => false,
+        }
+    }
+    /// Returns whether the value has changed.
+    fn step_by(&mut self, steps: i32) -> bool {
+        let value = self.normalize(self.value + self.step * steps as f64);
+        let changed = value != self.value;
+        self.set_value(value);
+        changed
+    }
+    fn notify(&mut self) {
+        if !self.skip_callbacks {
+            let this = self.base.as_outer_mut();
+            if let Some(ref mut cb) = self.on_value_change {
+                (cb.as_mut())(this, self.value);
+            }
+        }
+    }
+    fn layout_parts(&mut self, width: u16, height: u16) {
+        let arrows = cmp::min(width as i32, common::system_metric(self.base.hwnd, winuser::SM_CXVSCROLL));
+        backend::current().set_window_pos(self.hwnd_edit as usize, 0, 0, width as i32 - arrows, height as i32, winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE);
+        backend::current().set_window_pos(self.hwnd_updown as usize, width as i32 - arrows, 0, arrows, height as i32, winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE);
+    }
+}
+
+impl ControlInner for WindowsNumberInput {
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, px: i32, py: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (width, height, _) = self.measure(member, control, pw, ph);
+        let (hwnd, id) = unsafe {
+            common::create_control_hwnd(
+                px,
+                py,
+                width as i32,
+                height as i32,
+                parent.native_id() as windef::HWND,
+                winuser::WS_EX_CONTROLPARENT,
+                WINDOW_CLASS.as_ptr(),
+                "",
+                0,
+                selfptr,
+                None,
+            )
+        };
+        self.base.hwnd = hwnd;
+        self.base.subclass_id = id;
+        control.coords = Some((px, py));
+        unsafe {
+            self.hwnd_edit = common::create_part_hwnd(
+                (0, 0, width as i32, height as i32),
+                hwnd,
+                winuser::WS_EX_CLIENTEDGE,
+                WINDOW_CLASS_EDIT.as_ptr(),
+                &[0],
+                winuser::WS_TABSTOP | winuser::ES_AUTOHSCROLL | winuser::ES_RIGHT,
+                selfptr,
+                Some(edit_handler),
+            );
+            self.hwnd_updown = common::create_part_hwnd((0, 0, 0, height as i32), hwnd, 0, WINDOW_CLASS_UPDOWN.as_ptr(), &[0], commctrl::UDS_HOTTRACK, ptr::null_mut(), None);
+            backend::current().send_message(self.hwnd_edit as usize, winuser::WM_SETFONT, backend::current().send_message(hwnd as usize, winuser::WM_GETFONT, 0, 0) as usize, minwindef::FALSE as isize);
+            // the up-down only reports clicks, so its own position is kept away from the ends of its range
+            backend::current().send_message(self.hwnd_updown as usize, commctrl::UDM_SETRANGE32, 0, 2);
+            backend::current().send_message(self.hwnd_updown as usize, commctrl::UDM_SETPOS32, 0, 1);
+        }
+        self.layout_parts(width, height);
+        self.apply_text();
+    }
+    fn on_removed_from_container(&mut self, _member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        common::destroy_hwnd(self.hwnd_edit, common::subclass_id(WINDOW_CLASS_EDIT.as_ptr()) as usize, Some(edit_handler));
+        common::destroy_hwnd(self.hwnd_updown, 0, None);
+        self.base.destroy_control_hwnd();
+        self.hwnd_edit = ptr::null_mut();
+        self.hwnd_updown = ptr::null_mut();
+    }
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+}
+
+impl HasLayoutInner for WindowsNumberInput {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        self.base.invalidate();
+    }
+}
+
+impl HasNativeIdInner for WindowsNumberInput {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+
+impl HasSizeInner for WindowsNumberInput {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsNumberInput {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl MemberInner for WindowsNumberInput {}
+
+impl Drawable for WindowsNumberInput {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let w = match control.layout.width {
+                    layout::Size::MatchParent => parent_width as i32,
+                    layout::Size::Exact(w) => w as i32,
+                    layout::Size::WrapContent => {
+                        let widest = cmp::max(self.format.format(self.range.0, self.precision).chars().count(), self.format.format(self.range.1, self.precision).chars().count());
+                        let digit_size = common::measure_text(self.base.hwnd, ptr::null_mut(), "0", None);
+                        digit_size.cx * (cmp::min(widest, 16) as i32 + 1) + common::system_metric(self.base.hwnd, winuser::SM_CXVSCROLL) + common::default_padding(self.base.hwnd) * 2
+                    }
+                };
+                let h = match control.layout.height {
+                    layout::Size::MatchParent => parent_height as i32,
+                    layout::Size::Exact(h) => h as i32,
+                    layout::Size::WrapContent => common::default_height(self.base.hwnd),
+                };
+                (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+
+impl Spawnable for WindowsNumberInput {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_range(0.0, 100.0).into_control()
+    }
+}
+
+fn locale_info(kind: winnls::LCTYPE) -> Option<String> {
+    let mut buffer = [0u16; 8];
+    let len = unsafe { winnls::GetLocaleInfoEx(ptr::null(), kind, buffer.as_mut_ptr(), buffer.len() as i32) };
+    if len > 1 {
+        Some(String::from_utf16_lossy(&buffer[..len as usize - 1]))
+    } else {
+        None
+    }
+}
+
+unsafe fn register_window_class() -> Vec<u16> {
+    let class_name = OsStr::new("PlyguiWin32NumberInput").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
+        style: winuser::CS_DBLCLKS,
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
+    class_name
+}
+
+unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        if winuser::WM_CREATE == msg {
+            let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
+        }
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
+    }
+
+    let input: &mut NumberInput = mem::transmute(ww);
+    let input2: &mut NumberInput = mem::transmute(ww);
+    input.inner().inner().base.proc_handler.as_proc().unwrap()(input2, msg, wparam, lparam)
+}
+
+unsafe extern "system" fn handler(this: &mut NumberInput, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    let hwnd = this.inner().inner().base.hwnd;
+    match msg {
+        winuser::WM_SIZE => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+            this.inner_mut().inner_mut().layout_parts(width, height);
+            this.call_on_size::<NumberInput>(width, height);
+            return 0;
+        }
+        winuser::WM_COMMAND if lparam as windef::HWND == this.inner().inner().hwnd_edit => {
+            let input = this.inner_mut().inner_mut();
+            match minwindef::HIWORD(wparam as u32) {
+                winuser::EN_CHANGE if !input.skip_callbacks => {
+                    if input.on_text_changed() {
+                        input.notify();
+                    }
+                }
+                winuser::EN_KILLFOCUS => {
+                    // whatever was typed, the text shows the value again
+                    let value = input.value;
+                    input.set_value(value);
+                }
+                _ => {}
+            }
+            return 0;
+        }
+        winuser::WM_NOTIFY if (&*(lparam as winuser::LPNMHDR)).hwndFrom == this.inner().inner().hwnd_updown => {
+            if (&*(lparam as winuser::LPNMHDR)).code == commctrl::UDN_DELTAPOS {
+                let delta = (&*(lparam as commctrl::LPNMUPDOWN)).iDelta;
+                let input = this.inner_mut().inner_mut();
+                if input.step_by(delta) {
+                    input.notify();
+                }
+                // the position of the up-down itself stays as it is
+                return 1;
+            }
+            return 0;
+        }
+        _ => {}
+    }
+
+    backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam)
+}
+
+unsafe extern "system" fn edit_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    match msg {
+        winuser::WM_CHAR => {
+            let input: &mut NumberInput = mem::transmute(param);
+            let accepts = std::char::from_u32(wparam as u32).map(|c| input.inner().inner().format.accepts(c)).unwrap_or(true);
+            if !accepts {
+                winuser::MessageBeep(0xFFFFFFFF);
+                return 0;
+            }
+        }
+        winuser::WM_KEYDOWN if wparam as i32 == winuser::VK_UP || wparam as i32 == winuser::VK_DOWN => {
+            let input: &mut NumberInput = mem::transmute(param);
+            let input = input.inner_mut().inner_mut();
+            if input.step_by(if wparam as i32 == winuser::VK_UP { 1 } else { -1 }) {
+                input.notify();
+            }
+            return 0;
+        }
+        _ => {}
+    }
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
@@ -0,0 +1,185 @@
+//! Formatting, parsing and snapping of the values of number inputs and sliders, free of any window handle.
+
+use std::cmp::{max, min};
+
+/// Separators a number is written with, of the user locale for a number input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal: String,
+    pub thousand: String,
+    pub negative: String,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat {
+            decimal: ".".into(),
+            thousand: ",".into(),
+            negative: "-".into(),
+        }
+    }
+}
+
+impl NumberFormat {
+    /// `value` with `precision` digits after the decimal separator, without group separators. Zero has no sign.
+    pub fn format(&self, value: f64, precision: usize) -> String {
+        let text = format!("{:.*}", precision, value.abs()).replace('.', self.decimal.as_str());
+        if value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            format!("{}{}", self.negative, text)
+        } else {
+            text
+        }
+    }
+    /// `None` unless the whole text is a number, group separators aside.
+    pub fn parse(&self, text: &str) -> Option<f64> {
+        let text = text.trim();
+        let (negative, text) = if !self.negative.is_empty() && text.starts_with(self.negative.as_str()) {
+            (true, &text[self.negative.len()..])
+        } else {
+            (false, text)
+        };
+        let mut plain = if self.thousand.is_empty() { text.to_owned() } else { text.replace(self.thousand.as_str(), "") };
+        if self.spaced_groups() {
+            plain.retain(|c| !c.is_whitespace());
+        }
+        if self.decimal != "." {
+            if plain.contains('.') {
+                return None;
+            }
+            plain = plain.replace(self.decimal.as_str(), ".");
+        }
+        if plain.is_empty() || !plain.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return None;
+        }
+        plain.parse::<f64>().ok().map(|v| if negative { -v } else { v })
+    }
+    /// `parse`, also `None` for a number out of `range`, both ends included.
+    pub fn parse_within(&self, text: &str, (min, max): (f64, f64)) -> Option<f64> {
+        self.parse(text).filter(|value| *value >= min && *value <= max)
+    }
+    /// Whether typing `c` may lead to a valid number.
+    pub fn accepts(&self, c: char) -> bool {
+        c.is_ascii_digit() || c.is_control() || self.decimal.contains(c) || self.thousand.contains(c) || self.negative.contains(c) || (c == ' ' && self.spaced_groups())
+    }
+    /// Whether groups are separated by a space, usually a no-break one. Plain spaces are taken for it then, as that is what gets typed.
+    pub fn spaced_groups(&self) -> bool {
+        !self.thousand.is_empty() && self.thousand.chars().all(char::is_whitespace)
+    }
+}
+
+/// `value` clamped to the range and moved to the nearest `step` from its start.
+///
+/// Computed in `i64`, as the distance between the ends of the range may not fit into an `i32`.
+pub fn snap(value: i32, (start, end): (i32, i32), step: i32) -> i32 {
+    let value = min(end, max(start, value)) as i64;
+    let (start, end, step) = (start as i64, end as i64, step as i64);
+    let steps = ((value - start) as f64 / step as f64).round() as i64;
+    min(end, start + steps * step) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn german() -> NumberFormat {
+        NumberFormat {
+            decimal: ",".into(),
+            thousand: ".".into(),
+            negative: "-".into(),
+        }
+    }
+    fn french() -> NumberFormat {
+        NumberFormat {
+            decimal: ",".into(),
+            thousand: "\u{a0}".into(),
+            negative: "-".into(),
+        }
+    }
+
+    #[test]
+    fn format_with_locale_separators() {
+        assert_eq!(NumberFormat::default().format(1234.5, 2), "1234.50");
+        assert_eq!(german().format(1234.5, 1), "1234,5");
+        assert_eq!(german().format(-0.25, 2), "-0,25");
+        assert_eq!(NumberFormat::default().format(7.0, 0), "7");
+    }
+
+    #[test]
+    fn format_drops_the_sign_of_zero() {
+        assert_eq!(NumberFormat::default().format(-0.0, 2), "0.00");
+        assert_eq!(NumberFormat::default().format(-0.001, 2), "0.00");
+        let format = NumberFormat { negative: "\u{2212}".into(), ..NumberFormat::default() };
+        assert_eq!(format.format(-3.0, 0), "\u{2212}3");
+    }
+
+    #[test]
+    fn parse_with_locale_separators() {
+        assert_eq!(NumberFormat::default().parse(" 1,234.5 "), Some(1234.5));
+        assert_eq!(german().parse("1.234,5"), Some(1234.5));
+        // a point groups the digits there, and is no decimal separator where a comma is
+        assert_eq!(german().parse("1234.5"), Some(12345.0));
+        assert_eq!(french().parse("12.5"), None);
+        assert_eq!(NumberFormat::default().parse(""), None);
+        assert_eq!(NumberFormat::default().parse("12a"), None);
+        assert_eq!(NumberFormat::default().parse("1.2.3"), None);
+    }
+
+    #[test]
+    fn parse_takes_plain_spaces_for_no_break_groups() {
+        assert_eq!(french().parse("1\u{a0}234,5"), Some(1234.5));
+        assert_eq!(french().parse("1 234 567"), Some(1234567.0));
+        assert!(french().accepts(' '));
+        assert!(!german().accepts(' '));
+        assert_eq!(german().parse("1 234"), None);
+    }
+
+    #[test]
+    fn parse_the_negative_sign() {
+        assert_eq!(NumberFormat::default().parse("-12"), Some(-12.0));
+        assert_eq!(german().parse("-1.000,25"), Some(-1000.25));
+        assert_eq!(NumberFormat::default().parse("-"), None);
+        // only in front
+        assert_eq!(NumberFormat::default().parse("12-"), None);
+        assert_eq!(NumberFormat::default().parse("--12"), None);
+    }
+
+    #[test]
+    fn parse_within_rejects_out_of_range() {
+        let format = NumberFormat::default();
+        assert_eq!(format.parse_within("5", (0.0, 10.0)), Some(5.0));
+        assert_eq!(format.parse_within("10", (0.0, 10.0)), Some(10.0));
+        assert_eq!(format.parse_within("0", (0.0, 10.0)), Some(0.0));
+        assert_eq!(format.parse_within("10.5", (0.0, 10.0)), None);
+        assert_eq!(format.parse_within("-1", (0.0, 10.0)), None);
+    }
+
+    #[test]
+    fn accepts_what_may_become_a_number() {
+        let format = german();
+        for c in "0123456789,.-\u{8}".chars() {
+            assert!(format.accepts(c), "{:?}", c);
+        }
+        assert!(!format.accepts('e'));
+        assert!(!format.accepts('+'));
+    }
+
+    #[test]
+    fn snap_to_steps_from_the_start() {
+        assert_eq!(snap(7, (0, 100), 5), 5);
+        assert_eq!(snap(8, (0, 100), 5), 10);
+        assert_eq!(snap(8, (3, 100), 5), 8);
+        assert_eq!(snap(9, (3, 100), 5), 8);
+        // the end is kept even off the steps
+        assert_eq!(snap(99, (0, 99), 10), 99);
+        assert_eq!(snap(200, (0, 100), 7), 98);
+        assert_eq!(snap(-5, (0, 100), 7), 0);
+    }
+
+    #[test]
+    fn snap_over_the_whole_i32_range() {
+        assert_eq!(snap(i32::MAX, (i32::MIN, i32::MAX), 1), i32::MAX);
+        assert_eq!(snap(i32::MIN, (i32::MIN, i32::MAX), 1), i32::MIN);
+        assert_eq!(snap(0, (i32::MIN, i32::MAX), 1 << 30), 0);
+        assert_eq!(snap(i32::MAX - 10, (i32::MIN, i32::MAX), 1 << 30), i32::MAX);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::backend;
 use crate::common::{self, *};
 
 const CLASS_ID: &str = ::winapi::um::commctrl::PROGRESS_CLASS;
@@ -46,26 +47,26 @@ impl HasProgressInner for WindowsProgressBar {
     fn set_progress(&mut self, _base: &mut MemberBase, arg0: types::Progress) {
         self.progress = arg0;
         if !self.base.hwnd.is_null() {
-            let mut style = unsafe { winuser::GetWindowLongPtrW(self.base.hwnd, winuser::GWL_STYLE) };
+            let mut style = backend::current().window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE);
             match self.progress {
-                types::Progress::Undefined => unsafe {
-                    style |= commctrl::PBS_MARQUEE as WinPtr;
-                    winuser::SetWindowLongPtrW(self.base.hwnd, winuser::GWL_STYLE, style);
-                    winuser::SendMessageW(self.base.hwnd, commctrl::PBM_SETMARQUEE, 1, 0); 
+                types::Progress::Undefined => {
+                    style |= commctrl::PBS_MARQUEE as isize;
+                    backend::current().set_window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE, style);
+                    backend::current().send_message(self.base.hwnd as usize, commctrl::PBM_SETMARQUEE, 1, 0); 
                 },
-                types::Progress::Value(current, total) => unsafe {
-                    style &= !commctrl::PBS_MARQUEE as WinPtr;
-                    winuser::SetWindowLongPtrW(self.base.hwnd, winuser::GWL_STYLE, style);
-                    winuser::SendMessageW(self.base.hwnd, commctrl::PBM_SETSTATE, commctrl::PBST_NORMAL as usize, 0);
-                    winuser::SendMessageW(self.base.hwnd, commctrl::PBM_SETRANGE32, 0, total as isize);
-                    winuser::SendMessageW(self.base.hwnd, commctrl::PBM_SETPOS, current as usize, 0);
+                types::Progress::Value(current, total) => {
+                    style &= !commctrl::PBS_MARQUEE as isize;
+                    backend::current().set_window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE, style);
+                    backend::current().send_message(self.base.hwnd as usize, commctrl::PBM_SETSTATE, commctrl::PBST_NORMAL as usize, 0);
+                    backend::current().send_message(self.base.hwnd as usize, commctrl::PBM_SETRANGE32, 0, total as isize);
+                    backend::current().send_message(self.base.hwnd as usize, commctrl::PBM_SETPOS, current as usize, 0);
                 },
-                types::Progress::None => unsafe {
-                	style &= !commctrl::PBS_MARQUEE as WinPtr;
-                    winuser::SetWindowLongPtrW(self.base.hwnd, winuser::GWL_STYLE, style);
-                    winuser::SendMessageW(self.base.hwnd, commctrl::PBM_SETSTATE, commctrl::PBST_PAUSED as usize, 0);
-                    winuser::SendMessageW(self.base.hwnd, commctrl::PBM_SETRANGE32, 0, 0);
-                    winuser::SendMessageW(self.base.hwnd, commctrl::PBM_SETPOS, 0, 0);
+                types::Progress::None => {
+                	style &= !commctrl::PBS_MARQUEE as isize;
+                    backend::current().set_window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE, style);
+                    backend::current().send_message(self.base.hwnd as usize, commctrl::PBM_SETSTATE, commctrl::PBST_PAUSED as usize, 0);
+                    backend::current().send_message(self.base.hwnd as usize, commctrl::PBM_SETRANGE32, 0, 0);
+                    backend::current().send_message(self.base.hwnd as usize, commctrl::PBM_SETPOS, 0, 0);
                 }
             }
         }
@@ -157,14 +158,14 @@ impl Drawable for WindowsProgressBar {
                     layout::Size::MatchParent => parent_width as i32,
                     layout::Size::Exact(w) => w as i32,
                     layout::Size::WrapContent => {
-                        common::DEFAULT_HEIGHT / 2 + DEFAULT_PADDING + DEFAULT_PADDING
+                        common::default_height(self.base.hwnd) / 2 + common::default_padding(self.base.hwnd) * 2
                     }
                 };
                 let h = match control.layout.height {
                     layout::Size::MatchParent => parent_height as i32,
                     layout::Size::Exact(h) => h as i32,
                     layout::Size::WrapContent => {
-                        common::DEFAULT_HEIGHT / 2 + DEFAULT_PADDING + DEFAULT_PADDING
+                        common::default_height(self.base.hwnd) / 2 + common::default_padding(self.base.hwnd) * 2
                     }
                 };
                 (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
@@ -183,9 +184,9 @@ impl Spawnable for WindowsProgressBar {
 }
 
 unsafe extern "system" fn handler<T: controls::ProgressBar>(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
-        winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, param as WinPtr);
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
     }
     match msg {
         winuser::WM_SIZE => {
@@ -199,5 +200,5 @@ unsafe extern "system" fn handler<T: controls::ProgressBar>(hwnd: windef::HWND,
         _ => {}
     }
 
-    commctrl::DefSubclassProc(hwnd, msg, wparam, lparam)
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
 }
@@ -0,0 +1,459 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::layout_engine::{self, Padding, ScrollAction, Viewport};
+
+lazy_static! {
+    pub static ref WINDOW_CLASS: Vec<u16> = unsafe { register_window_class() };
+}
+
+pub type Scroll = AMember<AControl<AContainer<ASingleContainer<WindowsScroll>>>>;
+
+#[repr(C)]
+pub struct WindowsScroll {
+    base: common::WindowsControlBase<Scroll>,
+    child: Option<Box<dyn controls::Control>>,
+    horizontal: bool,
+    vertical: bool,
+    h: Viewport,
+    v: Viewport,
+}
+
+impl WindowsScroll {
+    fn with_uninit<O: controls::SingleContainer>(_: &mut mem::MaybeUninit<O>, horizontal: bool, vertical: bool) -> Self {
+        WindowsScroll {
+            base: common::WindowsControlBase::with_wndproc(Some(handler::<O>)),
+            child: None,
+            horizontal,
+            vertical,
+            h: Viewport::default(),
+            v: Viewport::default(),
+        }
+    }
+    /// Scrolls along the axes enabled by `horizontal` and `vertical`.
+    pub fn with_axes(horizontal: bool, vertical: bool) -> Box<Scroll> {
+        let mut b: Box<mem::MaybeUninit<Scroll>> = Box::new_uninit();
+        let ab = AMember::with_inner(AControl::with_inner(AContainer::with_inner(ASingleContainer::with_inner(Self::with_uninit(b.as_mut(), horizontal, vertical)))));
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+    pub fn with_child(child: Box<dyn controls::Control>) -> Box<Scroll> {
+        let mut scroll = Self::with_axes(false, true);
+        controls::SingleContainer::set_child(scroll.as_mut(), Some(child));
+        scroll
+    }
+
+    pub fn scroll_offset(&self) -> (i32, i32) {
+        (self.h.offset, self.v.offset)
+    }
+    pub fn content_size(&self) -> (u16, u16) {
+        (self.h.content, self.v.content)
+    }
+    pub fn scroll_to(&mut self, x: i32, y: i32) {
+        let line = self.line_step();
+        let changed_h = self.horizontal && self.h.scroll(ScrollAction::To(x), line);
+        let changed_v = self.vertical && self.v.scroll(ScrollAction::To(y), line);
+        if changed_h || changed_v {
+            self.apply_offset();
+        }
+    }
+    pub fn scroll_by(&mut self, dx: i32, dy: i32) {
+        let (x, y) = self.scroll_offset();
+        self.scroll_to(x + dx, y + dy);
+    }
+
+    /// Distance of one line of scrolling, the height of a default control on the current monitor.
+    fn line_step(&self) -> i32 {
+        common::default_height(self.base.hwnd)
+    }
+    /// See `layout_engine::scrolls_vertically`.
+    fn scrolls_vertically(&self) -> bool {
+        layout_engine::scrolls_vertically(if self.horizontal { Some(&self.h) } else { None }, if self.vertical { Some(&self.v) } else { None })
+    }
+    fn scroll_axis(&mut self, vertical: bool, action: ScrollAction) {
+        let line = self.line_step();
+        let changed = if vertical {
+            self.vertical && self.v.scroll(action, line)
+        } else {
+            self.horizontal && self.h.scroll(action, line)
+        };
+        if changed {
+            self.apply_offset();
+        }
+    }
+    fn layout_child(&mut self, width: u16, height: u16) {
+        if let Some(ref mut child) = self.child {
+            child.measure(width, height);
+            let (cw, ch) = child.size();
+            self.h.resize(if self.horizontal { cw } else { width }, width);
+            self.v.resize(if self.vertical { ch } else { height }, height);
+        } else {
+            self.h.resize(0, width);
+            self.v.resize(0, height);
+        }
+        self.apply_offset();
+    }
+    fn apply_offset(&mut self) {
+        if let Some(ref mut child) = self.child {
+            child.draw(Some((-self.h.offset, -self.v.offset)));
+        }
+        if !self.base.hwnd.is_null() {
+            if self.horizontal {
+                update_scroll_bar(self.base.hwnd, winuser::SB_HORZ, &self.h);
+            }
+            if self.vertical {
+                update_scroll_bar(self.base.hwnd, winuser::SB_VERT, &self.v);
+            }
+        }
+    }
+}
+
+impl SingleContainerInner for WindowsScroll {
+    fn set_child(&mut self, base: &mut MemberBase, child: Option<Box<dyn controls::Control>>) -> Option<Box<dyn controls::Control>> {
+        let mut old = self.child.take();
+        if let Some(old) = old.as_mut() {
+            if !self.base.hwnd.is_null() {
+                old.on_removed_from_container(self.base.as_outer_mut());
+            }
+        }
+        self.child = child;
+        self.h.offset = 0;
+        self.v.offset = 0;
+
+        if !self.base.hwnd.is_null() {
+            let (w, h) = base.as_any().downcast_ref::<Scroll>().unwrap().inner().base.measured;
+            if let Some(new) = self.child.as_mut() {
+                new.as_mut().on_added_to_container(self.base.as_outer_mut(), 0, 0, w, h);
+            }
+            self.layout_child(w, h);
+        }
+
+        old
+    }
+    fn child(&self) -> Option<&dyn controls::Control> {
+        self.child.as_ref().map(|c| c.as_ref())
+    }
+    fn child_mut(&mut self) -> Option<&mut dyn controls::Control> {
+        if let Some(child) = self.child.as_mut() {
+            Some(child.as_mut())
+        } else {
+            None
+        }
+    }
+}
+
+impl ContainerInner for WindowsScroll {
+    fn find_control_mut<'a>(&'a mut self, arg: types::FindBy<'a>) -> Option<&'a mut dyn controls::Control> {
+        if let Some(child) = self.child.as_mut() {
+            match arg {
+                types::FindBy::Id(id) => {
+                    if child.as_member_mut().id() == id {
+                        return Some(child.as_mut());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member_mut().tag() {
+                        if tag == mytag {
+                            return Some(child.as_mut());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container_mut() {
+                c.find_control_mut(arg)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+    fn find_control<'a>(&'a self, arg: types::FindBy<'a>) -> Option<&'a dyn controls::Control> {
+        if let Some(child) = self.child.as_ref() {
+            match arg {
+                types::FindBy::Id(id) => {
+                    if child.as_member().id() == id {
+                        return Some(child.as_ref());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member().tag() {
+                        if tag == mytag {
+                            return Some(child.as_ref());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container() {
+                c.find_control(arg)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl ControlInner for WindowsScroll {
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, px: i32, py: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        let (hwnd, id) = unsafe {
+            self.base.hwnd = parent.native_container_id() as windef::HWND; // required for measure, as we don't have own hwnd yet
+            let (width, height, _) = self.measure(member, control, pw, ph);
+            common::create_control_hwnd(
+                px,
+                py,
+                width as i32,
+                height as i32,
+                parent.native_id() as windef::HWND,
+                winuser::WS_EX_CONTROLPARENT,
+                WINDOW_CLASS.as_ptr(),
+                "",
+                winuser::WS_CLIPCHILDREN | winuser::WS_TABSTOP | if self.horizontal { winuser::WS_HSCROLL } else { 0 } | if self.vertical { winuser::WS_VSCROLL } else { 0 },
+                selfptr,
+                None,
+            )
+        };
+        self.base.hwnd = hwnd;
+        self.base.subclass_id = id;
+        control.coords = Some((px, py));
+        let (width, height) = common::size_hwnd(hwnd);
+        if let Some(ref mut child) = self.child {
+            let self2: &mut Scroll = unsafe { utils::base_to_impl_mut(member) };
+            child.on_added_to_container(self2, 0, 0, width, height);
+        }
+        self.layout_child(width, height);
+    }
+    fn on_removed_from_container(&mut self, member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        if let Some(ref mut child) = self.child {
+            let self2: &mut Scroll = unsafe { utils::base_to_impl_mut(member) };
+            child.on_removed_from_container(self2);
+        }
+        self.base.destroy_control_hwnd();
+    }
+
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+}
+
+impl HasLayoutInner for WindowsScroll {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        self.base.invalidate();
+    }
+}
+
+impl HasNativeIdInner for WindowsScroll {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+
+impl HasSizeInner for WindowsScroll {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+impl HasVisibilityInner for WindowsScroll {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl MemberInner for WindowsScroll {}
+
+impl Drawable for WindowsScroll {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => layout_engine::measure_single(
+                (control.layout.width, control.layout.height),
+                (parent_width, parent_height),
+                Padding::default(),
+                self.child.as_mut().map(|child| {
+                    move |w, h| {
+                        let (cw, ch, _) = child.measure(w, h);
+                        (cw, ch)
+                    }
+                }),
+            ),
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+
+impl Spawnable for WindowsScroll {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_axes(false, true).into_control()
+    }
+}
+
+fn update_scroll_bar(hwnd: windef::HWND, bar: u32, viewport: &Viewport) {
+    let info = backend::ScrollInfo {
+        min: 0,
+        max: cmp::max(0, viewport.content as i32 - 1),
+        page: viewport.view as u32,
+        pos: viewport.offset,
+        track_pos: 0,
+    };
+    backend::current().set_scroll_info(hwnd as usize, bar as i32, &info);
+}
+
+fn track_position(hwnd: windef::HWND, bar: u32) -> i32 {
+    match backend::current().scroll_info(hwnd as usize, bar as i32) {
+        Some(info) => info.track_pos,
+        None => {
+            unsafe {
+                common::log_error();
+            }
+            0
+        }
+    }
+}
+
+fn scroll_bar_action(hwnd: windef::HWND, bar: u32, code: isize) -> Option<ScrollAction> {
+    match code {
+        winuser::SB_LINEUP => Some(ScrollAction::LineBack),
+        winuser::SB_LINEDOWN => Some(ScrollAction::LineForward),
+        winuser::SB_PAGEUP => Some(ScrollAction::PageBack),
+        winuser::SB_PAGEDOWN => Some(ScrollAction::PageForward),
+        winuser::SB_TOP => Some(ScrollAction::Start),
+        winuser::SB_BOTTOM => Some(ScrollAction::End),
+        winuser::SB_THUMBTRACK | winuser::SB_THUMBPOSITION => Some(ScrollAction::To(track_position(hwnd, bar))),
+        _ => None,
+    }
+}
+
+fn wheel_lines(setting: u32) -> u32 {
+    let mut lines: u32 = 3;
+    unsafe {
+        winuser::SystemParametersInfoW(setting, 0, &mut lines as *mut _ as *mut c_void, 0);
+    }
+    lines
+}
+
+unsafe fn register_window_class() -> Vec<u16> {
+    let class_name = OsStr::new("PlyguiWin32Scroll").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
+        style: winuser::CS_DBLCLKS,
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
+    class_name
+}
+
+unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        if winuser::WM_CREATE == msg {
+            let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
+        }
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
+    }
+
+    let scroll: &mut Scroll = mem::transmute(ww);
+    let scroll2: &mut Scroll = mem::transmute(ww);
+    scroll.inner().inner().inner().inner().base.proc_handler.as_proc().unwrap()(scroll2, msg, wparam, lparam)
+}
+
+unsafe extern "system" fn handler<T: controls::SingleContainer>(this: &mut Scroll, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    let hwnd = this.inner().inner().inner().inner().base.hwnd;
+    match msg {
+        winuser::WM_SIZE | common::WM_UPDATE_INNER => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+
+            this.inner_mut().inner_mut().inner_mut().inner_mut().layout_child(width, height);
+            if msg != common::WM_UPDATE_INNER {
+                this.call_on_size::<T>(width, height);
+            }
+            return 0;
+        }
+        winuser::WM_VSCROLL | winuser::WM_HSCROLL if lparam == 0 => {
+            let vertical = msg == winuser::WM_VSCROLL;
+            let bar = if vertical { winuser::SB_VERT } else { winuser::SB_HORZ };
+            if let Some(action) = scroll_bar_action(hwnd, bar, minwindef::LOWORD(wparam as u32) as isize) {
+                this.inner_mut().inner_mut().inner_mut().inner_mut().scroll_axis(vertical, action);
+            }
+            return 0;
+        }
+        winuser::WM_MOUSEWHEEL | winuser::WM_MOUSEHWHEEL => {
+            let scroll = this.inner_mut().inner_mut().inner_mut().inner_mut();
+            let delta = winuser::GET_WHEEL_DELTA_WPARAM(wparam) as i32;
+            let line = scroll.line_step();
+            let (vertical, setting) = if msg == winuser::WM_MOUSEHWHEEL {
+                (false, winuser::SPI_GETWHEELSCROLLCHARS)
+            } else {
+                (scroll.scrolls_vertically(), winuser::SPI_GETWHEELSCROLLLINES)
+            };
+            let page = if vertical { scroll.v.view } else { scroll.h.view } as i32;
+            let distance = layout_engine::wheel_distance(delta, wheel_lines(setting), line, page);
+            // tilting to the right is a positive delta, and scrolls forward
+            let distance = if msg == winuser::WM_MOUSEHWHEEL { distance.saturating_neg() } else { distance };
+            scroll.scroll_axis(vertical, ScrollAction::By(distance));
+            return 0;
+        }
+        winuser::WM_LBUTTONDOWN => {
+            backend::current().set_focus(hwnd as usize);
+        }
+        winuser::WM_GETDLGCODE => {
+            return winuser::DLGC_WANTARROWS;
+        }
+        winuser::WM_KEYDOWN => {
+            let vertical = this.inner().inner().inner().inner().scrolls_vertically();
+            let action = match wparam as i32 {
+                winuser::VK_UP => Some((true, ScrollAction::LineBack)),
+                winuser::VK_DOWN => Some((true, ScrollAction::LineForward)),
+                winuser::VK_LEFT => Some((false, ScrollAction::LineBack)),
+                winuser::VK_RIGHT => Some((false, ScrollAction::LineForward)),
+                winuser::VK_PRIOR => Some((vertical, ScrollAction::PageBack)),
+                winuser::VK_NEXT => Some((vertical, ScrollAction::PageForward)),
+                winuser::VK_HOME => Some((vertical, ScrollAction::Start)),
+                winuser::VK_END => Some((vertical, ScrollAction::End)),
+                _ => None,
+            };
+            if let Some((vertical, action)) = action {
+                this.inner_mut().inner_mut().inner_mut().inner_mut().scroll_axis(vertical, action);
+                return 0;
+            }
+        }
+        winuser::WM_COMMAND | winuser::WM_NOTIFY | winuser::WM_HSCROLL | winuser::WM_VSCROLL => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
+        }
+        winuser::WM_CTLCOLORLISTBOX | winuser::WM_CTLCOLORSTATIC => {
+            let hdc = wparam as windef::HDC;
+            wingdi::SetBkMode(hdc, wingdi::TRANSPARENT as i32);
+
+            return wingdi::GetStockObject(wingdi::NULL_BRUSH as i32) as isize;
+        }
+        _ => {}
+    }
+
+    backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam)
+}
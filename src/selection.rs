@@ -0,0 +1,281 @@
+//! Selection state of the adapted controls, free of any window handle.
+//!
+//! Items are addressed by index paths, as in `ItemClickableInner::item_click`: a single index in a list, one per level in a tree.
+
+use std::cmp::{max, min};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SelectionMode {
+    None,
+    #[default]
+    Single,
+    /// Ctrl+click toggles an item, Shift+click selects the siblings between the last clicked item and this one.
+    Multiple,
+}
+
+/// Keys held during a click.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Selection {
+    mode: SelectionMode,
+    /// Selected paths, in the order they were selected.
+    selected: Vec<Vec<usize>>,
+    /// Start of Shift+click ranges.
+    anchor: Option<Vec<usize>>,
+}
+
+impl Selection {
+    pub fn new(mode: SelectionMode) -> Selection {
+        Selection { mode, ..Default::default() }
+    }
+    pub fn mode(&self) -> SelectionMode {
+        self.mode
+    }
+    /// Drops what the new mode can not hold: everything for `None`, all but the last selected item for `Single`.
+    /// Returns whether the selection changed.
+    pub fn set_mode(&mut self, mode: SelectionMode) -> bool {
+        self.mode = mode;
+        let keep = match mode {
+            SelectionMode::None => 0,
+            SelectionMode::Single => min(1, self.selected.len()),
+            SelectionMode::Multiple => self.selected.len(),
+        };
+        let dropped = self.selected.len() - keep;
+        self.selected.drain(..dropped);
+        if keep == 0 {
+            self.anchor = None;
+        }
+        dropped > 0
+    }
+    pub fn selected(&self) -> &[Vec<usize>] {
+        self.selected.as_slice()
+    }
+    pub fn is_selected(&self, indexes: &[usize]) -> bool {
+        self.selected.iter().any(|s| s.as_slice() == indexes)
+    }
+    /// Adds `indexes` to the selection, replacing it unless in `Multiple` mode. Returns whether the selection changed.
+    pub fn select(&mut self, indexes: &[usize]) -> bool {
+        match self.mode {
+            SelectionMode::None => false,
+            SelectionMode::Single => {
+                self.anchor = Some(indexes.to_vec());
+                if self.selected.len() == 1 && self.selected[0].as_slice() == indexes {
+                    false
+                } else {
+                    self.selected = vec![indexes.to_vec()];
+                    true
+                }
+            }
+            SelectionMode::Multiple => {
+                self.anchor = Some(indexes.to_vec());
+                if self.is_selected(indexes) {
+                    false
+                } else {
+                    self.selected.push(indexes.to_vec());
+                    true
+                }
+            }
+        }
+    }
+    /// Returns whether the selection changed.
+    pub fn deselect(&mut self, indexes: &[usize]) -> bool {
+        let len = self.selected.len();
+        self.selected.retain(|s| s.as_slice() != indexes);
+        len != self.selected.len()
+    }
+    /// Returns whether the selection changed.
+    pub fn clear(&mut self) -> bool {
+        self.anchor = None;
+        let changed = !self.selected.is_empty();
+        self.selected.clear();
+        changed
+    }
+    /// Applies a click on `indexes`. Returns whether the selection changed.
+    pub fn click(&mut self, indexes: &[usize], modifiers: Modifiers) -> bool {
+        if self.mode != SelectionMode::Multiple {
+            return self.select(indexes);
+        }
+        let anchor = self.anchor.clone();
+        match anchor {
+            Some(ref anchor) if modifiers.shift && is_sibling(anchor, indexes) => {
+                let old = if modifiers.ctrl { self.selected.clone() } else { vec![] };
+                let level = indexes.len() - 1;
+                let mut range: Vec<Vec<usize>> = (min(anchor[level], indexes[level])..=max(anchor[level], indexes[level]))
+                    .map(|i| {
+                        let mut path = indexes.to_vec();
+                        path[level] = i;
+                        path
+                    })
+                    .filter(|path| !old.contains(path))
+                    .collect();
+                let mut selected = old;
+                selected.append(&mut range);
+                let changed = selected != self.selected;
+                self.selected = selected;
+                changed
+            }
+            _ if modifiers.ctrl => {
+                self.anchor = Some(indexes.to_vec());
+                if !self.deselect(indexes) {
+                    self.selected.push(indexes.to_vec());
+                }
+                true
+            }
+            _ => {
+                self.anchor = Some(indexes.to_vec());
+                if self.selected.len() == 1 && self.selected[0].as_slice() == indexes {
+                    false
+                } else {
+                    self.selected = vec![indexes.to_vec()];
+                    true
+                }
+            }
+        }
+    }
+    /// Shifts the paths after an item inserted at `at`. The selection itself does not change.
+    pub fn inserted(&mut self, at: &[usize]) {
+        for path in self.selected.iter_mut().chain(self.anchor.iter_mut()) {
+            shift(path, at, 1);
+        }
+    }
+    /// Drops the item removed from `at` with its children, and shifts the paths after it. Returns whether the selection changed.
+    pub fn removed(&mut self, at: &[usize]) -> bool {
+        if self.anchor.as_ref().map(|anchor| anchor.starts_with(at)).unwrap_or(false) {
+            self.anchor = None;
+        }
+        let len = self.selected.len();
+        self.selected.retain(|path| !path.starts_with(at));
+        for path in self.selected.iter_mut().chain(self.anchor.iter_mut()) {
+            shift(path, at, -1);
+        }
+        len != self.selected.len()
+    }
+}
+
+/// Whether both paths are items of the same parent.
+fn is_sibling(a: &[usize], b: &[usize]) -> bool {
+    !a.is_empty() && a.len() == b.len() && a[..a.len() - 1] == b[..b.len() - 1]
+}
+
+/// Moves `path` by `delta` if it is, or is under, a later sibling of the item at `at`.
+fn shift(path: &mut [usize], at: &[usize], delta: isize) {
+    let level = match at.len() {
+        0 => return,
+        len => len - 1,
+    };
+    if path.len() > level && path[..level] == at[..level] && path[level] >= at[level] {
+        path[level] = (path[level] as isize + delta) as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTRL: Modifiers = Modifiers { ctrl: true, shift: false };
+    const SHIFT: Modifiers = Modifiers { ctrl: false, shift: true };
+    const CTRL_SHIFT: Modifiers = Modifiers { ctrl: true, shift: true };
+
+    fn paths(paths: &[&[usize]]) -> Vec<Vec<usize>> {
+        paths.iter().map(|path| path.to_vec()).collect()
+    }
+
+    #[test]
+    fn click_in_single_and_none_modes() {
+        let mut selection = Selection::new(SelectionMode::Single);
+        assert!(selection.click(&[1], Modifiers::default()));
+        assert!(!selection.click(&[1], Modifiers::default()));
+        // modifiers do nothing special with a single item to select
+        assert!(selection.click(&[2], CTRL));
+        assert_eq!(selection.selected(), paths(&[&[2]]).as_slice());
+
+        let mut selection = Selection::new(SelectionMode::None);
+        assert!(!selection.click(&[1], CTRL_SHIFT));
+        assert!(selection.selected().is_empty());
+    }
+
+    #[test]
+    fn click_in_multiple_mode() {
+        let mut selection = Selection::new(SelectionMode::Multiple);
+        assert!(selection.click(&[1], Modifiers::default()));
+        assert!(selection.click(&[3], CTRL));
+        assert_eq!(selection.selected(), paths(&[&[1], &[3]]).as_slice());
+        // Ctrl toggles
+        assert!(selection.click(&[1], CTRL));
+        assert_eq!(selection.selected(), paths(&[&[3]]).as_slice());
+        // Shift selects the range from the last clicked item, even one Ctrl deselected, dropping the rest unless Ctrl is held as well
+        assert!(selection.click(&[5], SHIFT));
+        assert_eq!(selection.selected(), paths(&[&[1], &[2], &[3], &[4], &[5]]).as_slice());
+        assert!(selection.click(&[8], CTRL));
+        assert!(selection.click(&[10], CTRL_SHIFT));
+        assert_eq!(selection.selected(), paths(&[&[1], &[2], &[3], &[4], &[5], &[8], &[9], &[10]]).as_slice());
+        assert!(!selection.click(&[9], CTRL_SHIFT));
+        // a plain click starts over
+        assert!(selection.click(&[4], Modifiers::default()));
+        assert_eq!(selection.selected(), paths(&[&[4]]).as_slice());
+    }
+
+    #[test]
+    fn shift_click_ranges_stay_among_siblings() {
+        let mut selection = Selection::new(SelectionMode::Multiple);
+        selection.click(&[0, 1], Modifiers::default());
+        assert!(selection.click(&[0, 3], SHIFT));
+        assert_eq!(selection.selected(), paths(&[&[0, 1], &[0, 2], &[0, 3]]).as_slice());
+        // the anchor is on another level, so this is a plain click
+        assert!(selection.click(&[2], SHIFT));
+        assert_eq!(selection.selected(), paths(&[&[2]]).as_slice());
+    }
+
+    #[test]
+    fn inserted_shifts_later_paths() {
+        let mut selection = Selection::new(SelectionMode::Multiple);
+        selection.click(&[0, 3], Modifiers::default());
+        selection.click(&[2], CTRL);
+        selection.inserted(&[1]);
+        assert_eq!(selection.selected(), paths(&[&[0, 3], &[3]]).as_slice());
+        selection.inserted(&[0, 3]);
+        assert_eq!(selection.selected(), paths(&[&[0, 4], &[3]]).as_slice());
+        selection.inserted(&[4]);
+        assert_eq!(selection.selected(), paths(&[&[0, 4], &[3]]).as_slice());
+        // the anchor has moved along with the item it was on
+        selection.click(&[5], SHIFT);
+        assert_eq!(selection.selected(), paths(&[&[3], &[4], &[5]]).as_slice());
+    }
+
+    #[test]
+    fn removed_drops_the_item_and_its_children() {
+        let mut selection = Selection::new(SelectionMode::Multiple);
+        selection.click(&[1], Modifiers::default());
+        selection.click(&[3], CTRL);
+        selection.click(&[2, 0], CTRL);
+        assert!(selection.removed(&[2]));
+        assert_eq!(selection.selected(), paths(&[&[1], &[2]]).as_slice());
+        assert!(!selection.removed(&[5]));
+        assert!(!selection.removed(&[0, 1]));
+        assert!(!selection.removed(&[0]));
+        assert_eq!(selection.selected(), paths(&[&[0], &[1]]).as_slice());
+        // the anchor was removed along with its item, so a Shift+click has nothing to start from
+        assert!(selection.click(&[3], SHIFT));
+        assert_eq!(selection.selected(), paths(&[&[3]]).as_slice());
+    }
+
+    #[test]
+    fn set_mode_drops_what_the_mode_can_not_hold() {
+        let mut selection = Selection::new(SelectionMode::Multiple);
+        selection.click(&[1], Modifiers::default());
+        selection.click(&[2], CTRL);
+        selection.click(&[3], CTRL);
+        assert!(!selection.set_mode(SelectionMode::Multiple));
+        assert!(selection.set_mode(SelectionMode::Single));
+        assert_eq!(selection.selected(), paths(&[&[3]]).as_slice());
+        assert!(!selection.set_mode(SelectionMode::Multiple));
+        assert!(selection.set_mode(SelectionMode::None));
+        assert!(selection.selected().is_empty());
+        assert!(!selection.set_mode(SelectionMode::Single));
+    }
+}
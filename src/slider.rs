@@ -0,0 +1,286 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::numeric::snap;
+
+const CLASS_ID: &str = commctrl::TRACKBAR_CLASS;
+
+lazy_static! {
+    pub static ref WINDOW_CLASS: Vec<u16> = OsStr::new(CLASS_ID).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+}
+
+pub type Slider = AMember<AControl<WindowsSlider>>;
+
+pub type OnValueChange = Box<dyn FnMut(&mut Slider, i32)>;
+
+#[repr(C)]
+pub struct WindowsSlider {
+    base: common::WindowsControlBase<Slider>,
+    orientation: layout::Orientation,
+    range: (i32, i32),
+    value: i32,
+    step: i32,
+    tick_frequency: i32,
+    on_value_change: Option<OnValueChange>,
+}
+
+impl WindowsSlider {
+    fn with_uninit(_: &mut mem::MaybeUninit<Slider>, orientation: layout::Orientation, min: i32, max: i32) -> Self {
+        WindowsSlider {
+            base: common::WindowsControlBase::with_handler(Some(handler)),
+            orientation,
+            range: (cmp::min(min, max), cmp::max(min, max)),
+            value: cmp::min(min, max),
+            step: 1,
+            tick_frequency: 0,
+            on_value_change: None,
+        }
+    }
+    pub fn with_range(min: i32, max: i32, orientation: layout::Orientation) -> Box<Slider> {
+        let mut b: Box<mem::MaybeUninit<Slider>> = Box::new_uninit();
+        let ab = AMember::with_inner(AControl::with_inner(Self::with_uninit(b.as_mut(), orientation, min, max)));
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+    /// Clamped to the range and snapped to the step. Does not call `on_value_change`.
+    pub fn set_value(&mut self, value: i32) {
+        self.value = snap(value, self.range, self.step);
+        if !self.base.hwnd.is_null() {
+            backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_SETPOS, minwindef::TRUE as usize, self.value as isize);
+        }
+    }
+    pub fn range(&self) -> (i32, i32) {
+        self.range
+    }
+    pub fn set_range(&mut self, min: i32, max: i32) {
+        self.range = (cmp::min(min, max), cmp::max(min, max));
+        self.apply_range();
+        let value = self.value;
+        self.set_value(value);
+    }
+    pub fn step(&self) -> i32 {
+        self.step
+    }
+    /// Values are snapped to multiples of `step` from the range start, which is also the distance of a single arrow key press.
+    pub fn set_step(&mut self, step: i32) {
+        self.step = cmp::max(1, step);
+        self.apply_step();
+        let value = self.value;
+        self.set_value(value);
+    }
+    pub fn tick_frequency(&self) -> i32 {
+        self.tick_frequency
+    }
+    /// Ticks every `frequency` values, `0` for no ticks.
+    pub fn set_tick_frequency(&mut self, frequency: i32) {
+        self.tick_frequency = cmp::max(0, frequency);
+        self.apply_ticks();
+    }
+    pub fn orientation(&self) -> layout::Orientation {
+        self.orientation
+    }
+    pub fn set_orientation(&mut self, orientation: layout::Orientation) {
+        if orientation != self.orientation {
+            self.orientation = orientation;
+            if !self.base.hwnd.is_null() {
+                let mut style = backend::current().window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE);
+                match orientation {
+                    layout::Orientation::Horizontal => style &= !commctrl::TBS_VERT as isize,
+                    layout::Orientation::Vertical => style |= commctrl::TBS_VERT as isize,
+                }
+                backend::current().set_window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE, style);
+            }
+            // the wrapped width and height swap
+            self.base.relayout_parent();
+            self.base.invalidate();
+        }
+    }
+    pub fn on_value_change(&mut self, callback: Option<OnValueChange>) {
+        self.on_value_change = callback;
+    }
+
+    fn style(&self) -> minwindef::DWORD {
+        let orientation = match self.orientation {
+            layout::Orientation::Horizontal => commctrl::TBS_HORZ,
+            layout::Orientation::Vertical => commctrl::TBS_VERT,
+        };
+        let ticks = if self.tick_frequency > 0 { commctrl::TBS_AUTOTICKS } else { commctrl::TBS_NOTICKS };
+        orientation | ticks | winuser::WS_TABSTOP
+    }
+    fn apply_range(&mut self) {
+        if !self.base.hwnd.is_null() {
+            backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_SETRANGEMIN, minwindef::FALSE as usize, self.range.0 as isize);
+            backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_SETRANGEMAX, minwindef::TRUE as usize, self.range.1 as isize);
+            self.apply_step();
+        }
+    }
+    fn apply_step(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let span = self.range.1 as i64 - self.range.0 as i64;
+            let page = cmp::min(i32::MAX as i64, cmp::max(self.step as i64, span / 10 / self.step as i64 * self.step as i64)) as i32;
+            backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_SETLINESIZE, 0, self.step as isize);
+            backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_SETPAGESIZE, 0, page as isize);
+        }
+    }
+    fn apply_ticks(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let mut style = backend::current().window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE);
+            if self.tick_frequency > 0 {
+                style = (style & !commctrl::TBS_NOTICKS as isize) | commctrl::TBS_AUTOTICKS as isize;
+            } else {
+                style = (style & !commctrl::TBS_AUTOTICKS as isize) | commctrl::TBS_NOTICKS as isize;
+            }
+            backend::current().set_window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE, style);
+            backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_SETTICFREQ, cmp::max(1, self.tick_frequency) as usize, 0);
+            self.base.invalidate();
+        }
+    }
+    fn on_native_scroll(&mut self, request: minwindef::WORD) -> bool {
+        let position = backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_GETPOS, 0, 0) as i32;
+        let value = snap(position, self.range, self.step);
+        if value != position && request as usize == commctrl::TB_ENDTRACK {
+            backend::current().send_message(self.base.hwnd as usize, commctrl::TBM_SETPOS, minwindef::TRUE as usize, value as isize);
+        }
+        if value != self.value {
+            self.value = value;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl ControlInner for WindowsSlider {
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, x: i32, y: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (w, h, _) = self.measure(member, control, pw, ph);
+        self.base.create_control_hwnd(x as i32, y as i32, w as i32, h as i32, self.base.hwnd, 0, WINDOW_CLASS.as_ptr(), "", self.style(), selfptr);
+        self.apply_range();
+        self.apply_ticks();
+        let value = self.value;
+        self.set_value(value);
+    }
+    fn on_removed_from_container(&mut self, _member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        self.base.destroy_control_hwnd();
+    }
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+}
+
+impl HasLayoutInner for WindowsSlider {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        self.base.invalidate();
+    }
+}
+
+impl HasNativeIdInner for WindowsSlider {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+
+impl HasSizeInner for WindowsSlider {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsSlider {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl MemberInner for WindowsSlider {}
+
+impl Drawable for WindowsSlider {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let default_height = common::default_height(self.base.hwnd);
+                let across = default_height + if self.tick_frequency > 0 { common::default_padding(self.base.hwnd) } else { 0 };
+                let along = default_height * 5;
+                let (wrap_w, wrap_h) = match self.orientation {
+                    layout::Orientation::Horizontal => (along, across),
+                    layout::Orientation::Vertical => (across, along),
+                };
+                let w = match control.layout.width {
+                    layout::Size::MatchParent => parent_width as i32,
+                    layout::Size::Exact(w) => w as i32,
+                    layout::Size::WrapContent => wrap_w,
+                };
+                let h = match control.layout.height {
+                    layout::Size::MatchParent => parent_height as i32,
+                    layout::Size::Exact(h) => h as i32,
+                    layout::Size::WrapContent => wrap_h,
+                };
+                (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+
+impl Spawnable for WindowsSlider {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_range(0, 100, layout::Orientation::Horizontal).into_control()
+    }
+}
+
+unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
+    }
+    match msg {
+        m if m == common::OCM_BASE + winuser::WM_HSCROLL || m == common::OCM_BASE + winuser::WM_VSCROLL => {
+            let slider: &mut Slider = mem::transmute(param);
+            let inner = slider.inner_mut().inner_mut();
+            if inner.on_native_scroll(minwindef::LOWORD(wparam as u32)) {
+                if let Some(ref mut cb) = inner.on_value_change {
+                    let slider2: &mut Slider = mem::transmute(param);
+                    (cb.as_mut())(slider2, inner.value);
+                }
+            }
+            return 0;
+        }
+        winuser::WM_SIZE => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+
+            let slider: &mut Slider = mem::transmute(param);
+            slider.call_on_size::<Slider>(width, height);
+        }
+        _ => {}
+    }
+
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
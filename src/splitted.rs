@@ -1,9 +1,11 @@
+use crate::backend;
 use crate::common::{self, *};
+use crate::layout_engine;
 
+// at dpi::BASE_DPI, as the paddings
 const DEFAULT_BOUND: i32 = DEFAULT_PADDING * 2;
 const DEFAULT_DIVIDER_PADDING: i32 = DEFAULT_PADDING * 8;
 const HALF_DIVIDER_PADDING: i32 = DEFAULT_DIVIDER_PADDING / 2;
-const HALF_BOUND: i32 = DEFAULT_BOUND / 2;
 
 lazy_static! {
     pub static ref WINDOW_CLASS: Vec<u16> = unsafe { register_window_class() };
@@ -27,27 +29,34 @@ pub struct WindowsSplitted {
 }
 
 impl WindowsSplitted {
+    #[inline]
+    fn padding(&self) -> i32 {
+        common::default_padding(self.base.hwnd)
+    }
+    /// Closest the divider gets to the edges.
+    #[inline]
+    fn bound(&self) -> i32 {
+        common::scaled(self.base.hwnd, DEFAULT_BOUND)
+    }
     fn children_sizes(&self, base: &ControlBase) -> (u16, u16) {
         let (w, h) = base.measured;
         let target = match self.orientation {
             layout::Orientation::Horizontal => w,
             layout::Orientation::Vertical => h,
         };
-        (
-            utils::coord_to_size((target as f32 * self.splitter) as i32 - DEFAULT_PADDING - HALF_BOUND),
-            utils::coord_to_size((target as f32 * (1.0 - self.splitter)) as i32 - DEFAULT_PADDING - HALF_BOUND),
-        )
+        layout_engine::split(target, self.splitter, self.padding(), self.bound())
     }
     fn draw_divider(&mut self, base: &ControlBase) {
         let (w, h) = base.measured;
+        let half = common::scaled(self.base.hwnd, HALF_DIVIDER_PADDING);
         let (x0, y0, x1, y1) = match self.orientation {
             layout::Orientation::Vertical => {
                 let coord = (h as f32 * self.splitter) as i32;
-                (HALF_DIVIDER_PADDING, coord, w as i32 - HALF_DIVIDER_PADDING, coord)
+                (half, coord, w as i32 - half, coord)
             }
             layout::Orientation::Horizontal => {
                 let coord = (w as f32 * self.splitter) as i32;
-                (coord, HALF_DIVIDER_PADDING, coord, h as i32 - HALF_DIVIDER_PADDING)
+                (coord, half, coord, h as i32 - half)
             }
         };
 
@@ -68,22 +77,9 @@ impl WindowsSplitted {
         }
     }
     fn draw_children(&mut self) {
-        let mut x = DEFAULT_PADDING;
-        let mut y = DEFAULT_PADDING;
-        for ref mut child in [self.first.as_mut(), self.second.as_mut()].iter_mut() {
-            child.draw(Some((x, y)));
-            let (xx, yy) = child.size();
-            match self.orientation {
-                layout::Orientation::Horizontal => {
-                    x += xx as i32;
-                    x += DEFAULT_BOUND;
-                }
-                layout::Orientation::Vertical => {
-                    y += yy as i32;
-                    y += DEFAULT_BOUND;
-                }
-            }
-        }
+        let origins = layout_engine::split_origins(self.orientation, self.padding(), self.bound(), self.first.size());
+        self.first.draw(Some(origins[0]));
+        self.second.draw(Some(origins[1]));
     }
     fn reload_cursor(&mut self) {
         unsafe {
@@ -107,15 +103,16 @@ impl WindowsSplitted {
         }
 
         let orientation = self.orientation;
+        let padding = self.padding();
         let (first_size, second_size) = self.children_sizes(base);
         let (width, height) = base.measured;
         for (size, child) in [(first_size, self.first.as_mut()), (second_size, self.second.as_mut())].iter_mut() {
             match orientation {
                 layout::Orientation::Horizontal => {
-                    child.measure(cmp::max(0, *size) as u16, cmp::max(0, height as i32 - DEFAULT_PADDING - DEFAULT_PADDING) as u16);
+                    child.measure(cmp::max(0, *size) as u16, cmp::max(0, height as i32 - padding - padding) as u16);
                 }
                 layout::Orientation::Vertical => {
-                    child.measure(cmp::max(0, width as i32 - DEFAULT_PADDING - DEFAULT_PADDING) as u16, cmp::max(0, *size) as u16);
+                    child.measure(cmp::max(0, width as i32 - padding - padding) as u16, cmp::max(0, *size) as u16);
                 }
             }
         }
@@ -243,19 +240,20 @@ impl ControlInner for WindowsSplitted {
 
         let self2: &mut Splitted = unsafe { mem::transmute(selfptr) };
         let (first_size, second_size) = self.children_sizes(control);
-
-        match self.orientation {
+        let padding = self.padding();
+        let (first, second) = match self.orientation {
             layout::Orientation::Horizontal => {
-                let h = utils::coord_to_size(control.measured.1 as i32 - DEFAULT_PADDING - DEFAULT_PADDING);
-                self.first.on_added_to_container(self2, DEFAULT_PADDING, DEFAULT_PADDING, first_size, h);
-                self.second.on_added_to_container(self2, DEFAULT_PADDING + DEFAULT_BOUND + first_size as i32, DEFAULT_PADDING, second_size, h);
+                let h = utils::coord_to_size(control.measured.1 as i32 - padding - padding);
+                ((first_size, h), (second_size, h))
             }
             layout::Orientation::Vertical => {
-                let w = utils::coord_to_size(control.measured.0 as i32 - DEFAULT_PADDING - DEFAULT_PADDING);
-                self.first.on_added_to_container(self2, DEFAULT_PADDING, DEFAULT_PADDING, w, first_size);
-                self.second.on_added_to_container(self2, DEFAULT_PADDING, DEFAULT_PADDING + DEFAULT_BOUND + first_size as i32, w, second_size);
+                let w = utils::coord_to_size(control.measured.0 as i32 - padding - padding);
+                ((w, first_size), (w, second_size))
             }
-        }
+        };
+        let origins = layout_engine::split_origins(self.orientation, padding, self.bound(), first);
+        self.first.on_added_to_container(self2, origins[0].0, origins[0].1, first.0, first.1);
+        self.second.on_added_to_container(self2, origins[1].0, origins[1].1, second.0, second.1);
         //self.draw_divider(control);
     }
     fn on_removed_from_container(&mut self, member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
@@ -283,7 +281,7 @@ impl HasLayoutInner for WindowsSplitted {
         self.base.invalidate();
     }
     fn layout_margin(&self, _member: &MemberBase) -> layout::BoundarySize {
-        layout::BoundarySize::AllTheSame(DEFAULT_PADDING)
+        layout::BoundarySize::AllTheSame(self.padding())
     }
 }
 
@@ -377,28 +375,14 @@ impl MultiContainerInner for WindowsSplitted {
                     let self2 = self.base.as_outer_mut();
                     let sizes = self.first.size();
                     self.first.on_removed_from_container(self2);
-                    child.on_added_to_container(self2, DEFAULT_PADDING, DEFAULT_PADDING, sizes.0, sizes.1);
+                    child.on_added_to_container(self2, self.padding(), self.padding(), sizes.0, sizes.1);
                 }
                 mem::swap(&mut self.first, &mut child);
             }
             1 => {
                 if !self.base.hwnd.is_null() {
                     let self2 = self.base.as_outer_mut();
-
-                    let mut x = DEFAULT_PADDING;
-                    let mut y = DEFAULT_PADDING;
-
-                    let (xx, yy) = self.first.size();
-                    match self.orientation {
-                        layout::Orientation::Horizontal => {
-                            x += xx as i32;
-                            x += DEFAULT_BOUND;
-                        }
-                        layout::Orientation::Vertical => {
-                            y += yy as i32;
-                            y += DEFAULT_BOUND;
-                        }
-                    }
+                    let (x, y) = layout_engine::split_origins(self.orientation, self.padding(), self.bound(), self.first.size())[1];
                     let sizes = self.second.size();
                     self.second.on_removed_from_container(self2);
                     child.on_added_to_container(self2, x, y, sizes.0, sizes.1);
@@ -448,66 +432,25 @@ impl Drawable for WindowsSplitted {
         //self.draw_children();
     }
     fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
-        use std::cmp::max;
-
-        let orientation = self.orientation;
         let old_size = control.measured;
-        let hp = DEFAULT_PADDING + DEFAULT_PADDING + if orientation == layout::Orientation::Horizontal { DEFAULT_BOUND } else { 0 };
-        let vp = DEFAULT_PADDING + DEFAULT_PADDING + if orientation == layout::Orientation::Vertical { DEFAULT_BOUND } else { 0 };
-        let (first_size, second_size) = self.children_sizes(control);
+        let shares = self.children_sizes(control);
+        let (padding, bound) = (self.padding(), self.bound());
         control.measured = match control.visibility {
             types::Visibility::Gone => (0, 0),
             _ => {
-                let mut measured = false;
-                let w = match control.layout.width {
-                    layout::Size::Exact(w) => w,
-                    layout::Size::MatchParent => parent_width,
-                    layout::Size::WrapContent => {
-                        let mut w = 0;
-                        for (size, child) in [(first_size, self.first.as_mut()), (second_size, self.second.as_mut())].iter_mut() {
-                            match orientation {
-                                layout::Orientation::Horizontal => {
-                                    let (cw, _, _) = child.measure(max(0, *size) as u16, max(0, parent_height as i32 - vp) as u16);
-                                    w += cw;
-                                }
-                                layout::Orientation::Vertical => {
-                                    let (cw, _, _) = child.measure(max(0, parent_width as i32 - hp) as u16, max(0, *size) as u16);
-                                    w = max(w, cw);
-                                }
-                            }
-                        }
-                        measured = true;
-                        max(0, w as i32 + hp) as u16
-                    }
-                };
-                let h = match control.layout.height {
-                    layout::Size::Exact(h) => h,
-                    layout::Size::MatchParent => parent_height,
-                    layout::Size::WrapContent => {
-                        let mut h = 0;
-                        for (size, child) in [(first_size, self.first.as_mut()), (second_size, self.second.as_mut())].iter_mut() {
-                            let ch = if measured {
-                                child.size().1
-                            } else {
-                                let (_, ch, _) = match orientation {
-                                    layout::Orientation::Horizontal => child.measure(max(0, *size) as u16, max(0, parent_height as i32 - vp) as u16),
-                                    layout::Orientation::Vertical => child.measure(max(0, parent_width as i32 - hp) as u16, max(0, *size) as u16),
-                                };
-                                ch
-                            };
-                            match orientation {
-                                layout::Orientation::Horizontal => {
-                                    h = max(h, ch);
-                                }
-                                layout::Orientation::Vertical => {
-                                    h += ch;
-                                }
-                            }
-                        }
-                        max(0, h as i32 + vp) as u16
-                    }
-                };
-                (w, h)
+                let mut children = [self.first.as_mut(), self.second.as_mut()];
+                layout_engine::measure_split(
+                    self.orientation,
+                    (control.layout.width, control.layout.height),
+                    (parent_width, parent_height),
+                    padding,
+                    bound,
+                    shares,
+                    |i, w, h| {
+                        let (cw, ch, _) = children[i].measure(w, h);
+                        (cw, ch)
+                    },
+                )
             }
         };
         (control.measured.0, control.measured.1, control.measured != old_size)
@@ -524,32 +467,24 @@ impl Spawnable for WindowsSplitted {
 
 unsafe fn register_window_class() -> Vec<u16> {
     let class_name = OsStr::new("PlyguiWin32Splitted").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-    let class = winuser::WNDCLASSEXW {
-        cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as minwindef::UINT,
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
         style: winuser::CS_DBLCLKS,
-        lpfnWndProc: Some(window_handler),
-        cbClsExtra: 0,
-        cbWndExtra: 0,
-        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
-        hIcon: winuser::LoadIconW(ptr::null_mut(), winuser::IDI_APPLICATION),
-        hCursor: winuser::LoadCursorW(ptr::null_mut(), winuser::IDC_ARROW),
-        hbrBackground: ptr::null_mut(),
-        lpszMenuName: ptr::null(),
-        lpszClassName: class_name.as_ptr(),
-        hIconSm: ptr::null_mut(),
-    };
-    winuser::RegisterClassExW(&class);
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
     class_name
 }
 
 unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
         if winuser::WM_CREATE == msg {
             let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
-            winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, cs.lpCreateParams as WinPtr);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
         }
-        return winuser::DefWindowProcW(hwnd, msg, wparam, lparam);
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
     }
     
     let s: &mut Splitted = mem::transmute(ww);
@@ -557,7 +492,7 @@ unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UIN
     if let Some(proc) = s.inner().inner().inner().inner().inner().base.proc_handler.as_proc() {
         proc(s2, msg, wparam, lparam)
     } else {
-        winuser::DefWindowProcW(hwnd, msg, wparam, lparam)
+        backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam)
     }
 }
 
@@ -570,8 +505,8 @@ unsafe extern "system" fn handler<T: controls::Splitted>(this: &mut Splitted, ms
             {
                 this.set_skip_draw(true);
                 {
-                    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
-                    let base = &mem::transmute::<WinPtr, &Splitted>(ww).inner().base;
+                    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+                    let base = &mem::transmute::<isize, &Splitted>(ww).inner().base;
                     let ll = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
                     ll.update_children_layout(base);
                     ll.draw_children();
@@ -583,7 +518,7 @@ unsafe extern "system" fn handler<T: controls::Splitted>(this: &mut Splitted, ms
             if msg != common::WM_UPDATE_INNER {
                 this.call_on_size::<T>(width, height);
             } else {
-                winuser::InvalidateRect(hwnd, ptr::null_mut(), minwindef::TRUE);
+                backend::current().invalidate(hwnd as usize, true);
             }
             return 0;
         }
@@ -593,51 +528,57 @@ unsafe extern "system" fn handler<T: controls::Splitted>(this: &mut Splitted, ms
             let mut updated = false;
 
             let (width, height) = this.inner().base.measured;
+            let bound = common::scaled(hwnd, DEFAULT_BOUND) as u16;
 
             match controls::HasOrientation::orientation(this) {
                 layout::Orientation::Horizontal => {
-                    if width >= DEFAULT_BOUND as u16 && x > DEFAULT_BOUND as u16 && x < (width - DEFAULT_BOUND as u16) {
-                        winuser::SetCursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cursor);
+                    if width >= bound && x > bound && x < (width - bound) {
+                        backend::current().set_cursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cursor as usize);
 
                         if wparam == winuser::MK_LBUTTON && true {
                             this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().splitter = x as f32 / width as f32;
                             updated = true;
                         }
                     } else {
-	                    winuser::SetCursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().default_cursor);	
+	                    backend::current().set_cursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().default_cursor as usize);	
                     }
                 }
                 layout::Orientation::Vertical => {
-                    if height >= DEFAULT_BOUND as u16 && y > DEFAULT_BOUND as u16 && y < (height - DEFAULT_BOUND as u16) {
-                        winuser::SetCursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cursor);
+                    if height >= bound && y > bound && y < (height - bound) {
+                        backend::current().set_cursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cursor as usize);
 
                         if wparam == winuser::MK_LBUTTON && this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().moving {
                             this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().splitter = y as f32 / height as f32;
                             updated = true;
                         }
                     } else {
-                    	winuser::SetCursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().default_cursor);
+                    	backend::current().set_cursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().default_cursor as usize);
                     }
                 }
             }
 
             if updated {
                 let packed = ((height as i32) << 16) + width as i32;
-                winuser::SendMessageW(hwnd, common::WM_UPDATE_INNER, 0, packed as isize);
+                backend::current().send_message(hwnd as usize, common::WM_UPDATE_INNER, 0, packed as isize);
             }
             return 0;
         }
         winuser::WM_LBUTTONDOWN => {
-            winuser::SetCursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cursor);
+            backend::current().set_cursor(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cursor as usize);
             this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().moving = true;
-            winuser::SetCapture(hwnd);
+            backend::current().set_capture(hwnd as usize);
             return 0;
         }
         winuser::WM_LBUTTONUP => {
-            winuser::ReleaseCapture();
+            backend::current().release_capture();
             this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().moving = false;
             return 0;
         }
+        winuser::WM_COMMAND | winuser::WM_NOTIFY | winuser::WM_HSCROLL | winuser::WM_VSCROLL => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
+        }
         winuser::WM_CTLCOLORLISTBOX | winuser::WM_CTLCOLORSTATIC => {
             let hdc = wparam as windef::HDC;
             //wingdi::SetTextColor(hdc, wingdi::RGB(0, 0, 0));
@@ -646,13 +587,13 @@ unsafe extern "system" fn handler<T: controls::Splitted>(this: &mut Splitted, ms
             return wingdi::GetStockObject(wingdi::NULL_BRUSH as i32) as isize;
         }
         winuser::WM_PAINT => {
-            let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
-            let base = &mem::transmute::<WinPtr, &Splitted>(ww).inner().base;
+            let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+            let base = &mem::transmute::<isize, &Splitted>(ww).inner().base;
             let this = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
             this.draw_divider(base);
         }
         _ => {}
     }
 
-    winuser::DefWindowProcW(hwnd, msg, wparam, lparam)
+    backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam)
 }
@@ -0,0 +1,563 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::dpi;
+
+const CLASS_ID: &str = commctrl::WC_LISTVIEW;
+
+lazy_static! {
+    pub static ref WINDOW_CLASS: Vec<u16> = OsStr::new(CLASS_ID).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+}
+
+pub type Table = AMember<AControl<WindowsTable>>;
+
+pub type OnRowCheck = Box<dyn FnMut(&mut Table, usize, bool)>;
+pub type OnTableSelectionChange = Box<dyn FnMut(&mut Table, &[usize])>;
+
+/// Source of the table cells. Rows are addressed by their adapter index, whatever order they are shown in.
+pub trait TableAdapter {
+    fn len(&self) -> usize;
+    fn text(&self, row: usize, column: usize) -> String;
+    /// Order of two rows by a column, used by header click sorting. Compares the cell texts by default.
+    fn compare(&self, a: usize, b: usize, column: usize) -> cmp::Ordering {
+        self.text(a, column).cmp(&self.text(b, column))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableColumn {
+    pub title: String,
+    /// At `dpi::BASE_DPI`, scaled to the monitor the table is on.
+    pub width: u16,
+}
+
+impl TableColumn {
+    pub fn new<S: AsRef<str>>(title: S, width: u16) -> TableColumn {
+        TableColumn { title: title.as_ref().into(), width }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[repr(C)]
+pub struct WindowsTable {
+    base: common::WindowsControlBase<Table>,
+    adapter: Box<dyn TableAdapter>,
+    columns: Vec<TableColumn>,
+    /// DPI the native columns are sized for.
+    columns_dpi: u32,
+    /// Adapter rows in the order they are shown.
+    order: Vec<usize>,
+    sorting: Option<(usize, SortOrder)>,
+    multi_select: bool,
+    check_boxes: bool,
+    checked: Vec<bool>,
+    on_row_check: Option<OnRowCheck>,
+    on_selection_change: Option<OnTableSelectionChange>,
+    skip_callbacks: bool,
+}
+
+impl WindowsTable {
+    fn with_uninit(_: &mut mem::MaybeUninit<Table>, columns: Vec<TableColumn>, adapter: Box<dyn TableAdapter>) -> Self {
+        let len = adapter.len();
+        WindowsTable {
+            base: common::WindowsControlBase::with_handler(Some(handler)),
+            adapter,
+            columns,
+            columns_dpi: dpi::BASE_DPI,
+            order: (0..len).collect(),
+            sorting: None,
+            multi_select: true,
+            check_boxes: false,
+            checked: vec![false; len],
+            on_row_check: None,
+            on_selection_change: None,
+            skip_callbacks: false,
+        }
+    }
+    pub fn with_adapter(columns: Vec<TableColumn>, adapter: Box<dyn TableAdapter>) -> Box<Table> {
+        let mut b: Box<mem::MaybeUninit<Table>> = Box::new_uninit();
+        let ab = AMember::with_inner(AControl::with_inner(Self::with_uninit(b.as_mut(), columns, adapter)));
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+
+    pub fn adapter(&self) -> &dyn TableAdapter {
+        self.adapter.as_ref()
+    }
+    /// Call `notify_data_changed` after changing the data.
+    pub fn adapter_mut(&mut self) -> &mut dyn TableAdapter {
+        self.adapter.as_mut()
+    }
+    /// Re-reads the row count and re-applies the sorting. Check marks are kept for the rows that still exist, selection is cleared.
+    pub fn notify_data_changed(&mut self) {
+        let len = self.adapter.len();
+        self.order = (0..len).collect();
+        self.checked.resize(len, false);
+        if let Some((column, order)) = self.sorting {
+            self.sort_rows(column, order);
+        }
+        if !self.base.hwnd.is_null() {
+            backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_SETITEMCOUNT, len, 0);
+            self.set_all_selected(false);
+            self.base.invalidate();
+        }
+    }
+
+    pub fn columns(&self) -> &[TableColumn] {
+        self.columns.as_slice()
+    }
+    /// Current width of a column, including the user resizing it, at `dpi::BASE_DPI`.
+    pub fn column_width(&self, column: usize) -> u16 {
+        if !self.base.hwnd.is_null() {
+            let width = backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_GETCOLUMNWIDTH, column, 0) as i32;
+            cmp::max(0, dpi::unscale(width, self.columns_dpi)) as u16
+        } else {
+            self.columns.get(column).map(|c| c.width).unwrap_or(0)
+        }
+    }
+    pub fn set_column_width(&mut self, column: usize, width: u16) {
+        if let Some(c) = self.columns.get_mut(column) {
+            c.width = width;
+            if !self.base.hwnd.is_null() {
+                backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_SETCOLUMNWIDTH, column, dpi::scale(width as i32, self.columns_dpi) as isize);
+            }
+        }
+    }
+    /// Columns in the order they are shown, as the user may drag them around.
+    pub fn column_order(&self) -> Vec<usize> {
+        let mut order: Vec<i32> = (0..self.columns.len() as i32).collect();
+        if !self.base.hwnd.is_null() && !order.is_empty() {
+            backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_GETCOLUMNORDERARRAY, order.len(), order.as_mut_ptr() as isize);
+        }
+        order.into_iter().map(|c| c as usize).collect()
+    }
+
+    pub fn sorting(&self) -> Option<(usize, SortOrder)> {
+        self.sorting
+    }
+    /// Sorts the rows the same way a header click does, keeping them selected. `None` restores the adapter order.
+    pub fn set_sorting(&mut self, sorting: Option<(usize, SortOrder)>) {
+        let selected = self.selected_rows();
+        self.sorting = sorting.filter(|(column, _)| *column < self.columns.len());
+        match self.sorting {
+            Some((column, order)) => self.sort_rows(column, order),
+            None => self.order = (0..self.adapter.len()).collect(),
+        }
+        if !self.base.hwnd.is_null() {
+            self.skip_callbacks = true;
+            self.set_all_selected(false);
+            for row in selected {
+                self.set_row_selected(row, true);
+            }
+            self.skip_callbacks = false;
+            self.update_header();
+            self.base.invalidate();
+        }
+    }
+
+    pub fn is_multi_select(&self) -> bool {
+        self.multi_select
+    }
+    pub fn set_multi_select(&mut self, multi_select: bool) {
+        if multi_select != self.multi_select {
+            self.multi_select = multi_select;
+            if !self.base.hwnd.is_null() {
+                let mut style = backend::current().window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE);
+                if multi_select {
+                    style &= !commctrl::LVS_SINGLESEL as isize;
+                } else {
+                    style |= commctrl::LVS_SINGLESEL as isize;
+                }
+                backend::current().set_window_long_ptr(self.base.hwnd as usize, winuser::GWL_STYLE, style);
+                if !multi_select {
+                    self.set_all_selected(false);
+                }
+            }
+        }
+    }
+    /// Selected rows, by adapter index, in the order they are shown.
+    pub fn selected_rows(&self) -> Vec<usize> {
+        let mut rows = vec![];
+        if !self.base.hwnd.is_null() {
+            let mut i = -1isize;
+            loop {
+                i = backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_GETNEXTITEM, i as usize, commctrl::LVNI_SELECTED);
+                if i < 0 {
+                    break;
+                }
+                if let Some(row) = self.order.get(i as usize) {
+                    rows.push(*row);
+                }
+            }
+        }
+        rows
+    }
+    /// Does not call `on_selection_change`.
+    pub fn set_row_selected(&mut self, row: usize, selected: bool) {
+        if let Some(position) = self.position_of(row) {
+            if selected && !self.multi_select {
+                self.set_all_selected(false);
+            }
+            self.set_state(position as isize, if selected { commctrl::LVIS_SELECTED } else { 0 }, commctrl::LVIS_SELECTED);
+            if selected {
+                backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_ENSUREVISIBLE, position, minwindef::FALSE as isize);
+            }
+        }
+    }
+
+    pub fn has_check_boxes(&self) -> bool {
+        self.check_boxes
+    }
+    pub fn set_check_boxes(&mut self, check_boxes: bool) {
+        if check_boxes != self.check_boxes {
+            self.check_boxes = check_boxes;
+            self.apply_extended_style();
+        }
+    }
+    pub fn is_row_checked(&self, row: usize) -> bool {
+        self.checked.get(row).cloned().unwrap_or(false)
+    }
+    /// Does not call `on_row_check`.
+    pub fn set_row_checked(&mut self, row: usize, checked: bool) {
+        if let Some(c) = self.checked.get_mut(row) {
+            *c = checked;
+        }
+        if let Some(position) = self.position_of(row) {
+            self.redraw_position(position);
+        }
+    }
+    pub fn checked_rows(&self) -> Vec<usize> {
+        self.checked.iter().enumerate().filter(|(_, c)| **c).map(|(row, _)| row).collect()
+    }
+
+    pub fn on_row_check(&mut self, callback: Option<OnRowCheck>) {
+        self.on_row_check = callback;
+    }
+    pub fn on_selection_change(&mut self, callback: Option<OnTableSelectionChange>) {
+        self.on_selection_change = callback;
+    }
+
+    fn sort_rows(&mut self, column: usize, order: SortOrder) {
+        let adapter = self.adapter.as_ref();
+        self.order.sort_by(|a, b| match order {
+            SortOrder::Ascending => adapter.compare(*a, *b, column),
+            SortOrder::Descending => adapter.compare(*b, *a, column),
+        });
+    }
+    fn position_of(&self, row: usize) -> Option<usize> {
+        if self.base.hwnd.is_null() {
+            None
+        } else {
+            self.order.iter().position(|r| *r == row)
+        }
+    }
+    fn set_state(&mut self, position: isize, state: minwindef::UINT, mask: minwindef::UINT) {
+        let mut item: commctrl::LVITEMW = unsafe { mem::zeroed() };
+        item.state = state;
+        item.stateMask = mask;
+        backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_SETITEMSTATE, position as usize, &mut item as *mut _ as isize);
+    }
+    fn set_all_selected(&mut self, selected: bool) {
+        self.set_state(-1, if selected { commctrl::LVIS_SELECTED } else { 0 }, commctrl::LVIS_SELECTED);
+    }
+    fn redraw_position(&mut self, position: usize) {
+        backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_REDRAWITEMS, position, position as isize);
+    }
+    fn toggle_checked(&mut self, positions: Vec<usize>) {
+        for position in positions {
+            if let Some(row) = self.order.get(position).cloned() {
+                let checked = !self.checked[row];
+                self.checked[row] = checked;
+                self.redraw_position(position);
+                let this = self.base.as_outer_mut();
+                if let Some(ref mut cb) = self.on_row_check {
+                    (cb.as_mut())(this, row, checked);
+                }
+            }
+        }
+    }
+    fn notify_selection(&mut self) {
+        if !self.skip_callbacks && self.on_selection_change.is_some() {
+            let rows = self.selected_rows();
+            let this = self.base.as_outer_mut();
+            if let Some(ref mut cb) = self.on_selection_change {
+                (cb.as_mut())(this, rows.as_slice());
+            }
+        }
+    }
+    fn apply_extended_style(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let mut style = commctrl::LVS_EX_FULLROWSELECT | commctrl::LVS_EX_HEADERDRAGDROP | commctrl::LVS_EX_DOUBLEBUFFER;
+            if self.check_boxes {
+                style |= commctrl::LVS_EX_CHECKBOXES;
+            }
+            backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_SETEXTENDEDLISTVIEWSTYLE, 0, style as isize);
+            // with owner data the check marks are kept here and handed out with the item texts
+            backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_SETCALLBACKMASK, if self.check_boxes { commctrl::LVIS_STATEIMAGEMASK as usize } else { 0 }, 0);
+            self.base.invalidate();
+        }
+    }
+    fn insert_columns(&mut self) {
+        self.columns_dpi = common::dpi(self.base.hwnd);
+        for (i, column) in self.columns.iter().enumerate() {
+            let mut title = common::str_to_wchar(column.title.as_str());
+            let mut lvc: commctrl::LVCOLUMNW = unsafe { mem::zeroed() };
+            lvc.mask = commctrl::LVCF_TEXT | commctrl::LVCF_WIDTH | commctrl::LVCF_FMT;
+            lvc.fmt = commctrl::LVCFMT_LEFT;
+            lvc.cx = dpi::scale(column.width as i32, self.columns_dpi);
+            lvc.pszText = title.as_mut_ptr();
+            unsafe {
+                if 0 > backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_INSERTCOLUMNW, i, &mut lvc as *mut _ as isize) {
+                    common::log_error();
+                }
+            }
+        }
+    }
+    /// Keeps the columns, resized by the user or not, as wide after the table moved to a monitor with another DPI.
+    fn rescale_columns(&mut self) {
+        let dpi = common::dpi(self.base.hwnd);
+        if dpi == self.columns_dpi {
+            return;
+        }
+        for column in 0..self.columns.len() {
+            let width = backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_GETCOLUMNWIDTH, column, 0) as i32;
+            backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_SETCOLUMNWIDTH, column, dpi::rescale(width, self.columns_dpi, dpi) as isize);
+        }
+        self.columns_dpi = dpi;
+    }
+    fn update_header(&mut self) {
+        unsafe {
+            let header = backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_GETHEADER, 0, 0) as windef::HWND;
+            if header.is_null() {
+                return;
+            }
+            for i in 0..self.columns.len() {
+                let mut hdi: commctrl::HDITEMW = mem::zeroed();
+                hdi.mask = commctrl::HDI_FORMAT;
+                backend::current().send_message(header as usize, commctrl::HDM_GETITEMW, i, &mut hdi as *mut _ as isize);
+                hdi.fmt &= !(commctrl::HDF_SORTUP | commctrl::HDF_SORTDOWN);
+                match self.sorting {
+                    Some((column, SortOrder::Ascending)) if column == i => hdi.fmt |= commctrl::HDF_SORTUP,
+                    Some((column, SortOrder::Descending)) if column == i => hdi.fmt |= commctrl::HDF_SORTDOWN,
+                    _ => {}
+                }
+                backend::current().send_message(header as usize, commctrl::HDM_SETITEMW, i, &mut hdi as *mut _ as isize);
+            }
+        }
+    }
+    fn on_column_click(&mut self, column: usize) {
+        let order = match self.sorting {
+            Some((sorted, SortOrder::Ascending)) if sorted == column => SortOrder::Descending,
+            _ => SortOrder::Ascending,
+        };
+        self.set_sorting(Some((column, order)));
+    }
+    fn fill_item(&self, item: &mut commctrl::LVITEMW) {
+        let row = match self.order.get(item.iItem as usize) {
+            Some(row) => *row,
+            None => return,
+        };
+        if item.mask & commctrl::LVIF_TEXT != 0 && item.cchTextMax > 0 {
+            let text = common::str_to_wchar(self.adapter.text(row, item.iSubItem as usize));
+            let len = cmp::min(text.len() - 1, item.cchTextMax as usize - 1);
+            unsafe {
+                ptr::copy_nonoverlapping(text.as_ptr(), item.pszText, len);
+                *item.pszText.add(len) = 0;
+            }
+        }
+        if item.mask & commctrl::LVIF_STATE != 0 && self.check_boxes {
+            item.state = commctrl::INDEXTOSTATEIMAGEMASK(if self.checked[row] { 2 } else { 1 });
+            item.stateMask |= commctrl::LVIS_STATEIMAGEMASK;
+        }
+    }
+}
+
+impl ControlInner for WindowsTable {
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, x: i32, y: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (w, h, _) = self.measure(member, control, pw, ph);
+        let mut style = commctrl::LVS_REPORT | commctrl::LVS_OWNERDATA | commctrl::LVS_SHOWSELALWAYS | winuser::WS_TABSTOP;
+        if !self.multi_select {
+            style |= commctrl::LVS_SINGLESEL;
+        }
+        self.base.create_control_hwnd(x as i32, y as i32, w as i32, h as i32, self.base.hwnd, winuser::WS_EX_CLIENTEDGE, WINDOW_CLASS.as_ptr(), "", style, selfptr);
+        self.apply_extended_style();
+        self.insert_columns();
+        backend::current().send_message(self.base.hwnd as usize, commctrl::LVM_SETITEMCOUNT, self.order.len(), 0);
+        self.update_header();
+    }
+    fn on_removed_from_container(&mut self, _member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        self.base.destroy_control_hwnd();
+    }
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+}
+
+impl HasLayoutInner for WindowsTable {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        self.base.invalidate();
+    }
+}
+
+impl HasNativeIdInner for WindowsTable {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+
+impl HasSizeInner for WindowsTable {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsTable {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl MemberInner for WindowsTable {}
+
+impl Drawable for WindowsTable {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let w = match control.layout.width {
+                    layout::Size::MatchParent => parent_width as i32,
+                    layout::Size::Exact(w) => w as i32,
+                    layout::Size::WrapContent => {
+                        self.columns.iter().map(|c| common::scaled(self.base.hwnd, c.width as i32)).sum::<i32>() + common::system_metric(self.base.hwnd, winuser::SM_CXVSCROLL) + common::default_padding(self.base.hwnd)
+                    }
+                };
+                let h = match control.layout.height {
+                    layout::Size::MatchParent => parent_height as i32,
+                    layout::Size::Exact(h) => h as i32,
+                    layout::Size::WrapContent => common::default_height(self.base.hwnd) * 8,
+                };
+                (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+
+struct EmptyAdapter;
+
+impl TableAdapter for EmptyAdapter {
+    fn len(&self) -> usize {
+        0
+    }
+    fn text(&self, _: usize, _: usize) -> String {
+        String::new()
+    }
+}
+
+impl Spawnable for WindowsTable {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_adapter(vec![], Box::new(EmptyAdapter)).into_control()
+    }
+}
+
+unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
+    }
+    match msg {
+        m if m == common::OCM_BASE + winuser::WM_NOTIFY => {
+            let table: &mut Table = mem::transmute(param);
+            let inner = table.inner_mut().inner_mut();
+            match (&*(lparam as winuser::LPNMHDR)).code {
+                commctrl::LVN_GETDISPINFOW => {
+                    let info = &mut *(lparam as *mut commctrl::NMLVDISPINFOW);
+                    inner.fill_item(&mut info.item);
+                }
+                commctrl::LVN_COLUMNCLICK => {
+                    let column = (&*(lparam as commctrl::LPNMLISTVIEW)).iSubItem;
+                    inner.on_column_click(column as usize);
+                }
+                commctrl::LVN_ITEMCHANGED => {
+                    let change = &*(lparam as commctrl::LPNMLISTVIEW);
+                    if change.uChanged & commctrl::LVIF_STATE != 0 && (change.uNewState ^ change.uOldState) & commctrl::LVIS_SELECTED != 0 {
+                        inner.notify_selection();
+                    }
+                }
+                commctrl::LVN_ODSTATECHANGED => {
+                    let change = &*(lparam as commctrl::LPNMLVODSTATECHANGE);
+                    if (change.uNewState ^ change.uOldState) & commctrl::LVIS_SELECTED != 0 {
+                        inner.notify_selection();
+                    }
+                }
+                commctrl::NM_CLICK | commctrl::NM_DBLCLK if inner.check_boxes => {
+                    let mut hit: commctrl::LVHITTESTINFO = mem::zeroed();
+                    hit.pt = (&*(lparam as commctrl::LPNMITEMACTIVATE)).ptAction;
+                    backend::current().send_message(hwnd as usize, commctrl::LVM_HITTEST, 0, &mut hit as *mut _ as isize);
+                    if hit.iItem >= 0 && hit.flags & commctrl::LVHT_ONITEMSTATEICON != 0 {
+                        inner.toggle_checked(vec![hit.iItem as usize]);
+                    }
+                }
+                commctrl::LVN_KEYDOWN if inner.check_boxes => {
+                    let key = (&*(lparam as commctrl::LPNMLVKEYDOWN)).wVKey;
+                    if key as i32 == winuser::VK_SPACE {
+                        let mut positions = vec![];
+                        let mut i = -1isize;
+                        loop {
+                            i = backend::current().send_message(hwnd as usize, commctrl::LVM_GETNEXTITEM, i as usize, commctrl::LVNI_SELECTED);
+                            if i < 0 {
+                                break;
+                            }
+                            positions.push(i as usize);
+                        }
+                        inner.toggle_checked(positions);
+                    }
+                }
+                _ => {}
+            }
+            return 0;
+        }
+        winuser::WM_SIZE => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+
+            let table: &mut Table = mem::transmute(param);
+            table.call_on_size::<Table>(width, height);
+        }
+        winuser::WM_DPICHANGED_AFTERPARENT => {
+            let table: &mut Table = mem::transmute(param);
+            table.inner_mut().inner_mut().rescale_columns();
+        }
+        _ => {}
+    }
+
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
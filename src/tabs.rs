@@ -0,0 +1,711 @@
+use crate::backend;
+use crate::common::{self, *};
+
+lazy_static! {
+    pub static ref WINDOW_CLASS_TABS: Vec<u16> = OsStr::new(commctrl::WC_TABCONTROL).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    pub static ref WINDOW_CLASS: Vec<u16> = unsafe { register_window_class() };
+}
+
+pub type Tabs = AMember<AControl<AContainer<AMultiContainer<WindowsTabs>>>>;
+
+pub type OnTabChange = Box<dyn FnMut(&mut Tabs, usize)>;
+/// Returns whether the page may be closed.
+pub type OnTabClose = Box<dyn FnMut(&mut Tabs, usize) -> bool>;
+
+struct TabPage {
+    control: Box<dyn controls::Control>,
+    label: String,
+    icon: Option<image::DynamicImage>,
+    image: i32,
+    closable: bool,
+}
+
+impl TabPage {
+    fn with_control(control: Box<dyn controls::Control>) -> TabPage {
+        TabPage {
+            control,
+            label: String::new(),
+            icon: None,
+            image: -1,
+            closable: false,
+        }
+    }
+}
+
+/// Pages are siblings of the tab control, placed over its display area. Only the selected page is shown.
+/// Tabs are owner-drawn, so that closable ones get a close button of the system's own look.
+#[repr(C)]
+pub struct WindowsTabs {
+    base: WindowsControlBase<Tabs>,
+    hwnd_tabs: windef::HWND,
+    images: commctrl::HIMAGELIST,
+    pages: Vec<TabPage>,
+    selected: usize,
+    on_tab_change: Option<OnTabChange>,
+    on_tab_close: Option<OnTabClose>,
+}
+
+impl WindowsTabs {
+    fn with_uninit(_: &mut mem::MaybeUninit<Tabs>) -> Self {
+        WindowsTabs {
+            base: WindowsControlBase::with_wndproc(Some(handler)),
+            hwnd_tabs: ptr::null_mut(),
+            images: ptr::null_mut(),
+            pages: Vec::new(),
+            selected: 0,
+            on_tab_change: None,
+            on_tab_close: None,
+        }
+    }
+    pub fn new() -> Box<Tabs> {
+        let mut b: Box<mem::MaybeUninit<Tabs>> = Box::new_uninit();
+        let ab = AMember::with_inner(AControl::with_inner(AContainer::with_inner(AMultiContainer::with_inner(Self::with_uninit(b.as_mut())))));
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        if self.pages.is_empty() {
+            None
+        } else {
+            Some(self.selected)
+        }
+    }
+    /// Does not call `on_tab_change`.
+    pub fn select(&mut self, index: usize) {
+        if index < self.pages.len() && index != self.selected {
+            self.selected = index;
+            if !self.hwnd_tabs.is_null() {
+                backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_SETCURSEL, index, 0);
+                self.update_children_layout();
+            }
+        }
+    }
+    pub fn tab_label(&self, index: usize) -> Option<&str> {
+        self.pages.get(index).map(|p| p.label.as_str())
+    }
+    pub fn set_tab_label<S: AsRef<str>>(&mut self, index: usize, label: S) {
+        if let Some(page) = self.pages.get_mut(index) {
+            page.label = label.as_ref().into();
+            self.update_tab(index);
+        }
+    }
+    pub fn tab_icon(&self, index: usize) -> Option<&image::DynamicImage> {
+        self.pages.get(index).and_then(|p| p.icon.as_ref())
+    }
+    pub fn set_tab_icon(&mut self, index: usize, icon: Option<image::DynamicImage>) {
+        if let Some(page) = self.pages.get_mut(index) {
+            page.icon = icon;
+            self.update_image(index);
+            self.update_tab(index);
+        }
+    }
+    pub fn is_tab_closable(&self, index: usize) -> bool {
+        self.pages.get(index).map(|p| p.closable).unwrap_or(false)
+    }
+    /// Closable tabs get a close button, and close on a middle click as well.
+    pub fn set_tab_closable(&mut self, index: usize, closable: bool) {
+        if let Some(page) = self.pages.get_mut(index) {
+            page.closable = closable;
+            self.update_padding();
+            self.update_tab(index);
+        }
+    }
+    pub fn on_tab_change(&mut self, callback: Option<OnTabChange>) {
+        self.on_tab_change = callback;
+    }
+    /// Called before a closable tab is closed by the user. Without a callback, tabs are closed right away.
+    pub fn on_tab_close(&mut self, callback: Option<OnTabClose>) {
+        self.on_tab_close = callback;
+    }
+
+    fn update_children_layout(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let (width, height) = common::size_hwnd(self.base.hwnd);
+            let packed = ((height as i32) << 16) + width as i32;
+            backend::current().send_message(self.base.hwnd as usize, common::WM_UPDATE_INNER, 0, packed as isize);
+        }
+    }
+    fn layout_pages(&mut self, width: u16, height: u16) {
+        backend::current().set_window_pos(self.hwnd_tabs as usize, 0, 0, width as i32, height as i32, winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE);
+        let (x, y, w, h) = display_rect(self.hwnd_tabs, width, height);
+        let selected = self.selected;
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            page.control.measure(w, h);
+            page.control.draw(Some((x, y)));
+            unsafe {
+                backend::current().show_window(page.control.native_id(), i == selected);
+            }
+        }
+    }
+    fn tab_item(&self, index: usize, label: &mut Vec<u16>) -> commctrl::TCITEMW {
+        let page = &self.pages[index];
+        *label = common::str_to_wchar(page.label.as_str());
+        commctrl::TCITEMW {
+            mask: commctrl::TCIF_TEXT | commctrl::TCIF_IMAGE,
+            dwState: 0,
+            dwStateMask: 0,
+            pszText: label.as_mut_ptr(),
+            cchTextMax: label.len() as i32,
+            iImage: page.image,
+            lParam: 0,
+        }
+    }
+    fn update_tab(&mut self, index: usize) {
+        if !self.hwnd_tabs.is_null() {
+            let mut label = Vec::new();
+            let mut item = self.tab_item(index, &mut label);
+            unsafe {
+                if 0 == backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_SETITEMW, index, &mut item as *mut _ as isize) {
+                    common::log_error();
+                }
+            }
+            self.update_children_layout();
+        }
+    }
+    /// The tab control sizes owner-drawn tabs from their label and icon only, so the room for the close buttons
+    /// is in the padding, which is the same for all tabs.
+    fn update_padding(&self) {
+        if self.hwnd_tabs.is_null() {
+            return;
+        }
+        let padding = common::default_padding(self.hwnd_tabs);
+        let close = if self.pages.iter().any(|p| p.closable) { close_side(self.hwnd_tabs) + padding } else { 0 };
+        // the padding is added at both sides of a tab, while the label is drawn right after the left one
+        let horizontal = padding + (close + 1) / 2;
+        backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_SETPADDING, 0, minwindef::MAKELONG(horizontal as u16, (padding / 2) as u16) as isize);
+    }
+    fn draw_tab(&self, item: &winuser::DRAWITEMSTRUCT) {
+        let page = match self.pages.get(item.itemID as usize) {
+            Some(page) => page,
+            None => return,
+        };
+        let padding = common::default_padding(self.hwnd_tabs);
+        let mut rc = item.rcItem;
+        rc.left += padding;
+        unsafe {
+            let font = backend::current().send_message(self.hwnd_tabs as usize, winuser::WM_GETFONT, 0, 0) as windef::HGDIOBJ;
+            let old_font = wingdi::SelectObject(item.hDC, font);
+            wingdi::SetBkMode(item.hDC, wingdi::TRANSPARENT as i32);
+            wingdi::SetTextColor(item.hDC, winuser::GetSysColor(winuser::COLOR_BTNTEXT));
+            if page.image >= 0 {
+                let side = common::system_metric(self.hwnd_tabs, winuser::SM_CXSMICON);
+                commctrl::ImageList_Draw(self.images, page.image, item.hDC, rc.left, rc.top + (rc.bottom - rc.top - side) / 2, commctrl::ILD_TRANSPARENT);
+                rc.left += side + padding;
+            }
+            if page.closable {
+                let mut close = close_rect(self.hwnd_tabs, &item.rcItem);
+                winuser::DrawFrameControl(item.hDC, &mut close, winuser::DFC_CAPTION, winuser::DFCS_CAPTIONCLOSE | winuser::DFCS_FLAT);
+                rc.right = close.left - padding;
+            }
+            let label = OsStr::new(page.label.as_str()).encode_wide().collect::<Vec<_>>();
+            winuser::DrawTextW(item.hDC, label.as_ptr(), label.len() as i32, &mut rc, winuser::DT_LEFT | winuser::DT_VCENTER | winuser::DT_SINGLELINE | winuser::DT_END_ELLIPSIS);
+            wingdi::SelectObject(item.hDC, old_font);
+        }
+    }
+    fn update_image(&mut self, index: usize) {
+        if self.images.is_null() {
+            return;
+        }
+        if self.pages[index].icon.is_none() {
+            self.remove_image(index);
+            return;
+        }
+        let size = common::system_metric(self.base.hwnd, winuser::SM_CXSMICON) as u32;
+        let page = &mut self.pages[index];
+        if let Some(ref icon) = page.icon {
+            unsafe {
+                let mut bitmap = ptr::null_mut();
+                common::image_to_native(&icon.resize_exact(size, size, image::imageops::FilterType::Lanczos3), &mut bitmap);
+                if page.image < 0 {
+                    page.image = commctrl::ImageList_Add(self.images, bitmap, ptr::null_mut());
+                } else if 0 == commctrl::ImageList_Replace(self.images, page.image, bitmap, ptr::null_mut()) {
+                    common::log_error();
+                }
+                wingdi::DeleteObject(bitmap as *mut c_void);
+            }
+        }
+    }
+    fn remove_image(&mut self, index: usize) {
+        let removed = self.pages[index].image;
+        if removed < 0 || self.images.is_null() {
+            return;
+        }
+        self.pages[index].image = -1;
+        unsafe {
+            commctrl::ImageList_Remove(self.images, removed);
+        }
+        // images after the removed one move down, and so do the indices of their tabs
+        for i in 0..self.pages.len() {
+            if self.pages[i].image > removed {
+                self.pages[i].image -= 1;
+                self.update_tab(i);
+            }
+        }
+    }
+    fn insert_tab(&mut self, index: usize) {
+        let mut label = Vec::new();
+        let mut item = self.tab_item(index, &mut label);
+        unsafe {
+            if index as isize != backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_INSERTITEMW, index, &mut item as *mut _ as isize) {
+                common::log_error();
+            }
+        }
+    }
+    fn remove_page(&mut self, index: usize) -> Option<Box<dyn controls::Control>> {
+        if index >= self.pages.len() {
+            return None;
+        }
+        self.remove_image(index);
+        let mut page = self.pages.remove(index);
+        if index < self.selected || (index == self.selected && self.selected >= self.pages.len() && self.selected > 0) {
+            self.selected -= 1;
+        }
+        if !self.base.hwnd.is_null() {
+            page.control.on_removed_from_container(self.base.as_outer_mut());
+            backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_DELETEITEM, index, 0);
+            backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_SETCURSEL, self.selected, 0);
+            self.update_children_layout();
+        }
+        Some(page.control)
+    }
+    /// `remove_page` for good, calling `on_tab_change` when another page is shown in place of the removed one.
+    fn close_page(&mut self, index: usize) -> Option<Box<dyn controls::Control>> {
+        let was_selected = self.selected() == Some(index);
+        let removed = self.remove_page(index);
+        self.update_padding();
+        if was_selected && removed.is_some() && !self.pages.is_empty() {
+            let selected = self.selected;
+            let this = self.base.as_outer_mut();
+            if let Some(ref mut cb) = self.on_tab_change {
+                (cb.as_mut())(this, selected);
+            }
+        }
+        removed
+    }
+    fn request_close(&mut self, index: usize) {
+        if !self.pages.get(index).map(|p| p.closable).unwrap_or(false) {
+            return;
+        }
+        let this = self.base.as_outer_mut();
+        let allowed = match self.on_tab_close {
+            Some(ref mut cb) => (cb.as_mut())(this, index),
+            None => true,
+        };
+        if allowed {
+            self.close_page(index);
+        }
+    }
+    fn on_selection_changed(&mut self) {
+        let index = backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_GETCURSEL, 0, 0);
+        if index < 0 || index as usize == self.selected {
+            return;
+        }
+        self.selected = index as usize;
+        let (width, height) = common::size_hwnd(self.base.hwnd);
+        self.layout_pages(width, height);
+        let this = self.base.as_outer_mut();
+        if let Some(ref mut cb) = self.on_tab_change {
+            (cb.as_mut())(this, index as usize);
+        }
+    }
+}
+
+impl MultiContainerInner for WindowsTabs {
+    fn len(&self) -> usize {
+        self.pages.len()
+    }
+    fn set_child_to(&mut self, _base: &mut MemberBase, index: usize, child: Box<dyn controls::Control>) -> Option<Box<dyn controls::Control>> {
+        let replaced = self.pages.get(index).map(|p| (p.label.clone(), p.icon.clone(), p.closable));
+        let keep_selected = replaced.is_some() && index == self.selected;
+        let old = self.remove_page(index);
+        let index = cmp::min(index, self.pages.len());
+
+        let mut page = TabPage::with_control(child);
+        if let Some((label, icon, closable)) = replaced {
+            page.label = label;
+            page.icon = icon;
+            page.closable = closable;
+        }
+        self.pages.insert(index, page);
+        self.update_padding();
+        if keep_selected {
+            self.selected = index;
+        } else if self.pages.len() > 1 && index <= self.selected {
+            self.selected += 1;
+        }
+        if !self.base.hwnd.is_null() {
+            self.update_image(index);
+            self.insert_tab(index);
+            let (w, h) = common::size_hwnd(self.base.hwnd);
+            let (x, y, w, h) = display_rect(self.hwnd_tabs, w, h);
+            self.pages[index].control.on_added_to_container(self.base.as_outer_mut(), x, y, w, h);
+            backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_SETCURSEL, self.selected, 0);
+            self.update_children_layout();
+        }
+        old
+    }
+    fn remove_child_from(&mut self, _base: &mut MemberBase, index: usize) -> Option<Box<dyn controls::Control>> {
+        self.close_page(index)
+    }
+    fn child_at(&self, index: usize) -> Option<&dyn controls::Control> {
+        self.pages.get(index).map(|p| p.control.as_ref())
+    }
+    fn child_at_mut(&mut self, index: usize) -> Option<&mut dyn controls::Control> {
+        if let Some(p) = self.pages.get_mut(index) {
+            Some(p.control.as_mut())
+        } else {
+            None
+        }
+    }
+}
+impl ControlInner for WindowsTabs {
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, px: i32, py: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (width, height, _) = self.measure(member, control, pw, ph);
+        let (hwnd, hwnd_tabs, id) = unsafe {
+            let (hwnd, id) = common::create_control_hwnd(
+                px,
+                py,
+                width as i32,
+                height as i32,
+                parent.native_id() as windef::HWND,
+                winuser::WS_EX_CONTROLPARENT | winuser::WS_CLIPCHILDREN,
+                WINDOW_CLASS.as_ptr(),
+                "",
+                0,
+                selfptr,
+                None,
+            );
+            let hwnd_tabs = common::create_part_hwnd(
+                (0, 0, width as i32, height as i32),
+                hwnd,
+                0,
+                WINDOW_CLASS_TABS.as_ptr(),
+                &[0],
+                winuser::WS_CLIPSIBLINGS | winuser::WS_TABSTOP | commctrl::TCS_FOCUSONBUTTONDOWN | commctrl::TCS_OWNERDRAWFIXED,
+                selfptr,
+                Some(tabs_handler),
+            );
+            backend::current().send_message(hwnd_tabs as usize, winuser::WM_SETFONT, backend::current().send_message(hwnd as usize, winuser::WM_GETFONT, 0, 0) as usize, minwindef::FALSE as isize);
+            (hwnd, hwnd_tabs, id)
+        };
+        self.base.hwnd = hwnd;
+        self.hwnd_tabs = hwnd_tabs;
+        self.base.subclass_id = id;
+        control.coords = Some((px, py));
+
+        unsafe {
+            let size = common::system_metric(self.base.hwnd, winuser::SM_CXSMICON);
+            self.images = commctrl::ImageList_Create(size, size, commctrl::ILC_COLOR32, 0, 1);
+            backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_SETIMAGELIST, 0, self.images as isize);
+        }
+        self.update_padding();
+        for i in 0..self.pages.len() {
+            self.update_image(i);
+            self.insert_tab(i);
+        }
+        backend::current().send_message(self.hwnd_tabs as usize, commctrl::TCM_SETCURSEL, self.selected, 0);
+        let (x, y, w, h) = display_rect(self.hwnd_tabs, width, height);
+        for page in self.pages.iter_mut() {
+            let self2: &mut Tabs = unsafe { utils::base_to_impl_mut(member) };
+            page.control.on_added_to_container(self2, x, y, w, h);
+        }
+        self.layout_pages(width, height);
+    }
+    fn on_removed_from_container(&mut self, member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        for page in self.pages.iter_mut() {
+            let self2: &mut Tabs = unsafe { utils::base_to_impl_mut(member) };
+            page.control.on_removed_from_container(self2);
+            page.image = -1;
+        }
+        unsafe {
+            commctrl::ImageList_Destroy(self.images);
+        }
+        common::destroy_hwnd(self.hwnd_tabs, common::subclass_id(WINDOW_CLASS_TABS.as_ptr()) as usize, Some(tabs_handler));
+        self.base.destroy_control_hwnd();
+        self.hwnd_tabs = ptr::null_mut();
+        self.images = ptr::null_mut();
+    }
+}
+impl HasLayoutInner for WindowsTabs {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        let hwnd = self.base.hwnd;
+        if !hwnd.is_null() {
+            self.base.invalidate();
+        }
+    }
+}
+impl HasNativeIdInner for WindowsTabs {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+impl MemberInner for WindowsTabs {}
+
+impl HasSizeInner for WindowsTabs {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsTabs {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl ContainerInner for WindowsTabs {
+    fn find_control_mut<'a>(&'a mut self, arg: types::FindBy<'a>) -> Option<&'a mut dyn controls::Control> {
+        for page in self.pages.as_mut_slice() {
+            let child = &mut page.control;
+            match arg {
+                types::FindBy::Id(ref id) => {
+                    if child.as_member_mut().id() == *id {
+                        return Some(child.as_mut());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member_mut().tag() {
+                        if tag == mytag {
+                            return Some(child.as_mut());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container_mut() {
+                let ret = c.find_control_mut(arg.clone());
+                if ret.is_none() {
+                    continue;
+                }
+                return ret;
+            }
+        }
+        None
+    }
+    fn find_control<'a>(&'a self, arg: types::FindBy<'a>) -> Option<&'a dyn controls::Control> {
+        for page in self.pages.as_slice() {
+            let child = &page.control;
+            match arg {
+                types::FindBy::Id(ref id) => {
+                    if child.as_member().id() == *id {
+                        return Some(child.as_ref());
+                    }
+                }
+                types::FindBy::Tag(tag) => {
+                    if let Some(mytag) = child.as_member().tag() {
+                        if tag == mytag {
+                            return Some(child.as_ref());
+                        }
+                    }
+                }
+            }
+            if let Some(c) = child.is_container() {
+                let ret = c.find_control(arg.clone());
+                if ret.is_none() {
+                    continue;
+                }
+                return ret;
+            }
+        }
+        None
+    }
+}
+
+impl Drawable for WindowsTabs {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let (_, _, w, h) = display_rect(self.hwnd_tabs, parent_width, parent_height);
+                let wraps = |size: layout::Size| if let layout::Size::WrapContent = size { true } else { false };
+                let mut content = (0, 0);
+                if wraps(control.layout.width) || wraps(control.layout.height) {
+                    for page in self.pages.iter_mut() {
+                        let (cw, ch, _) = page.control.measure(w, h);
+                        content = (cmp::max(content.0, cw), cmp::max(content.1, ch));
+                    }
+                }
+                let width = match control.layout.width {
+                    layout::Size::MatchParent => parent_width,
+                    layout::Size::Exact(w) => w,
+                    layout::Size::WrapContent => utils::coord_to_size(content.0 as i32 + parent_width as i32 - w as i32),
+                };
+                let height = match control.layout.height {
+                    layout::Size::MatchParent => parent_height,
+                    layout::Size::Exact(h) => h,
+                    layout::Size::WrapContent => utils::coord_to_size(content.1 as i32 + parent_height as i32 - h as i32),
+                };
+                (width, height)
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+impl Spawnable for WindowsTabs {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::new().into_control()
+    }
+}
+
+/// Origin and size of the page area within a tab control of the given size.
+fn display_rect(hwnd_tabs: windef::HWND, width: u16, height: u16) -> (i32, i32, u16, u16) {
+    let mut rc = windef::RECT {
+        left: 0,
+        top: 0,
+        right: width as i32,
+        bottom: height as i32,
+    };
+    if hwnd_tabs.is_null() {
+        // no tab control to ask yet, so a guess of one row of tabs
+        let padding = common::default_padding(hwnd_tabs);
+        rc.left += padding;
+        rc.top += common::default_height(hwnd_tabs) + padding;
+        rc.right -= padding;
+        rc.bottom -= padding;
+    } else {
+        backend::current().send_message(hwnd_tabs as usize, commctrl::TCM_ADJUSTRECT, minwindef::FALSE as usize, &mut rc as *mut _ as isize);
+    }
+    (rc.left, rc.top, utils::coord_to_size(rc.right - rc.left), utils::coord_to_size(rc.bottom - rc.top))
+}
+fn close_side(hwnd_tabs: windef::HWND) -> i32 {
+    common::system_metric(hwnd_tabs, winuser::SM_CXMENUCHECK)
+}
+/// Where the close button of a tab occupying `tab` is drawn and clicked.
+fn close_rect(hwnd_tabs: windef::HWND, tab: &windef::RECT) -> windef::RECT {
+    let padding = common::default_padding(hwnd_tabs);
+    let side = cmp::max(0, cmp::min(close_side(hwnd_tabs), tab.bottom - tab.top - padding));
+    let top = tab.top + (tab.bottom - tab.top - side) / 2;
+    windef::RECT {
+        left: tab.right - padding - side,
+        top,
+        right: tab.right - padding,
+        bottom: top + side,
+    }
+}
+unsafe fn tab_rect(hwnd_tabs: windef::HWND, index: usize) -> windef::RECT {
+    let mut rc: windef::RECT = mem::zeroed();
+    backend::current().send_message(hwnd_tabs as usize, commctrl::TCM_GETITEMRECT, index, &mut rc as *mut _ as isize);
+    rc
+}
+unsafe fn register_window_class() -> Vec<u16> {
+    let class_name = OsStr::new("PlyguiWin32Tabs").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
+        style: winuser::CS_DBLCLKS,
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
+    class_name
+}
+
+unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        if winuser::WM_CREATE == msg {
+            let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
+        }
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
+    }
+
+    let tabs: &mut Tabs = mem::transmute(ww);
+    let tabs2: &mut Tabs = mem::transmute(ww);
+    tabs.inner().inner().inner().inner().base.proc_handler.as_proc().unwrap()(tabs2, msg, wparam, lparam)
+}
+
+unsafe extern "system" fn handler(this: &mut Tabs, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
+    let hwnd = this.inner().inner().inner().inner().base.hwnd;
+    match msg {
+        winuser::WM_SIZE | common::WM_UPDATE_INNER => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+            this.inner_mut().inner_mut().inner_mut().inner_mut().layout_pages(width, height);
+
+            if msg != common::WM_UPDATE_INNER {
+                this.call_on_size::<Tabs>(width, height);
+            }
+            return 0;
+        }
+        winuser::WM_DRAWITEM if (&*(lparam as winuser::LPDRAWITEMSTRUCT)).hwndItem == this.inner().inner().inner().inner().hwnd_tabs => {
+            this.inner().inner().inner().inner().draw_tab(&*(lparam as winuser::LPDRAWITEMSTRUCT));
+            return minwindef::TRUE as isize;
+        }
+        winuser::WM_NOTIFY if (&*(lparam as winuser::LPNMHDR)).hwndFrom == this.inner().inner().inner().inner().hwnd_tabs => {
+            if (&*(lparam as winuser::LPNMHDR)).code == commctrl::TCN_SELCHANGE {
+                this.inner_mut().inner_mut().inner_mut().inner_mut().on_selection_changed();
+            }
+            return 0;
+        }
+        winuser::WM_COMMAND | winuser::WM_NOTIFY | winuser::WM_HSCROLL | winuser::WM_VSCROLL => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
+        }
+        winuser::WM_CTLCOLORLISTBOX | winuser::WM_CTLCOLORSTATIC => {
+            let hdc = wparam as windef::HDC;
+            wingdi::SetBkMode(hdc, wingdi::TRANSPARENT as i32);
+
+            return wingdi::GetStockObject(wingdi::NULL_BRUSH as i32) as isize;
+        }
+        _ => {}
+    }
+
+    backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam)
+}
+
+unsafe extern "system" fn tabs_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    match msg {
+        winuser::WM_LBUTTONDOWN | winuser::WM_MBUTTONUP => {
+            let mut hit: commctrl::TCHITTESTINFO = mem::zeroed();
+            hit.pt = point_from_lparam(lparam);
+            let index = backend::current().send_message(hwnd as usize, commctrl::TCM_HITTEST, 0, &mut hit as *mut _ as isize);
+            if index >= 0 {
+                let index = index as usize;
+                let tabs: &mut Tabs = mem::transmute(param);
+                let closable = tabs.inner().inner().inner().inner().is_tab_closable(index);
+                let on_close_button = closable && winuser::PtInRect(&close_rect(hwnd, &tab_rect(hwnd, index)), hit.pt) != 0;
+                if msg == winuser::WM_MBUTTONUP || on_close_button {
+                    // handled before the tab control sees the press, as it would switch to the tab being closed
+                    tabs.inner_mut().inner_mut().inner_mut().inner_mut().request_close(index);
+                    return 0;
+                }
+            }
+        }
+        _ => {}
+    }
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
+
+fn point_from_lparam(lparam: minwindef::LPARAM) -> windef::POINT {
+    windef::POINT {
+        x: minwindef::LOWORD(lparam as u32) as i16 as i32,
+        y: minwindef::HIWORD(lparam as u32) as i16 as i32,
+    }
+}
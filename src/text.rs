@@ -1,19 +1,211 @@
+use crate::backend;
 use crate::common::{self, *};
+use crate::font::Font;
 
 const CLASS_ID: &str = "static";
+const CLASS_ID_SELECTABLE: &str = "Edit";
 
 lazy_static! {
     pub static ref WINDOW_CLASS: Vec<u16> = OsStr::new(CLASS_ID).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+    pub static ref WINDOW_CLASS_SELECTABLE: Vec<u16> = OsStr::new(CLASS_ID_SELECTABLE).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
 }
 
 pub type Text = AMember<AControl<AText<WindowsText>>>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HorizontalAlignment {
+    Left,
+    Center,
+    Right,
+}
+impl Default for HorizontalAlignment {
+    fn default() -> HorizontalAlignment {
+        HorizontalAlignment::Left
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerticalAlignment {
+    Top,
+    Middle,
+    Bottom,
+}
+impl Default for VerticalAlignment {
+    fn default() -> VerticalAlignment {
+        VerticalAlignment::Top
+    }
+}
+
+/// What becomes of text wider than the control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextOverflow {
+    /// Lines are broken between words, the text growing in height instead.
+    Wrap,
+    /// A single line, cut at the right edge.
+    Clip,
+    /// A single line, ending with an ellipsis where it is cut.
+    EndEllipsis,
+    /// A single line with the middle of a path replaced by an ellipsis, keeping the file name after the last backslash.
+    PathEllipsis,
+}
+impl Default for TextOverflow {
+    fn default() -> TextOverflow {
+        TextOverflow::Wrap
+    }
+}
+
 #[repr(C)]
 pub struct WindowsText {
     base: common::WindowsControlBase<Text>,
     text: String,
+    h_alignment: HorizontalAlignment,
+    v_alignment: VerticalAlignment,
+    overflow: TextOverflow,
+    selectable: bool,
 }
 
+impl WindowsText {
+    pub fn font(&self) -> Option<&Font> {
+        self.base.font()
+    }
+    /// `None` brings back the default font.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.base.set_font(font);
+    }
+    pub fn alignment(&self) -> (HorizontalAlignment, VerticalAlignment) {
+        (self.h_alignment, self.v_alignment)
+    }
+    pub fn set_alignment(&mut self, horizontal: HorizontalAlignment, vertical: VerticalAlignment) {
+        self.h_alignment = horizontal;
+        self.v_alignment = vertical;
+        self.update_window();
+    }
+    pub fn overflow(&self) -> TextOverflow {
+        self.overflow
+    }
+    pub fn set_overflow(&mut self, overflow: TextOverflow) {
+        self.overflow = overflow;
+        self.update_window();
+    }
+    pub fn is_selectable(&self) -> bool {
+        self.selectable
+    }
+    /// Selectable text can be selected and copied with the mouse and keyboard. It is shown by a read-only edit control, which
+    /// aligns it always to the top and cuts it without ellipsis.
+    pub fn set_selectable(&mut self, selectable: bool) {
+        if self.selectable != selectable {
+            self.selectable = selectable;
+            self.recreate_window();
+        }
+    }
+
+    fn window_class(&self) -> ntdef::LPCWSTR {
+        if self.selectable {
+            WINDOW_CLASS_SELECTABLE.as_ptr()
+        } else {
+            WINDOW_CLASS.as_ptr()
+        }
+    }
+    fn style(&self) -> minwindef::DWORD {
+        if !self.selectable {
+            // drawn by `paint`, the style only keeps the static from taking `&` for a mnemonic
+            return winuser::WS_TABSTOP | winuser::SS_NOPREFIX;
+        }
+        let mut style = winuser::WS_TABSTOP | winuser::ES_READONLY | winuser::ES_MULTILINE;
+        style |= match self.h_alignment {
+            HorizontalAlignment::Left => winuser::ES_LEFT,
+            HorizontalAlignment::Center => winuser::ES_CENTER,
+            HorizontalAlignment::Right => winuser::ES_RIGHT,
+        };
+        if self.overflow != TextOverflow::Wrap {
+            style |= winuser::ES_AUTOHSCROLL;
+        }
+        style
+    }
+    /// The edit control wants Windows line ends.
+    fn window_text(&self) -> Cow<str> {
+        if self.selectable {
+            Cow::Owned(self.text.replace("\r\n", "\n").replace('\n', "\r\n"))
+        } else {
+            Cow::Borrowed(self.text.as_str())
+        }
+    }
+    fn create_window(&mut self, x: i32, y: i32, w: i32, h: i32, parent: windef::HWND, selfptr: *mut c_void) {
+        let (class, text, style) = (self.window_class(), self.window_text().into_owned(), self.style());
+        self.base.create_control_hwnd(x, y, w, h, parent, 0, class, text.as_str(), style, selfptr);
+        if self.selectable {
+            backend::current().send_message(self.base.hwnd as usize, winuser::EM_SETMARGINS as u32, (winuser::EC_LEFTMARGIN | winuser::EC_RIGHTMARGIN) as usize, 0);
+        }
+    }
+    /// Styles of an edit control are fixed once it is created, so it is created anew in place of the old one.
+    fn recreate_window(&mut self) {
+        let hwnd = self.base.hwnd;
+        if hwnd.is_null() {
+            return;
+        }
+        let parent = match self.base.parent_hwnd() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let rc = backend::current().window_rect(hwnd as usize);
+        let visible = backend::current().is_visible(hwnd as usize);
+        let selfptr = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA) as *mut c_void;
+        self.base.destroy_control_hwnd();
+        self.create_window(rc.left, rc.top, rc.width(), rc.height(), parent, selfptr);
+        if !visible {
+            backend::current().show_window(self.base.hwnd as usize, false);
+        }
+    }
+    fn update_window(&mut self) {
+        if self.selectable {
+            self.recreate_window();
+        } else {
+            self.base.invalidate();
+        }
+    }
+    fn draw_text_format(&self) -> minwindef::UINT {
+        let mut format = winuser::DT_NOPREFIX | winuser::DT_EXPANDTABS;
+        format |= match self.h_alignment {
+            HorizontalAlignment::Left => winuser::DT_LEFT,
+            HorizontalAlignment::Center => winuser::DT_CENTER,
+            HorizontalAlignment::Right => winuser::DT_RIGHT,
+        };
+        format |= match self.overflow {
+            TextOverflow::Wrap => winuser::DT_WORDBREAK,
+            TextOverflow::Clip => winuser::DT_SINGLELINE,
+            TextOverflow::EndEllipsis => winuser::DT_SINGLELINE | winuser::DT_END_ELLIPSIS,
+            TextOverflow::PathEllipsis => winuser::DT_SINGLELINE | winuser::DT_PATH_ELLIPSIS,
+        };
+        format
+    }
+    unsafe fn paint(&mut self, hwnd: windef::HWND) {
+        let mut ps: winuser::PAINTSTRUCT = mem::zeroed();
+        let dc = winuser::BeginPaint(hwnd, &mut ps);
+        let mut rc: windef::RECT = mem::zeroed();
+        winuser::GetClientRect(hwnd, &mut rc);
+        // the parent sets the colors and gives the background, as it does for the static drawing itself
+        let brush = backend::current().send_message(backend::current().parent(hwnd as usize), winuser::WM_CTLCOLORSTATIC, dc as usize, hwnd as isize) as windef::HBRUSH;
+        if !brush.is_null() {
+            winuser::FillRect(dc, &rc, brush);
+        }
+        let hfont = self.base.hfont();
+        let old_font = wingdi::SelectObject(dc, hfont as windef::HGDIOBJ);
+        let text_height = match self.overflow {
+            TextOverflow::Wrap => common::measure_text(hwnd, hfont, self.text.as_str(), Some(rc.right - rc.left)).cy,
+            _ => common::measure_text(hwnd, hfont, "", None).cy,
+        };
+        let free = cmp::max(0, rc.bottom - rc.top - text_height);
+        rc.top += match self.v_alignment {
+            VerticalAlignment::Top => 0,
+            VerticalAlignment::Middle => free / 2,
+            VerticalAlignment::Bottom => free,
+        };
+        let text = OsStr::new(self.text.as_str()).encode_wide().collect::<Vec<_>>();
+        winuser::DrawTextW(dc, text.as_ptr(), text.len() as i32, &mut rc, self.draw_text_format());
+        wingdi::SelectObject(dc, old_font);
+        winuser::EndPaint(hwnd, &ps);
+    }
+}
 impl HasLabelInner for WindowsText {
     fn label(&self, _base: &MemberBase) -> Cow<str> {
         Cow::Borrowed(self.text.as_ref())
@@ -22,10 +214,8 @@ impl HasLabelInner for WindowsText {
         self.text = label.into();
         let hwnd = self.base.hwnd;
         if !hwnd.is_null() {
-            let control_name = OsStr::new(&self.text).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-            unsafe {
-                winuser::SetWindowTextW(self.base.hwnd, control_name.as_ptr());
-            }
+            let control_name = common::str_to_wchar(self.window_text());
+            backend::current().set_window_text(self.base.hwnd as usize, control_name.as_slice());
             self.base.invalidate();
         }
     }
@@ -35,6 +225,10 @@ impl<O: controls::Text> NewTextInner<O> for WindowsText {
         WindowsText {
             base: common::WindowsControlBase::with_handler(Some(handler::<O>)),
             text: String::new(),
+            h_alignment: HorizontalAlignment::default(),
+            v_alignment: VerticalAlignment::default(),
+            overflow: TextOverflow::default(),
+            selectable: false,
         }
     }
 }
@@ -61,8 +255,8 @@ impl ControlInner for WindowsText {
         let selfptr = member as *mut _ as *mut c_void;
         self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
         let (w, h, _) = self.measure(member, control, pw, ph);
-        self.base.create_control_hwnd(x as i32, y as i32, w as i32, h as i32, self.base.hwnd, 0, WINDOW_CLASS.as_ptr(), self.text.as_str(), 
-            winuser::WS_TABSTOP | winuser::SS_NOPREFIX, selfptr);
+        let parent = self.base.hwnd;
+        self.create_window(x as i32, y as i32, w as i32, h as i32, parent, selfptr);
     }
     fn on_removed_from_container(&mut self, _member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
         self.base.destroy_control_hwnd();
@@ -125,32 +319,27 @@ impl Drawable for WindowsText {
         control.measured = match control.visibility {
             types::Visibility::Gone => (0, 0),
             _ => {
-                let mut label_size: windef::SIZE = unsafe { mem::zeroed() };
+                let hfont = self.base.hfont();
                 let w = match control.layout.width {
                     layout::Size::MatchParent => parent_width as i32,
                     layout::Size::Exact(w) => w as i32,
                     layout::Size::WrapContent => {
-                        if label_size.cx < 1 {
-                            let label = OsStr::new(self.text.as_str()).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-                            unsafe {
-                                wingdi::GetTextExtentPointW(winuser::GetDC(self.base.hwnd), label.as_ptr(), self.text.len() as i32, &mut label_size);
-                            }
+                        let w = common::measure_text(self.base.hwnd, hfont, self.text.as_str(), None).cx;
+                        if self.overflow == TextOverflow::Wrap {
+                            // wrapped at the width available rather than growing past it
+                            cmp::min(w, parent_width as i32)
+                        } else {
+                            w
                         }
-                        label_size.cx as i32
                     }
                 };
                 let h = match control.layout.height {
                     layout::Size::MatchParent => parent_height as i32,
                     layout::Size::Exact(h) => h as i32,
-                    layout::Size::WrapContent => {
-                        if label_size.cy < 1 {
-                            let label = OsStr::new(self.text.as_str()).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-                            unsafe {
-                                wingdi::GetTextExtentPointW(winuser::GetDC(self.base.hwnd), label.as_ptr(), self.text.len() as i32, &mut label_size);
-                            }
-                        }
-                        label_size.cy as i32
-                    }
+                    layout::Size::WrapContent => match self.overflow {
+                        TextOverflow::Wrap => common::measure_text(self.base.hwnd, hfont, self.text.as_str(), Some(w)).cy,
+                        _ => common::measure_text(self.base.hwnd, hfont, "", None).cy,
+                    },
                 };
                 (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
             }
@@ -168,9 +357,9 @@ impl Spawnable for WindowsText {
 }
 
 unsafe extern "system" fn handler<T: controls::Text>(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
-        winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, param as WinPtr);
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
     }
     match msg {
         winuser::WM_SIZE => {
@@ -179,40 +368,22 @@ unsafe extern "system" fn handler<T: controls::Text>(hwnd: windef::HWND, msg: mi
 
             let text: &mut Text = mem::transmute(param);
             text.call_on_size::<T>(width, height);
+            if !text.inner().inner().inner().selectable {
+                // aligned text moves with the size
+                backend::current().invalidate(hwnd as usize, false);
+            }
             return 0;
         }
-        /*winuser::WM_PAINT => {
-            use crate::plygui_api::controls::HasLabel;
-            
+        winuser::WM_PAINT => {
             let text: &mut Text = mem::transmute(param);
-            let mut ps: winuser::PAINTSTRUCT = mem::zeroed();
-            let hdc = winuser::BeginPaint(hwnd, &mut ps);
-            let mut text = common::str_to_wchar(text.label());
-            let mut rc = common::window_rect(hwnd);
-            
-            wingdi::SetBkMode(hdc, wingdi::TRANSPARENT as i32);
-            winuser::DrawTextW(hdc, text.as_mut_ptr(), text.len() as i32, &mut rc, winuser::DT_CENTER | winuser::DT_VCENTER);
-            winuser::EndPaint(hwnd, &mut ps);
-            return 0;
-        }
-        winuser::WM_NCCALCSIZE => {
-            match wparam as i32 {
-                minwindef::TRUE => {
-                    let nccalc: &mut winuser::NCCALCSIZE_PARAMS = mem::transmute(lparam);
-                    //println!("{:?}", nccalc);
-                }
-                minwindef::FALSE => {
-                    let rect: &mut windef::RECT = mem::transmute(lparam);
-                    rect.left += DEFAULT_PADDING;
-                    rect.top += DEFAULT_PADDING;
-                    rect.right -= DEFAULT_PADDING;
-                    rect.bottom -= DEFAULT_PADDING;
-                }
-                _ => {}
+            let text = text.inner_mut().inner_mut().inner_mut();
+            if !text.selectable {
+                text.paint(hwnd);
+                return 0;
             }
-        }*/
+        }
         _ => {}
     }
 
-    commctrl::DefSubclassProc(hwnd, msg, wparam, lparam)
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
 }
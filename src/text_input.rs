@@ -0,0 +1,417 @@
+use crate::backend;
+use crate::common::{self, *};
+use crate::font::Font;
+
+const CLASS_ID: &str = "Edit";
+const PASSWORD_CHAR: u16 = 0x25CF;
+
+lazy_static! {
+    pub static ref WINDOW_CLASS: Vec<u16> = OsStr::new(CLASS_ID).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
+}
+
+pub type TextInput = AMember<AControl<WindowsTextInput>>;
+
+pub type OnTextChange = Box<dyn FnMut(&mut TextInput, &str)>;
+pub type OnTextSubmit = Box<dyn FnMut(&mut TextInput, &str)>;
+
+#[repr(C)]
+pub struct WindowsTextInput {
+    base: common::WindowsControlBase<TextInput>,
+    text: String,
+    placeholder: String,
+    multi_line: bool,
+    wrap: bool,
+    password: bool,
+    read_only: bool,
+    max_length: usize,
+    selection: (usize, usize),
+    on_change: Option<OnTextChange>,
+    on_submit: Option<OnTextSubmit>,
+    skip_callbacks: bool,
+}
+
+impl WindowsTextInput {
+    fn with_uninit(_: &mut mem::MaybeUninit<TextInput>, multi_line: bool, wrap: bool) -> Self {
+        WindowsTextInput {
+            base: common::WindowsControlBase::with_handler(Some(handler)),
+            text: String::new(),
+            placeholder: String::new(),
+            multi_line,
+            wrap,
+            password: false,
+            read_only: false,
+            max_length: 0,
+            selection: (0, 0),
+            on_change: None,
+            on_submit: None,
+            skip_callbacks: false,
+        }
+    }
+    fn with_params<S: AsRef<str>>(text: S, multi_line: bool, wrap: bool) -> Box<TextInput> {
+        let mut b: Box<mem::MaybeUninit<TextInput>> = Box::new_uninit();
+        let mut ab = AMember::with_inner(AControl::with_inner(Self::with_uninit(b.as_mut(), multi_line, wrap)));
+        ab.inner_mut().inner_mut().set_text(text);
+        unsafe {
+            b.as_mut_ptr().write(ab);
+            b.assume_init()
+        }
+    }
+    pub fn with_text<S: AsRef<str>>(text: S) -> Box<TextInput> {
+        Self::with_params(text, false, false)
+    }
+    /// Lines are broken at the control width if `wrap` is set, otherwise the control scrolls horizontally.
+    pub fn multi_line<S: AsRef<str>>(text: S, wrap: bool) -> Box<TextInput> {
+        Self::with_params(text, true, wrap)
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+    /// Does not call `on_change`.
+    pub fn set_text<S: AsRef<str>>(&mut self, text: S) {
+        self.text = text.as_ref().into();
+        self.selection = (0, 0);
+        if !self.base.hwnd.is_null() {
+            let text = common::str_to_wchar(native_line_breaks(self.text.as_str(), self.multi_line));
+            self.skip_callbacks = true;
+            backend::current().set_window_text(self.base.hwnd as usize, text.as_slice());
+            self.skip_callbacks = false;
+            self.base.invalidate();
+        }
+    }
+    pub fn placeholder(&self) -> &str {
+        self.placeholder.as_str()
+    }
+    /// Shown while the input is empty. Single-line inputs only, as the native control has no cue banner in multi-line mode.
+    pub fn set_placeholder<S: AsRef<str>>(&mut self, placeholder: S) {
+        self.placeholder = placeholder.as_ref().into();
+        self.apply_placeholder();
+    }
+    pub fn is_multi_line(&self) -> bool {
+        self.multi_line
+    }
+    pub fn is_password(&self) -> bool {
+        self.password
+    }
+    pub fn set_password(&mut self, password: bool) {
+        self.password = password;
+        if !self.base.hwnd.is_null() && !self.multi_line {
+            backend::current().send_message(self.base.hwnd as usize, winuser::EM_SETPASSWORDCHAR as u32, if password { PASSWORD_CHAR as usize } else { 0 }, 0);
+            self.base.invalidate();
+        }
+    }
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        if !self.base.hwnd.is_null() {
+            backend::current().send_message(self.base.hwnd as usize, winuser::EM_SETREADONLY as u32, read_only as usize, 0);
+        }
+    }
+    /// Maximum length in UTF-16 units, as the edit control counts them, `0` for no limit.
+    /// Characters beyond the Basic Multilingual Plane, most emoji among them, take two, and so does a line break of a multi-line input.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length;
+        self.apply_max_length();
+    }
+    /// Selected range as character offsets, `start == end` being the caret position.
+    pub fn selection(&self) -> (usize, usize) {
+        if self.base.hwnd.is_null() {
+            return self.selection;
+        }
+        let mut start: minwindef::DWORD = 0;
+        let mut end: minwindef::DWORD = 0;
+        backend::current().send_message(self.base.hwnd as usize, winuser::EM_GETSEL as u32, &mut start as *mut _ as usize, &mut end as *mut _ as isize);
+        let native = native_line_breaks(self.text.as_str(), self.multi_line);
+        (utf16_to_char(native.as_ref(), start as usize, self.multi_line), utf16_to_char(native.as_ref(), end as usize, self.multi_line))
+    }
+    pub fn set_selection(&mut self, start: usize, end: usize) {
+        let len = self.text.chars().count();
+        self.selection = (cmp::min(start, len), cmp::min(end, len));
+        self.apply_selection();
+    }
+    pub fn select_all(&mut self) {
+        let len = self.text.chars().count();
+        self.set_selection(0, len);
+    }
+    pub fn on_change(&mut self, callback: Option<OnTextChange>) {
+        self.on_change = callback;
+    }
+    /// Called on Enter in single-line inputs and on Ctrl+Enter in multi-line ones.
+    pub fn on_submit(&mut self, callback: Option<OnTextSubmit>) {
+        self.on_submit = callback;
+    }
+    pub fn font(&self) -> Option<&Font> {
+        self.base.font()
+    }
+    /// `None` brings back the default font.
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.base.set_font(font);
+    }
+
+    fn style(&self) -> minwindef::DWORD {
+        let mut style = winuser::WS_TABSTOP;
+        if self.multi_line {
+            style |= winuser::ES_MULTILINE | winuser::ES_WANTRETURN | winuser::ES_AUTOVSCROLL | winuser::WS_VSCROLL;
+            if !self.wrap {
+                style |= winuser::ES_AUTOHSCROLL | winuser::WS_HSCROLL;
+            }
+        } else {
+            style |= winuser::ES_AUTOHSCROLL;
+            if self.password {
+                style |= winuser::ES_PASSWORD;
+            }
+        }
+        if self.read_only {
+            style |= winuser::ES_READONLY;
+        }
+        style
+    }
+    fn apply_placeholder(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let placeholder = common::str_to_wchar(self.placeholder.as_str());
+            backend::current().send_message(self.base.hwnd as usize, commctrl::EM_SETCUEBANNER, minwindef::TRUE as usize, placeholder.as_ptr() as isize);
+        }
+    }
+    fn apply_max_length(&mut self) {
+        if !self.base.hwnd.is_null() {
+            backend::current().send_message(self.base.hwnd as usize, winuser::EM_SETLIMITTEXT as u32, self.max_length, 0);
+        }
+    }
+    fn apply_selection(&mut self) {
+        if !self.base.hwnd.is_null() {
+            let native = native_line_breaks(self.text.as_str(), self.multi_line);
+            let start = char_to_utf16(native.as_ref(), self.selection.0, self.multi_line);
+            let end = char_to_utf16(native.as_ref(), self.selection.1, self.multi_line);
+            backend::current().send_message(self.base.hwnd as usize, winuser::EM_SETSEL as u32, start, end as isize);
+        }
+    }
+    fn sync_text(&mut self) {
+        self.text = common::window_text(self.base.hwnd).replace("\r\n", "\n");
+    }
+    fn measure_text(&self, hfont: windef::HFONT) -> windef::SIZE {
+        let sample = if self.text.is_empty() { self.placeholder.as_str() } else { self.text.as_str() };
+        let mut size: windef::SIZE = unsafe { mem::zeroed() };
+        // line by line, as an empty last line still takes its height in an edit control
+        for line in sample.split('\n') {
+            let line_size = common::measure_text(self.base.hwnd, hfont, line, None);
+            size.cx = cmp::max(size.cx, line_size.cx);
+            size.cy += line_size.cy;
+        }
+        size
+    }
+}
+
+impl ControlInner for WindowsTextInput {
+    fn on_added_to_container(&mut self, member: &mut MemberBase, control: &mut ControlBase, parent: &dyn controls::Container, x: i32, y: i32, pw: u16, ph: u16) {
+        let selfptr = member as *mut _ as *mut c_void;
+        self.base.hwnd = unsafe { parent.native_container_id() as windef::HWND }; // required for measure, as we don't have own hwnd yet
+        let (w, h, _) = self.measure(member, control, pw, ph);
+        let text = native_line_breaks(self.text.as_str(), self.multi_line).into_owned();
+        self.base.create_control_hwnd(
+            x as i32,
+            y as i32,
+            w as i32,
+            h as i32,
+            self.base.hwnd,
+            winuser::WS_EX_CLIENTEDGE,
+            WINDOW_CLASS.as_ptr(),
+            text.as_str(),
+            self.style(),
+            selfptr,
+        );
+        self.apply_placeholder();
+        self.apply_max_length();
+        self.apply_selection();
+    }
+    fn on_removed_from_container(&mut self, _member: &mut MemberBase, _control: &mut ControlBase, _: &dyn controls::Container) {
+        self.selection = self.selection();
+        self.base.destroy_control_hwnd();
+    }
+    fn parent(&self) -> Option<&dyn controls::Member> {
+        self.base.parent().map(|p| p.as_member())
+    }
+    fn parent_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.parent_mut().map(|p| p.as_member_mut())
+    }
+    fn root(&self) -> Option<&dyn controls::Member> {
+        self.base.root().map(|p| p.as_member())
+    }
+    fn root_mut(&mut self) -> Option<&mut dyn controls::Member> {
+        self.base.root_mut().map(|p| p.as_member_mut())
+    }
+}
+
+impl HasLayoutInner for WindowsTextInput {
+    fn on_layout_changed(&mut self, _base: &mut MemberBase) {
+        self.base.invalidate();
+    }
+}
+
+impl HasNativeIdInner for WindowsTextInput {
+    type Id = common::Hwnd;
+
+    fn native_id(&self) -> Self::Id {
+        self.base.hwnd.into()
+    }
+}
+
+impl HasSizeInner for WindowsTextInput {
+    fn on_size_set(&mut self, _: &mut MemberBase, _: (u16, u16)) -> bool {
+        self.base.invalidate();
+        true
+    }
+}
+
+impl HasVisibilityInner for WindowsTextInput {
+    fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
+        self.base.on_set_visibility(value)
+    }
+}
+
+impl MemberInner for WindowsTextInput {}
+
+impl Drawable for WindowsTextInput {
+    fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
+        self.base.draw(control.coords, control.measured);
+    }
+    fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
+        let old_size = control.measured;
+
+        control.measured = match control.visibility {
+            types::Visibility::Gone => (0, 0),
+            _ => {
+                let (padding, default_height) = (common::default_padding(self.base.hwnd), common::default_height(self.base.hwnd));
+                let hfont = self.base.hfont();
+                let mut text_size: Option<windef::SIZE> = None;
+                let w = match control.layout.width {
+                    layout::Size::MatchParent => parent_width as i32,
+                    layout::Size::Exact(w) => w as i32,
+                    layout::Size::WrapContent => {
+                        let size = text_size.get_or_insert_with(|| self.measure_text(hfont));
+                        cmp::max(size.cx, default_height * 4) + padding + padding
+                    }
+                };
+                let h = match control.layout.height {
+                    layout::Size::MatchParent => parent_height as i32,
+                    layout::Size::Exact(h) => h as i32,
+                    layout::Size::WrapContent => {
+                        if self.multi_line {
+                            let size = text_size.get_or_insert_with(|| self.measure_text(hfont));
+                            cmp::max(size.cy + padding + padding, default_height * 3)
+                        } else {
+                            default_height
+                        }
+                    }
+                };
+                (cmp::max(0, w) as u16, cmp::max(0, h) as u16)
+            }
+        };
+        (control.measured.0, control.measured.1, control.measured != old_size)
+    }
+    fn invalidate(&mut self, _member: &mut MemberBase, _control: &mut ControlBase) {
+        self.base.invalidate()
+    }
+}
+
+impl Spawnable for WindowsTextInput {
+    fn spawn() -> Box<dyn controls::Control> {
+        Self::with_text("").into_control()
+    }
+}
+
+/// The native control breaks lines with `\r\n`, while the text is kept with plain `\n`.
+fn native_line_breaks(text: &str, multi_line: bool) -> Cow<'_, str> {
+    if multi_line && text.contains('\n') {
+        Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+fn utf16_to_char(native: &str, position: usize, multi_line: bool) -> usize {
+    let mut units = 0;
+    let mut chars = 0;
+    let mut iter = native.chars().peekable();
+    while let Some(c) = iter.next() {
+        if units >= position {
+            break;
+        }
+        units += c.len_utf16();
+        if multi_line && c == '\r' && iter.peek() == Some(&'\n') {
+            iter.next();
+            units += 1;
+        }
+        chars += 1;
+    }
+    chars
+}
+fn char_to_utf16(native: &str, position: usize, multi_line: bool) -> usize {
+    let mut units = 0;
+    let mut iter = native.chars().peekable();
+    for _ in 0..position {
+        match iter.next() {
+            Some(c) => {
+                units += c.len_utf16();
+                if multi_line && c == '\r' && iter.peek() == Some(&'\n') {
+                    iter.next();
+                    units += 1;
+                }
+            }
+            None => break,
+        }
+    }
+    units
+}
+
+unsafe extern "system" fn handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
+    if ww == 0 {
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
+    }
+    match msg {
+        m if m == common::OCM_BASE + winuser::WM_COMMAND => {
+            if minwindef::HIWORD(wparam as u32) == winuser::EN_CHANGE {
+                let input: &mut TextInput = mem::transmute(param);
+                let inner = input.inner_mut().inner_mut();
+                inner.sync_text();
+                if !inner.skip_callbacks {
+                    if let Some(ref mut cb) = inner.on_change {
+                        let input2: &mut TextInput = mem::transmute(param);
+                        (cb.as_mut())(input2, inner.text.as_str());
+                    }
+                }
+            }
+            return 0;
+        }
+        winuser::WM_CHAR if wparam == 0x0D || wparam == 0x0A => {
+            let input: &mut TextInput = mem::transmute(param);
+            let inner = input.inner_mut().inner_mut();
+            // Enter submits single-line inputs, Ctrl+Enter (arriving as a line feed) multi-line ones
+            if inner.multi_line == (wparam == 0x0A) {
+                if let Some(ref mut cb) = inner.on_submit {
+                    let input2: &mut TextInput = mem::transmute(param);
+                    (cb.as_mut())(input2, inner.text.as_str());
+                    return 0;
+                }
+                if !inner.multi_line {
+                    // single-line edits beep on Enter
+                    return 0;
+                }
+            }
+        }
+        winuser::WM_SIZE => {
+            let width = lparam as u16;
+            let height = (lparam >> 16) as u16;
+
+            let input: &mut TextInput = mem::transmute(param);
+            input.call_on_size::<TextInput>(width, height);
+        }
+        _ => {}
+    }
+
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
+}
@@ -1,3 +1,4 @@
+use crate::backend;
 use crate::common::{self, *};
 
 use winapi::um::shellapi;
@@ -23,7 +24,7 @@ impl WindowsTray {
                 let hwnd = (&*self.this).native_id().into();
                 if self.menu.2 > -2 {
                     self.menu.2 = -2;
-                    winuser::SendMessageW(hwnd, winuser::WM_CANCELMODE, 0, 0);
+                    backend::current().send_message(hwnd as usize, winuser::WM_CANCELMODE, 0, 0);
                 } else {
                     self.menu.2 = -1;
                     let mut click_point = mem::zeroed();
@@ -88,9 +89,7 @@ impl HasLabelInner for WindowsTray {
         self.label = label.into();
         if !self.cfg.hWnd.is_null() {
             let control_name = common::str_to_wchar(&self.label);
-            unsafe {
-                winuser::SetWindowTextW(self.cfg.hWnd, control_name.as_ptr());
-            }
+            backend::current().set_window_text(self.cfg.hWnd as usize, control_name.as_slice());
         }
     }
 }
@@ -189,11 +188,9 @@ impl TrayInner for WindowsTray {
                 }
             }
             if let Some(items) = menu {
-                unsafe {
-                    let menu = winuser::CreatePopupMenu();
-                    common::make_menu(menu, items, &mut tt.menu.1);
-                    tt.menu.0 = menu;
-                }
+                let menu = backend::current().create_menu(true) as windef::HMENU;
+                common::make_menu(menu, items, &mut tt.menu.1);
+                tt.menu.0 = menu;
             }
             tt.install_image();
         }
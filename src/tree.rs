@@ -1,4 +1,7 @@
+use crate::backend;
 use crate::common::{self, *};
+use crate::navigation::{self, TypeAhead};
+use crate::selection::{Modifiers, Selection, SelectionMode};
 
 lazy_static! {
     pub static ref WINDOW_CLASS_TREE: Vec<u16> = OsStr::new(commctrl::WC_TREEVIEW).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
@@ -7,12 +10,41 @@ lazy_static! {
 
 pub type Tree = AMember<AControl<AContainer<AAdapted<ATree<WindowsTree>>>>>;
 
+pub type OnTreeSelectionChange = Box<dyn FnMut(&mut Tree, &[Vec<usize>])>;
+
+/// Reports an item dragged from the first index path to the second one. The tree itself stays as it is until the adapter follows.
+pub type OnTreeItemMove = Box<dyn FnMut(&mut Tree, &[usize], &[usize])>;
+
+/// Validates the name typed in for the item at the index path, returning whether to accept it.
+/// A rejected name stays in the edit box to be fixed or cancelled. An accepted one is for the application to put into the adapter,
+/// the item keeps showing the old name until then.
+pub type OnTreeLabelEdit = Box<dyn FnMut(&mut Tree, &[usize], &str) -> bool>;
+
+struct Drag {
+    from: Vec<usize>,
+    /// Path the item would be inserted at, with the item still in place.
+    slot: Option<Vec<usize>>,
+}
+
 #[repr(C)]
 pub struct WindowsTree {
     base: WindowsControlBase<Tree>,
     hwnd_tree: windef::HWND,
     items: TreeNodeList<winapi::um::commctrl::HTREEITEM>,
+    /// Children are spawned when their branch is expanded and released when it collapses.
+    /// The `expanded` flag of a node then tells whether its children are there.
+    lazy: bool,
+    selection: Selection,
+    type_ahead: TypeAhead,
+    reorderable: bool,
+    drag: Option<Drag>,
+    label_editable: bool,
+    /// Name rejected by `on_label_edit`, shown again once editing restarts.
+    rejected: Option<String>,
     on_item_click: Option<callbacks::OnItemClick>,
+    on_selection_change: Option<OnTreeSelectionChange>,
+    on_item_move: Option<OnTreeItemMove>,
+    on_label_edit: Option<OnTreeLabelEdit>,
 }
 
 impl WindowsTree {
@@ -24,6 +56,7 @@ impl WindowsTree {
         
         let mut item = adapter.adapter.spawn_item_view(indexes, this);
         
+        let (lazy, hwnd_tree) = (self.lazy, self.hwnd_tree);
         let mut items = &mut self.items.0;
         let mut parent = None;
         for i in 0..indexes.len() {
@@ -31,13 +64,19 @@ impl WindowsTree {
             let end = i+1 >= indexes.len();
             if end {
             	items.insert(index, TreeNode {
-                    expanded: if let adapter::Node::Branch(expanded) = node { *expanded } else { false },
+                    expanded: !lazy && if let adapter::Node::Branch(expanded) = node { *expanded } else { false },
                     root: item.take().unwrap(),
                     branches: vec![],
                     native: ptr::null_mut(),
                 });
                 
                 add_native_item(this, items, index, parent, pw, ph);
+                if lazy {
+                    set_children_callback(hwnd_tree, items[index].native);
+                    if let adapter::Node::Branch(true) = node {
+                        backend::current().post_message(hwnd_tree as usize, winapi::um::commctrl::TVM_EXPAND, winapi::um::commctrl::TVE_EXPAND, items[index].native as isize);
+                    }
+                }
                 //return;
             } else {
             	parent = Some((items[index].native, items[index].expanded));
@@ -53,7 +92,7 @@ impl WindowsTree {
             let index = indexes[i];
                 
             if i+1 >= indexes.len() {
-                if minwindef::TRUE as isize != unsafe { winuser::SendMessageW(self.hwnd_tree, winapi::um::commctrl::TVM_DELETEITEM, 0, items[index].native as isize) } {
+                if minwindef::TRUE as isize != backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_DELETEITEM, 0, items[index].native as isize) {
 	                unsafe { common::log_error(); }
 	            } else {
 	            	remove_native_item(this, items, index);
@@ -72,6 +111,7 @@ impl WindowsTree {
         
         let mut item = adapter.adapter.spawn_item_view(indexes, this);
 			        
+		let (lazy, hwnd_tree) = (self.lazy, self.hwnd_tree);
 		let mut items = &mut self.items.0;
         let mut parent = None;
         for i in 0..indexes.len() {
@@ -79,17 +119,24 @@ impl WindowsTree {
                 
             if i+1 >= indexes.len() {
                 let mut deleted = items.remove(index);
-	            if minwindef::TRUE as isize != unsafe { winuser::SendMessageW(self.hwnd_tree, winapi::um::commctrl::TVM_DELETEITEM, 0, deleted.native as isize) } {
+	            if minwindef::TRUE as isize != backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_DELETEITEM, 0, deleted.native as isize) {
 	                unsafe { common::log_error(); }
 	            } else {
 	            	deleted.root.on_removed_from_container(this);
 		            items.insert(index, TreeNode {
-	                    expanded: if let adapter::Node::Branch(expanded) = node { *expanded } else { false },
+	                    expanded: !lazy && if let adapter::Node::Branch(expanded) = node { *expanded } else { false },
 	                    root: item.take().unwrap(),
 	                    branches: vec![],
 	                    native: ptr::null_mut(),
 	                });
 	            	add_native_item(this, items, index, parent, pw, ph);
+	            	if lazy {
+	            	    // the children are gone with the old item, and come back from the adapter on expansion
+	            	    set_children_callback(hwnd_tree, items[index].native);
+	            	    if let adapter::Node::Branch(true) = node {
+            	            backend::current().post_message(hwnd_tree as usize, winapi::um::commctrl::TVM_EXPAND, winapi::um::commctrl::TVE_EXPAND, items[index].native as isize);
+	            	    }
+	            	}
                 
 	                match items[index].node() {
 	                	adapter::Node::Branch(expanded) => {
@@ -108,14 +155,55 @@ impl WindowsTree {
         }
         //unsafe { self.redraw_visible() }
     }
+    /// Spawns the children of the branch at `indexes` from the adapter, unless they are there already.
+    fn populate(&mut self, base: &mut MemberBase, indexes: &[usize]) {
+        match find_node(&mut self.items.0, indexes) {
+            Some(node) if !node.expanded => node.expanded = true,
+            _ => return,
+        }
+        let (_, _, adapter, _) = unsafe { Tree::adapter_base_parts_mut(base) };
+        let count = adapter.adapter.len_at(indexes).unwrap_or(0);
+        let mut path = indexes.to_vec();
+        path.push(0);
+        for i in 0..count {
+            *path.last_mut().unwrap() = i;
+            let (_, _, adapter, _) = unsafe { Tree::adapter_base_parts_mut(base) };
+            if let Some(node) = adapter.adapter.node_at(path.as_slice()) {
+                self.add_item_inner(base, path.as_slice(), &node);
+            }
+        }
+    }
+    /// Removes the children of the branch at `indexes`, so they are spawned anew on the next expansion.
+    fn release(&mut self, base: &mut MemberBase, indexes: &[usize]) {
+        let this: &mut Tree = unsafe { utils::base_to_impl_mut(base) };
+        if let Some(node) = find_node(&mut self.items.0, indexes) {
+            for i in (0..node.branches.len()).rev() {
+                remove_native_item(this, &mut node.branches, i);
+            }
+            node.expanded = false;
+            backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_EXPAND, winapi::um::commctrl::TVE_COLLAPSE | winapi::um::commctrl::TVE_COLLAPSERESET, node.native as isize);
+        }
+    }
+    /// Whether an adapter change at `indexes` concerns nodes that are spawned. Either way, the parent is asked for its children anew.
+    fn is_loaded(&mut self, indexes: &[usize], added: bool) -> bool {
+        let parent_path = &indexes[..indexes.len().saturating_sub(1)];
+        if let Some(parent) = find_node(&mut self.items.0, parent_path) {
+            set_children_callback(self.hwnd_tree, parent.native);
+        }
+        if added {
+            parent_path.is_empty() || find_node(&mut self.items.0, parent_path).map(|parent| parent.expanded).unwrap_or(false)
+        } else {
+            find_node(&mut self.items.0, indexes).is_some()
+        }
+    }
     fn invalidate_inner(&mut self) {
-    	unsafe { winuser::RedrawWindow(self.hwnd_tree, ptr::null_mut(), ptr::null_mut(), winuser::RDW_INVALIDATE | winuser::RDW_UPDATENOW) };
+    	backend::current().redraw(self.hwnd_tree as usize, false);
         self.base.invalidate();
     }
     unsafe fn redraw_visible(&mut self) {
     	//winuser::InvalidateRect(self.base.hwnd, ptr::null_mut(), minwindef::FALSE);
     	let color = winuser::GetSysColor(winuser::COLOR_3DFACE);
-		winuser::SendMessageW(self.hwnd_tree, winapi::um::commctrl::TVM_SETBKCOLOR, 0, color as isize);
+		backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_SETBKCOLOR, 0, color as isize);
 		
 		let (w, _) = common::size_hwnd(self.hwnd_tree);
     	
@@ -137,6 +225,314 @@ impl WindowsTree {
 //		}
 //		winuser::ReleaseDC(self.hwnd_tree, hdc);
     }
+    /// Sets the item height anew from the item views, after the tree moved to a monitor with another DPI.
+    unsafe fn fit_item_height(&mut self) {
+        fn tallest(items: &[TreeNode<winapi::um::commctrl::HTREEITEM>]) -> u16 {
+            items.iter().map(|item| cmp::max(item.root.size().1, tallest(&item.branches))).max().unwrap_or(0)
+        }
+        let height = cmp::max(line_height(self.hwnd_tree), tallest(&self.items.0) as i32) + common::scaled(self.hwnd_tree, 2);
+        if 0 > backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_SETITEMHEIGHT, height as usize, 0) {
+            common::log_error();
+        }
+        self.redraw_visible();
+    }
+}
+impl WindowsTree {
+    /// Makes the tree spawn the children of a branch only once it is expanded, and release them when it collapses.
+    ///
+    /// The adapter is then asked for a branch only through `len_at` and `node_at`, so it may fetch its data on demand.
+    /// Takes effect only before the tree is added to a container, later calls are ignored.
+    pub fn set_lazy(&mut self, lazy: bool) {
+        if self.base.hwnd.is_null() {
+            self.lazy = lazy;
+        }
+    }
+    pub fn is_lazy(&self) -> bool {
+        self.lazy
+    }
+    pub fn selection_mode(&self) -> SelectionMode {
+        self.selection.mode()
+    }
+    /// Calls `on_selection_change` when the new mode can not hold all the selected items.
+    pub fn set_selection_mode(&mut self, mode: SelectionMode) {
+        if self.selection.set_mode(mode) {
+            self.notify_selection();
+        }
+    }
+    /// Selected items in the order they were selected, kept in step with the adapter inserting and removing items.
+    /// In a lazy tree, items of collapsed branches stay selected.
+    pub fn selected(&self) -> &[Vec<usize>] {
+        self.selection.selected()
+    }
+    /// Does not call `on_selection_change`.
+    pub fn select(&mut self, indexes: &[usize]) {
+        if self.selection.select(indexes) {
+            self.redraw_selection();
+        }
+    }
+    /// Does not call `on_selection_change`.
+    pub fn deselect(&mut self, indexes: &[usize]) {
+        if self.selection.deselect(indexes) {
+            self.redraw_selection();
+        }
+    }
+    /// Does not call `on_selection_change`.
+    pub fn clear_selection(&mut self) {
+        if self.selection.clear() {
+            self.redraw_selection();
+        }
+    }
+    pub fn on_selection_change(&mut self, callback: Option<OnTreeSelectionChange>) {
+        self.on_selection_change = callback;
+    }
+    fn notify_selection(&mut self) {
+        self.redraw_selection();
+        let this = self.base.as_outer_mut();
+        if let Some(ref mut callback) = self.on_selection_change {
+            (callback.as_mut())(this, self.selection.selected());
+        }
+    }
+    /// Repaints the items and their views, as the selected ones are drawn in the colors of the selection.
+    fn redraw_selection(&self) {
+        if !self.hwnd_tree.is_null() {
+            backend::current().redraw(self.hwnd_tree as usize, true);
+        }
+    }
+    /// Whether `hwnd` is the view of a selected item.
+    fn is_view_selected(&self, hwnd: windef::HWND) -> bool {
+        view_path(&self.items.0, hwnd).map(|path| self.selection.is_selected(path.as_slice())).unwrap_or(false)
+    }
+    /// Calls `on_item_click` for the item at `indexes`, as a mouse click on it does.
+    fn activate(&mut self, indexes: &[usize]) {
+        let this = self.base.as_outer_mut();
+        if let Some(node) = find_node(&mut self.items.0, indexes) {
+            if let Some(ref mut callback) = self.on_item_click {
+                (callback.as_mut())(this, indexes, node.root.as_member_mut().is_control_mut().unwrap());
+            }
+        }
+    }
+    /// Moves the caret to the next visible item whose view text starts with what was typed.
+    fn type_char(&mut self, c: char) {
+        let visible = visible_items(self.hwnd_tree);
+        let caret = backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_CARET, 0) as winapi::um::commctrl::HTREEITEM;
+        let current = visible.iter().position(|item| *item == caret);
+        let time = unsafe { winuser::GetMessageTime() } as u32;
+        let hwnd_tree = self.hwnd_tree;
+        if let Some(found) = self.type_ahead.type_char(c, time, current, visible.len(), |i| item_text(hwnd_tree, visible[i])) {
+            backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_SELECTITEM, winapi::um::commctrl::TVGN_CARET, visible[found] as isize);
+            backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_ENSUREVISIBLE, 0, visible[found] as isize);
+            // selecting from code is not reported as a keyboard change, so the model is updated here
+            let indexes = index_from_hitem(visible[found], self.hwnd_tree);
+            if !indexes.is_empty() && self.selection.click(indexes.as_slice(), Modifiers::default()) {
+                self.notify_selection();
+            }
+        }
+    }
+    pub fn is_reorderable(&self) -> bool {
+        self.reorderable
+    }
+    /// Lets the user drag items around, reported through `on_item_move`.
+    /// Dropping onto the middle of a branch moves the item to the end of its children.
+    pub fn set_reorderable(&mut self, reorderable: bool) {
+        self.reorderable = reorderable;
+        if !reorderable {
+            self.cancel_drag();
+        }
+    }
+    pub fn on_item_move(&mut self, callback: Option<OnTreeItemMove>) {
+        self.on_item_move = callback;
+    }
+    pub fn is_label_editable(&self) -> bool {
+        self.label_editable
+    }
+    /// Lets the user rename items with F2 or a slow double click, the names going to `on_label_edit` for validation.
+    /// Without `on_label_edit` there is nothing to take the name, so editing does not start.
+    pub fn set_label_editable(&mut self, editable: bool) {
+        self.label_editable = editable;
+        if self.hwnd_tree.is_null() {
+            return;
+        }
+        let style = backend::current().window_long_ptr(self.hwnd_tree as usize, winuser::GWL_STYLE);
+        let style = if editable { style | winapi::um::commctrl::TVS_EDITLABELS as isize } else { style & !(winapi::um::commctrl::TVS_EDITLABELS as isize) };
+        backend::current().set_window_long_ptr(self.hwnd_tree as usize, winuser::GWL_STYLE, style);
+        if !editable {
+            backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_ENDEDITLABELNOW, minwindef::TRUE as usize, 0);
+        }
+    }
+    pub fn on_label_edit(&mut self, callback: Option<OnTreeLabelEdit>) {
+        self.on_label_edit = callback;
+    }
+    /// Starts renaming the item at `indexes`, returning whether it could start.
+    pub fn edit_label(&mut self, indexes: &[usize]) -> bool {
+        if !self.label_editable || self.hwnd_tree.is_null() {
+            return false;
+        }
+        let hitem = match find_node(&mut self.items.0, indexes) {
+            Some(node) => node.native,
+            None => return false,
+        };
+        backend::current().set_focus(self.hwnd_tree as usize);
+        0 != backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_EDITLABELW, 0, hitem as isize)
+    }
+    /// Puts the name shown by the item view into the edit box, instead of the placeholder label.
+    fn begin_label_edit(&mut self, hitem: winapi::um::commctrl::HTREEITEM) -> bool {
+        let rejected = self.rejected.take();
+        if !self.label_editable || self.on_label_edit.is_none() {
+            return false;
+        }
+        let edit = backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_GETEDITCONTROL, 0, 0) as windef::HWND;
+        if edit.is_null() {
+            return false;
+        }
+        let text = match rejected {
+            Some(text) => text,
+            None => item_text(self.hwnd_tree, hitem).unwrap_or_default(),
+        };
+        let text = common::str_to_wchar(text);
+        unsafe {
+            backend::current().set_window_text(edit as usize, text.as_slice());
+            backend::current().send_message(edit as usize, winuser::EM_SETSEL, 0, -1);
+            // over the item views
+            winuser::SetWindowPos(edit, winuser::HWND_TOP, 0, 0, 0, 0, winuser::SWP_NOMOVE | winuser::SWP_NOSIZE);
+        }
+        true
+    }
+    fn end_label_edit(&mut self, hitem: winapi::um::commctrl::HTREEITEM, text: *const u16) {
+        if text.is_null() {
+            // cancelled
+            return;
+        }
+        let indexes = index_from_hitem(hitem, self.hwnd_tree);
+        let text = unsafe { common::wchar_to_str(text) };
+        let this = self.base.as_outer_mut();
+        let accepted = match self.on_label_edit {
+            Some(ref mut callback) => (callback.as_mut())(this, indexes.as_slice(), text.as_str()),
+            // removed while editing, so dropped just as when cancelled
+            None => return,
+        };
+        if !accepted {
+            self.rejected = Some(text);
+            backend::current().post_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_EDITLABELW, 0, hitem as isize);
+        }
+    }
+    fn begin_drag(&mut self, hitem: winapi::um::commctrl::HTREEITEM) {
+        let from = index_from_hitem(hitem, self.hwnd_tree);
+        if from.is_empty() {
+            return;
+        }
+        self.drag = Some(Drag { from, slot: None });
+        backend::current().set_capture(self.hwnd_tree as usize);
+    }
+    /// Follows the dragged item to `(x, y)`, showing where it would land with the insert mark or the drop highlight.
+    fn drag_to(&mut self, x: i32, y: i32) {
+        if self.drag.is_none() {
+            return;
+        }
+        let hwnd_tree = self.hwnd_tree;
+        let (_, _, adapter, _) = unsafe { Tree::adapter_base_parts_mut(common::member_base_from_hwnd(self.base.hwnd).unwrap()) };
+        let mut hit_info = winapi::um::commctrl::TVHITTESTINFO {
+            pt: windef::POINT { x, y },
+            ..Default::default()
+        };
+        let hit = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_HITTEST, 0, &mut hit_info as *mut _ as isize) as winapi::um::commctrl::HTREEITEM;
+        let mut rc: windef::RECT = Default::default();
+        let found = !hit.is_null() && unsafe {
+            *(&mut rc as *mut _ as *mut winapi::um::commctrl::HTREEITEM) = hit;
+            0 != backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMRECT, minwindef::FALSE as usize, &mut rc as *mut _ as isize)
+        };
+        // the slot, and the item marked with the insert mark (`Some(after)`) or the drop highlight (`None`)
+        let (slot, mark) = if !found {
+            // past the last item
+            (vec![adapter.adapter.len_at(&[]).unwrap_or(0)], Some(true))
+        } else {
+            let mut indexes = index_from_hitem(hit, hwnd_tree);
+            let branch = if let Some(adapter::Node::Branch(_)) = adapter.adapter.node_at(indexes.as_slice()) { true } else { false };
+            let (upper, lower) = if branch {
+                let third = (rc.bottom - rc.top) / 3;
+                (rc.top + third, rc.bottom - third)
+            } else {
+                let half = (rc.top + rc.bottom) / 2;
+                (half, half)
+            };
+            let expanded = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMSTATE, hit as usize, winapi::um::commctrl::TVIS_EXPANDED as isize) as u32 & winapi::um::commctrl::TVIS_EXPANDED != 0;
+            if y < upper {
+                (indexes, Some(false))
+            } else if y < lower {
+                let len = adapter.adapter.len_at(indexes.as_slice()).unwrap_or(0);
+                indexes.push(len);
+                (indexes, None)
+            } else if branch && expanded {
+                // right below an expanded branch are its children
+                indexes.push(0);
+                (indexes, Some(true))
+            } else {
+                if let Some(last) = indexes.last_mut() {
+                    *last += 1;
+                }
+                (indexes, Some(true))
+            }
+        };
+        let drag = self.drag.as_mut().unwrap();
+        if drag.slot.as_ref() == Some(&slot) {
+            return;
+        }
+        drag.slot = Some(slot);
+        let last = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_LASTVISIBLE, 0);
+        match mark {
+            Some(after) => {
+                backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_SELECTITEM, winapi::um::commctrl::TVGN_DROPHILITE, 0);
+                backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_SETINSERTMARK, after as usize, if found { hit as isize } else { last });
+            }
+            None => {
+                backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_SETINSERTMARK, 0, 0);
+                backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_SELECTITEM, winapi::um::commctrl::TVGN_DROPHILITE, hit as isize);
+            }
+        }
+    }
+    /// Ends a drag, returning whether there was one.
+    fn drop_drag(&mut self) -> bool {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return false,
+        };
+        self.end_drag();
+        if let Some(to) = drag.slot.and_then(|slot| navigation::move_target(drag.from.as_slice(), slot.as_slice())) {
+            let this = self.base.as_outer_mut();
+            if let Some(ref mut callback) = self.on_item_move {
+                (callback.as_mut())(this, drag.from.as_slice(), to.as_slice());
+            }
+        }
+        true
+    }
+    fn cancel_drag(&mut self) {
+        if self.drag.take().is_some() {
+            self.end_drag();
+        }
+    }
+    fn end_drag(&mut self) {
+        backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_SETINSERTMARK, 0, 0);
+        backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_SELECTITEM, winapi::um::commctrl::TVGN_DROPHILITE, 0);
+        backend::current().release_capture();
+    }
+    fn draw_focus(&mut self) {
+        if backend::current().focus() != self.hwnd_tree as usize {
+            return;
+        }
+        let caret = backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_CARET, 0) as winapi::um::commctrl::HTREEITEM;
+        if caret.is_null() {
+            return;
+        }
+        let mut rc: windef::RECT = Default::default();
+        unsafe {
+            *(&mut rc as *mut _ as *mut winapi::um::commctrl::HTREEITEM) = caret;
+            if 0 != backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMRECT, minwindef::TRUE as usize, &mut rc as *mut _ as isize) {
+                // drawn over the item view, which the tree clips away otherwise
+                let dc = winuser::GetDCEx(self.hwnd_tree, ptr::null_mut(), winuser::DCX_CACHE | winuser::DCX_CLIPSIBLINGS);
+                winuser::DrawFocusRect(dc, &rc);
+                winuser::ReleaseDC(self.hwnd_tree, dc);
+            }
+        }
+    }
 }
 impl<O: controls::Tree> NewTreeInner<O> for WindowsTree {
     fn with_uninit(_: &mut mem::MaybeUninit<O>) -> Self {
@@ -144,7 +540,17 @@ impl<O: controls::Tree> NewTreeInner<O> for WindowsTree {
             base: common::WindowsControlBase::with_wndproc(Some(handler::<O>)),
             hwnd_tree: 0 as windef::HWND,
             items: Default::default(),
+            lazy: false,
+            selection: Selection::new(SelectionMode::Single),
+            type_ahead: TypeAhead::default(),
+            reorderable: false,
+            drag: None,
+            label_editable: false,
+            rejected: None,
             on_item_click: None,
+            on_selection_change: None,
+            on_item_move: None,
+            on_label_edit: None,
         }
     }
 }
@@ -185,7 +591,28 @@ impl ItemClickableInner for WindowsTree {
 }
 impl AdaptedInner for WindowsTree {
     fn on_item_change(&mut self, base: &mut MemberBase, value: adapter::Change) {
+        let selection_changed = match value {
+            adapter::Change::Added(ref at, _) => {
+                self.selection.inserted(at);
+                false
+            }
+            adapter::Change::Removed(ref at) => self.selection.removed(at),
+            adapter::Change::Edited(_, _) => false,
+        };
+        if selection_changed {
+            self.notify_selection();
+        }
         if !self.base.hwnd.is_null() {
+            if self.lazy {
+                let loaded = match value {
+                    adapter::Change::Added(ref at, _) => self.is_loaded(at, true),
+                    adapter::Change::Removed(ref at) | adapter::Change::Edited(ref at, _) => self.is_loaded(at, false),
+                };
+                if !loaded {
+                    self.invalidate_inner();
+                    return;
+                }
+            }
             match value {
 	            adapter::Change::Added(at, ref node) => {
 	                self.add_item_inner(base, at, node);
@@ -233,24 +660,19 @@ impl ControlInner for WindowsTree {
                 selfptr,
                 None,
             );
-            let hwnd_tree = winuser::CreateWindowExW(
+            let hwnd_tree = common::create_part_hwnd(
+                (0, 0, width as i32, height as i32),
+                hwnd,
                 winapi::um::commctrl::TVS_EX_DOUBLEBUFFER,
                 WINDOW_CLASS_TREE.as_ptr(),
-                WINDOW_CLASS.as_ptr(),
+                WINDOW_CLASS.as_slice(),
                 winapi::um::commctrl::TVS_NONEVENHEIGHT | winuser::BS_GROUPBOX
-	                 | winuser::WS_CLIPCHILDREN | winuser::WS_BORDER | winuser::WS_CHILD
-	                 | winuser::WS_VISIBLE | winapi::um::commctrl::TVS_TRACKSELECT
+	                 | winuser::WS_CLIPCHILDREN | winuser::WS_BORDER | winapi::um::commctrl::TVS_TRACKSELECT
+	                 | if self.label_editable { winapi::um::commctrl::TVS_EDITLABELS } else { 0 }
 	                 | winapi::um::commctrl::TVS_EX_FADEINOUTEXPANDOS | winapi::um::commctrl::TVS_EX_DOUBLEBUFFER,
-                0,
-                0,
-                width as i32,
-                height as i32,
-                hwnd,
-                ptr::null_mut(),
-                common::hinstance(),
                 selfptr,
+                Some(ahandler),
             );
-            commctrl::SetWindowSubclass(hwnd_tree, Some(ahandler), common::subclass_id(WINDOW_CLASS_TREE.as_ptr()) as usize, selfptr as usize);
             (hwnd, hwnd_tree, id)
         };
         self.base.hwnd = hwnd;
@@ -259,9 +681,9 @@ impl ControlInner for WindowsTree {
         control.coords = Some((px, py));
         
         unsafe { 
-        	winuser::SetWindowLongPtrW(self.hwnd_tree, winuser::GWLP_USERDATA, selfptr as WinPtr); 
+        	backend::current().set_window_long_ptr(self.hwnd_tree as usize, winuser::GWLP_USERDATA, selfptr as isize); 
         	winapi::um::uxtheme::SetWindowTheme(self.hwnd_tree, common::THEME_EXPLORER.as_ptr(), ptr::null_mut());
-        	if 0 > winuser::SendMessageW(self.hwnd_tree, winapi::um::commctrl::TVM_SETITEMHEIGHT, 1, 0) {
+        	if 0 > backend::current().send_message(self.hwnd_tree as usize, winapi::um::commctrl::TVM_SETITEMHEIGHT, 1, 0) {
                 common::log_error();
             }
         	self.redraw_visible();
@@ -269,6 +691,15 @@ impl ControlInner for WindowsTree {
         
         let (member, _, adapter, _) = unsafe { Tree::adapter_base_parts_mut(member) };
 
+        if self.lazy {
+            for i in 0..adapter.adapter.len_at(&[]).unwrap_or(0) {
+                let (_, _, adapter, _) = unsafe { Tree::adapter_base_parts_mut(member) };
+                if let Some(node) = adapter.adapter.node_at(&[i]) {
+                    self.add_item_inner(member, &[i], &node);
+                }
+            }
+            return;
+        }
         adapter.adapter.for_each(&mut (|indexes, node| {
         	self.add_item_inner(member, indexes, node);
         }));
@@ -379,10 +810,8 @@ impl HasVisibilityInner for WindowsTree {
 impl Drawable for WindowsTree {
     fn draw(&mut self, _member: &mut MemberBase, control: &mut ControlBase) {
         if let Some((x, y)) = control.coords {
-            unsafe {
-                winuser::SetWindowPos(self.base.hwnd, ptr::null_mut(), x, y, control.measured.0 as i32, control.measured.1 as i32, 0);
-                winuser::SetWindowPos(self.hwnd_tree, ptr::null_mut(), 0, 0, control.measured.0 as i32, control.measured.1 as i32, 0);
-            }
+            backend::current().set_window_pos(self.base.hwnd as usize, x, y, control.measured.0 as i32, control.measured.1 as i32, 0);
+            backend::current().set_window_pos(self.hwnd_tree as usize, 0, 0, control.measured.0 as i32, control.measured.1 as i32, 0);
         }
     }
     fn measure(&mut self, _member: &mut MemberBase, control: &mut ControlBase, parent_width: u16, parent_height: u16) -> (u16, u16, bool) {
@@ -417,32 +846,24 @@ impl Spawnable for WindowsTree {
 
 unsafe fn register_window_class() -> Vec<u16> {
     let class_name = OsStr::new("PlyguiWin32Tree").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-    let class = winuser::WNDCLASSEXW {
-        cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as minwindef::UINT,
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
         style: winuser::CS_DBLCLKS,
-        lpfnWndProc: Some(window_handler),
-        cbClsExtra: 0,
-        cbWndExtra: 0,
-        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
-        hIcon: winuser::LoadIconW(ptr::null_mut(), winuser::IDI_APPLICATION),
-        hCursor: winuser::LoadCursorW(ptr::null_mut(), winuser::IDC_ARROW),
-        hbrBackground: ptr::null_mut(),
-        lpszMenuName: ptr::null(),
-        lpszClassName: class_name.as_ptr(),
-        hIconSm: ptr::null_mut(),
-    };
-    winuser::RegisterClassExW(&class);
+        wndproc: window_handler as usize,
+        background: None,
+        icon_and_cursor: true,
+    });
     class_name
 }
 
 unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
         if winuser::WM_CREATE == msg {
             let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
-            winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, cs.lpCreateParams as WinPtr);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
         }
-        return winuser::DefWindowProcW(hwnd, msg, wparam, lparam);
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
     }
     
     let tree: &mut Tree = mem::transmute(ww);
@@ -452,7 +873,7 @@ unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UIN
     } else if let Some(whandler) = tree.inner().inner().inner().inner().inner().base.proc_handler.as_handler() {
     	whandler(hwnd, msg, wparam, lparam, 0, 0)
     } else {
-	    winuser::DefWindowProcW(hwnd, msg, wparam, lparam)
+	    backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam)
     }
 }
 
@@ -472,7 +893,10 @@ unsafe extern "system" fn handler<T: controls::Tree>(this: &mut Tree, msg: minwi
 			            	dbg!("erase");
 			            }
 		                winapi::um::commctrl::CDDS_ITEMPREPAINT => {
-                        	let color = winuser::GetSysColor(winuser::COLOR_3DFACE);
+		                    // the placeholder label is hidden in the background, which shows the selection
+		                    let indexes = index_from_hitem(custom_draw.nmcd.dwItemSpec as winapi::um::commctrl::HTREEITEM, hwnd_tree);
+		                    let selected = this.inner().inner().inner().inner().inner().selection.is_selected(indexes.as_slice());
+                        	let color = winuser::GetSysColor(if selected { winuser::COLOR_HIGHLIGHT } else { winuser::COLOR_3DFACE });
 							custom_draw.clrText = color;
                             custom_draw.clrTextBk = color;
                         	//redraw_item(custom_draw.nmcd.dwItemSpec as winapi::um::commctrl::HTREEITEM, hwnd_tree, hwnd, &mut custom_draw.nmcd.rc, Some(false));
@@ -495,7 +919,7 @@ unsafe extern "system" fn handler<T: controls::Tree>(this: &mut Tree, msg: minwi
     			        common::log_error();
     			        panic!("Cannot get cursor position!");
     			    }
-	    			let clicked = winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_HITTEST, 0, &mut hit_info as *mut _ as isize) as *mut winapi::um::commctrl::TREEITEM;
+	    			let clicked = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_HITTEST, 0, &mut hit_info as *mut _ as isize) as *mut winapi::um::commctrl::TREEITEM;
 		            if !clicked.is_null() {
     		            let indexes = index_from_hitem(clicked, hwnd_tree);
     				    if indexes.len() > 0 {
@@ -506,10 +930,75 @@ unsafe extern "system" fn handler<T: controls::Tree>(this: &mut Tree, msg: minwi
         			        	let this = common::member_from_hwnd::<T>(hwnd).unwrap();
         	                    (cb.as_mut())(this, indexes.as_slice(), clicked.root.as_member_mut().is_control_mut().unwrap());
         	                }
+        	                let modifiers = Modifiers {
+        	                    ctrl: winuser::GetKeyState(winuser::VK_CONTROL) < 0,
+        	                    shift: winuser::GetKeyState(winuser::VK_SHIFT) < 0,
+        	                };
+        	                let tree = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+        	                if tree.selection.click(indexes.as_slice(), modifiers) {
+        	                    tree.notify_selection();
+        	                }
             			}
 				        common::log_error();
 		            }				    
 	    		}
+    			winapi::um::commctrl::TVN_ITEMEXPANDINGA
+		    			 | winapi::um::commctrl::TVN_ITEMEXPANDINGW => {
+    			    // both encodings share the layout up to the item handle and the action
+    			    let tv = &*(lparam as winapi::um::commctrl::LPNMTREEVIEWW);
+    			    let tree = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+    			    if tree.lazy && tv.action as usize & winapi::um::commctrl::TVE_EXPAND != 0 {
+    			        let indexes = index_from_hitem(tv.itemNew.hItem, hwnd_tree);
+    			        tree.populate(common::member_base_from_hwnd(hwnd).unwrap(), indexes.as_slice());
+    			    }
+    			}
+    			winapi::um::commctrl::TVN_SELCHANGEDW => {
+    			    let tv = &*(lparam as winapi::um::commctrl::LPNMTREEVIEWW);
+    			    if tv.action as isize == winapi::um::commctrl::TVC_BYKEYBOARD {
+    			        let indexes = index_from_hitem(tv.itemNew.hItem, hwnd_tree);
+    			        let modifiers = Modifiers {
+    			            ctrl: false,
+    			            shift: winuser::GetKeyState(winuser::VK_SHIFT) < 0,
+    			        };
+    			        let tree = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+    			        tree.type_ahead.reset();
+    			        if !indexes.is_empty() && tree.selection.click(indexes.as_slice(), modifiers) {
+    			            tree.notify_selection();
+    			        }
+    			    }
+    			}
+    			winapi::um::commctrl::TVN_BEGINDRAGW if this.inner().inner().inner().inner().inner().reorderable => {
+    			    let tv = &*(lparam as winapi::um::commctrl::LPNMTREEVIEWW);
+    			    this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().begin_drag(tv.itemNew.hItem);
+    			}
+    			winapi::um::commctrl::TVN_BEGINLABELEDITW => {
+    			    let info = &*(lparam as winapi::um::commctrl::LPNMTVDISPINFOW);
+    			    let tree = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+    			    return if tree.begin_label_edit(info.item.hItem) { 0 } else { 1 };
+    			}
+    			winapi::um::commctrl::TVN_ENDLABELEDITW => {
+    			    let info = &*(lparam as winapi::um::commctrl::LPNMTVDISPINFOW);
+    			    let tree = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+    			    tree.end_label_edit(info.item.hItem, info.item.pszText);
+    			    // the native label stays a placeholder, the new name comes back through the adapter
+    			    return 0;
+    			}
+    			winapi::um::commctrl::NM_RETURN => {
+    			    let caret = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_CARET, 0) as winapi::um::commctrl::HTREEITEM;
+    			    if !caret.is_null() {
+    			        let indexes = index_from_hitem(caret, hwnd_tree);
+    			        this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().activate(indexes.as_slice());
+    			    }
+    			    return 1;
+    			}
+    			winapi::um::commctrl::TVN_GETDISPINFOW => {
+    			    let info = &mut *(lparam as winapi::um::commctrl::LPNMTVDISPINFOW);
+    			    if info.item.mask & winapi::um::commctrl::TVIF_CHILDREN != 0 {
+    			        let indexes = index_from_hitem(info.item.hItem, hwnd_tree);
+    			        let (_, _, adapter, _) = Tree::adapter_base_parts_mut(common::member_base_from_hwnd(hwnd).unwrap());
+    			        info.item.cChildren = if adapter.adapter.len_at(indexes.as_slice()).unwrap_or(0) > 0 { 1 } else { 0 };
+    			    }
+    			}
     			winapi::um::commctrl::TVN_ITEMEXPANDEDA
 		    			 | winapi::um::commctrl::TVN_ITEMEXPANDEDW => {
     				//dbg!(msg, (&*(lparam as winuser::LPNMHDR)).code);
@@ -528,6 +1017,10 @@ unsafe extern "system" fn handler<T: controls::Tree>(this: &mut Tree, msg: minwi
     					}
     					_ => {}
     				}
+    			    let tree = this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+    			    if tree.lazy && tv.action as usize & winapi::um::commctrl::TVE_COLLAPSE != 0 {
+    			        tree.release(common::member_base_from_hwnd(hwnd).unwrap(), indexes.as_slice());
+    			    }
     			}
     			_ => {}
     		}
@@ -551,14 +1044,50 @@ unsafe extern "system" fn handler<T: controls::Tree>(this: &mut Tree, msg: minwi
          _ => {}
     }
 
-    winuser::DefWindowProcW(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().native_id().into(), msg, wparam, lparam)
+    backend::current().def_window_proc(usize::from(this.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().native_id()), msg, wparam, lparam)
 }
 unsafe extern "system" fn ahandler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM, _: usize, param: usize) -> isize {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
-        winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, param as WinPtr);
+        backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, param as isize);
     }
     match msg {
+        winuser::WM_KEYDOWN if wparam as i32 == winuser::VK_F2 => {
+            let tree: &mut Tree = mem::transmute(ww);
+            let tree = tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            let caret = backend::current().send_message(hwnd as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_CARET, 0);
+            if tree.label_editable && caret != 0 {
+                backend::current().send_message(hwnd as usize, winapi::um::commctrl::TVM_EDITLABELW, 0, caret);
+                return 0;
+            }
+        }
+        winuser::WM_KEYDOWN if wparam as i32 == winuser::VK_ESCAPE => {
+            let tree: &mut Tree = mem::transmute(ww);
+            let tree = tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            if tree.drag.is_some() {
+                tree.cancel_drag();
+                return 0;
+            }
+            tree.redraw_visible();
+        }
+        winuser::WM_MOUSEMOVE => {
+            let tree: &mut Tree = mem::transmute(ww);
+            let tree = tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
+            if tree.drag.is_some() {
+                tree.drag_to(minwindef::LOWORD(lparam as u32) as i16 as i32, minwindef::HIWORD(lparam as u32) as i16 as i32);
+                return 0;
+            }
+        }
+        winuser::WM_LBUTTONUP => {
+            let tree: &mut Tree = mem::transmute(ww);
+            if tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().drop_drag() {
+                return 0;
+            }
+        }
+        winuser::WM_CAPTURECHANGED => {
+            let tree: &mut Tree = mem::transmute(ww);
+            tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().cancel_drag();
+        }
 	    winuser::WM_VSCROLL |
 	    winuser::WM_HSCROLL |
 	    winuser::WM_MOUSEWHEEL|
@@ -567,9 +1096,36 @@ unsafe extern "system" fn ahandler(hwnd: windef::HWND, msg: minwindef::UINT, wpa
 		    let tree = tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut();
 			tree.redraw_visible();
         }
+        winuser::WM_CHAR => {
+            // the native incremental search sees only the placeholder labels, so the item views are searched instead
+            if let Some(c) = std::char::from_u32(wparam as u32).filter(|c| !c.is_control()) {
+                let tree: &mut Tree = mem::transmute(ww);
+                tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().type_char(c);
+                return 0;
+            }
+        }
+        winuser::WM_PAINT => {
+            let result = backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam);
+            let tree: &mut Tree = mem::transmute(ww);
+            tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().draw_focus();
+            return result;
+        }
+        winuser::WM_SETFOCUS | winuser::WM_KILLFOCUS => {
+            backend::current().invalidate(hwnd as usize, false);
+        }
+        winuser::WM_CTLCOLORSTATIC => {
+            let tree: &mut Tree = mem::transmute(ww);
+            if tree.inner().inner().inner().inner().inner().is_view_selected(lparam as windef::HWND) {
+                return common::selected_item_colors(wparam as windef::HDC);
+            }
+        }
+        winuser::WM_DPICHANGED_AFTERPARENT => {
+            let tree: &mut Tree = mem::transmute(ww);
+            tree.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().fit_item_height();
+        }
         _ => {}
     }
-    commctrl::DefSubclassProc(hwnd, msg, wparam, lparam)
+    backend::current().def_subclass_proc(hwnd as usize, msg, wparam, lparam)
 }
 
 unsafe fn redraw_item(drawn: winapi::um::commctrl::HTREEITEM, hwnd_tree: windef::HWND, hwnd: windef::HWND, rc: &mut windef::RECT, action: Option<bool>) {
@@ -582,14 +1138,14 @@ unsafe fn redraw_item(drawn: winapi::um::commctrl::HTREEITEM, hwnd_tree: windef:
 	if drawn.is_null() {
 		return;
 	}			    
-    if 0 == winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_GETITEMW, 0, &mut retrieve_item as *mut _ as isize) {
+    if 0 == backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMW, 0, &mut retrieve_item as *mut _ as isize) {
     	common::log_error();
     	panic!("Cannot find TreeView item");
     }
     let item = common::member_base_from_hwnd(retrieve_item.lParam as windef::HWND).unwrap().as_member_mut().is_control_mut().unwrap();
 	
 	*(rc as *mut _ as *mut winapi::um::commctrl::HTREEITEM) = drawn;
-	let action = action.unwrap_or(0 != winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_GETITEMRECT, minwindef::TRUE as usize, rc as *mut _ as isize));
+	let action = action.unwrap_or(0 != backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMRECT, minwindef::TRUE as usize, rc as *mut _ as isize));
     if action {
         let this = common::member_from_hwnd::<Tree>(hwnd).unwrap();
         let (pw, ph) = this.inner().base.measured;
@@ -599,23 +1155,35 @@ unsafe fn redraw_item(drawn: winapi::um::commctrl::HTREEITEM, hwnd_tree: windef:
 		    retrieve_item.pszText = label.as_ptr() as *const _ as *mut u16;
 			retrieve_item.mask |= winapi::um::commctrl::TVIF_TEXT;
 			retrieve_item.iIntegral = th as i32;
-    		winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_SETITEMW, 0, &mut retrieve_item as *mut _ as isize);
+    		backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_SETITEMW, 0, &mut retrieve_item as *mut _ as isize);
 			item.draw(None);
     	}
-    	winuser::ShowWindow(item.native_id() as windef::HWND, winuser::SW_SHOW);
-        winuser::SetWindowPos(
-        	item.native_id() as windef::HWND, 
-        	ptr::null_mut(), 
+    	backend::current().show_window(item.native_id(), true);
+        backend::current().set_window_pos(
+        	item.native_id(), 
         	rc.left + 1, 
         	rc.top + 1, 
         	cmp::max(tw as i32, rc.right - rc.left), 
         	cmp::max(th as i32, rc.bottom - rc.top), 
         	winuser::SWP_NOSIZE | winuser::SWP_NOSENDCHANGING | winuser::SWP_NOREDRAW);
     } else {
-    	 winuser::ShowWindow(item.native_id() as windef::HWND, winuser::SW_HIDE);
+    	 backend::current().show_window(item.native_id(), false);
     }
 }
 
+/// Index path of the item shown by the view `hwnd`.
+fn view_path(items: &[TreeNode<winapi::um::commctrl::HTREEITEM>], hwnd: windef::HWND) -> Option<Vec<usize>> {
+    for (i, item) in items.iter().enumerate() {
+        if unsafe { item.root.native_id() } as windef::HWND == hwnd {
+            return Some(vec![i]);
+        }
+        if let Some(mut path) = view_path(&item.branches, hwnd) {
+            path.insert(0, i);
+            return Some(path);
+        }
+    }
+    None
+}
 fn index_from_hitem(hitem: winapi::um::commctrl::HTREEITEM, hwnd_tree: windef::HWND) -> Vec<usize> {
 	let mut drawn = hitem;
 	
@@ -628,25 +1196,25 @@ fn index_from_hitem(hitem: winapi::um::commctrl::HTREEITEM, hwnd_tree: windef::H
     
     let mut indexes = Vec::new();    
 
-    if 0 == unsafe { winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_GETITEMW, 0, &mut retrieve_item as *mut _ as isize) } {
+    if 0 == backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMW, 0, &mut retrieve_item as *mut _ as isize) {
     	return indexes;
     }
 	
 	let mut parent = None;
     
     while {
-    	if 0 == unsafe { winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_GETITEMW, 0, &mut retrieve_item as *mut _ as isize) } {
+    	if 0 == backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMW, 0, &mut retrieve_item as *mut _ as isize) {
         	unsafe { common::log_error(); }
         	parent = None;
         } else {
-        	let parent1 = unsafe { winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_PARENT, drawn as *mut _ as isize) as *mut winapi::um::commctrl::TREEITEM} ;
+        	let parent1 = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_PARENT, drawn as *mut _ as isize) as *mut winapi::um::commctrl::TREEITEM;
             parent = if parent1.is_null() { None } else { Some(parent1) };
         }
         
         let mut i = 0;
         let mut index_current = drawn;
         while {
-        	index_current = unsafe { winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_PREVIOUS, index_current as *mut _ as isize) as *mut winapi::um::commctrl::TREEITEM} ;
+        	index_current = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_PREVIOUS, index_current as *mut _ as isize) as *mut winapi::um::commctrl::TREEITEM;
         	!index_current.is_null()
         } {
             i += 1;
@@ -713,15 +1281,14 @@ fn add_native_item(
     	
         insert_struct
     };
-    items[index].native = unsafe {
-    	winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_INSERTITEMW, 0, &insert_struct as *const winapi::um::commctrl::TVINSERTSTRUCTW as isize) as *mut winapi::um::commctrl::TREEITEM
-    };
+    items[index].native = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_INSERTITEMW, 0, &insert_struct as *const winapi::um::commctrl::TVINSERTSTRUCTW as isize) as *mut winapi::um::commctrl::TREEITEM;
     unsafe {
-        let item_height = winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_GETITEMHEIGHT, 1, 0);
+        let item_height = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMHEIGHT, 1, 0);
+        let needed = (cmp::max(line_height(hwnd_tree), yy as i32) + common::scaled(hwnd_tree, 2)) as isize;
         if item_height < 1 {
             common::log_error();
-        } else if ((yy as isize) + 2) > item_height {
-        	if 0 > winuser::SendMessageW(hwnd_tree, winapi::um::commctrl::TVM_SETITEMHEIGHT, yy as usize + 2, 0) {
+        } else if needed > item_height {
+        	if 0 > backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_SETITEMHEIGHT, needed as usize, 0) {
                 common::log_error();
             }
         }
@@ -733,7 +1300,7 @@ fn add_native_item(
     			let native = items[index].native;
     			add_native_item(this, &mut items[index].branches, i, Some((native, expanded)), pw, ph);
     		}
-    		if 0 == winuser::PostMessageW(hwnd_tree, winapi::um::commctrl::TVM_EXPAND, if expanded { winapi::um::commctrl::TVE_EXPAND } else { winapi::um::commctrl::TVE_COLLAPSE }, items[index].native as isize) {
+    		if !backend::current().post_message(hwnd_tree as usize, winapi::um::commctrl::TVM_EXPAND, if expanded { winapi::um::commctrl::TVE_EXPAND } else { winapi::um::commctrl::TVE_COLLAPSE }, items[index].native as isize) {
                 common::log_error();
     		}
     	},
@@ -741,13 +1308,61 @@ fn add_native_item(
     }
     match parent {
     	Some((parent_tree_item, expand_parent)) => unsafe {
-    		if 0 == winuser::PostMessageW(hwnd_tree, winapi::um::commctrl::TVM_EXPAND, if expand_parent { winapi::um::commctrl::TVE_EXPAND } else { winapi::um::commctrl::TVE_COLLAPSE }, parent_tree_item as isize) {
+    		if !backend::current().post_message(hwnd_tree as usize, winapi::um::commctrl::TVM_EXPAND, if expand_parent { winapi::um::commctrl::TVE_EXPAND } else { winapi::um::commctrl::TVE_COLLAPSE }, parent_tree_item as isize) {
                 common::log_error();
     		}
     	}
     	_ => {}
     }
 }
+/// Items not hidden in collapsed branches, top to bottom.
+fn visible_items(hwnd_tree: windef::HWND) -> Vec<winapi::um::commctrl::HTREEITEM> {
+    let mut items = vec![];
+    let mut item = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_ROOT, 0) as winapi::um::commctrl::HTREEITEM;
+    while !item.is_null() {
+        items.push(item);
+        item = backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETNEXTITEM, winapi::um::commctrl::TVGN_NEXTVISIBLE, item as isize) as winapi::um::commctrl::HTREEITEM;
+    }
+    items
+}
+/// Height of a line in the font of the tree, which the label edit box takes whatever the item views are.
+fn line_height(hwnd_tree: windef::HWND) -> i32 {
+    let hfont = backend::current().send_message(hwnd_tree as usize, winuser::WM_GETFONT, 0, 0) as windef::HFONT;
+    common::measure_text(hwnd_tree, hfont, "", None).cy
+}
+/// Window text of the view shown by `hitem`.
+fn item_text(hwnd_tree: windef::HWND, hitem: winapi::um::commctrl::HTREEITEM) -> Option<String> {
+    let mut item = winapi::um::commctrl::TVITEMEXW {
+        mask: winapi::um::commctrl::TVIF_PARAM,
+        hItem: hitem,
+        ..Default::default()
+    };
+    if 0 == backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_GETITEMW, 0, &mut item as *mut _ as isize) || item.lParam == 0 {
+        return None;
+    }
+    Some(common::window_text(item.lParam as windef::HWND))
+}
+fn find_node<'a>(items: &'a mut Vec<TreeNode<winapi::um::commctrl::HTREEITEM>>, indexes: &[usize]) -> Option<&'a mut TreeNode<winapi::um::commctrl::HTREEITEM>> {
+    let (first, rest) = indexes.split_first()?;
+    let node = items.get_mut(*first)?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        find_node(&mut node.branches, rest)
+    }
+}
+/// Makes the tree view ask, through `TVN_GETDISPINFOW`, whether the item has children, so unpopulated branches still show an expand button.
+fn set_children_callback(hwnd_tree: windef::HWND, hitem: winapi::um::commctrl::HTREEITEM) {
+    let mut item = winapi::um::commctrl::TVITEMEXW {
+        mask: winapi::um::commctrl::TVIF_CHILDREN,
+        hItem: hitem,
+        cChildren: winapi::um::commctrl::I_CHILDRENCALLBACK,
+        ..Default::default()
+    };
+    if !hitem.is_null() && 0 == backend::current().send_message(hwnd_tree as usize, winapi::um::commctrl::TVM_SETITEMW, 0, &mut item as *mut _ as isize) {
+        unsafe { common::log_error(); }
+    }
+}
 fn expand(
 	hwnd_tree: windef::HWND,
 	item: &mut TreeNode<winapi::um::commctrl::HTREEITEM>,
@@ -1,3 +1,4 @@
+use crate::backend;
 use crate::common::{self, *};
 
 lazy_static! {
@@ -9,7 +10,6 @@ pub struct WindowsWindow {
     hwnd: windef::HWND,
     hwnd_menu: windef::HMENU,
     wndproc: unsafe extern "system" fn(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT,  
-    msg: winuser::MSG,
     child: Option<Box<dyn controls::Control>>,
     menu: Vec<callbacks::Action>,
     on_close: Option<callbacks::OnClose>,
@@ -20,19 +20,16 @@ pub type Window = AMember<AContainer<ASingleContainer<ACloseable<AWindow<Windows
 
 impl WindowsWindow {
     pub(crate) fn dispatch(&mut self) -> i32 {
-        let ret = unsafe { winuser::PeekMessageW(&mut self.msg, ptr::null_mut(), 0, 0, winuser::PM_REMOVE) };
-        if ret > 0 {
-            unsafe {
-                winuser::TranslateMessage(&mut self.msg);
-                winuser::DispatchMessageW(&mut self.msg);
-            }
-        }
-        ret
+        backend::current().dispatch_message() as i32
     }
     fn size_inner(&self) -> (u16, u16) {
         let rect = unsafe { window_rect(self.hwnd) };
         ((rect.right - rect.left) as u16, (rect.bottom - rect.top) as u16)
     }
+    /// Scale of the monitor the window is on, `1.0` being 100%.
+    pub fn scale_factor(&self) -> f32 {
+        common::scale_factor(self.hwnd)
+    }
     fn redraw(&mut self) {
         let size = self.size_inner();
         if let Some(ref mut child) = self.child {
@@ -45,9 +42,8 @@ impl WindowsWindow {
 impl HasLabelInner for WindowsWindow {
     fn label(&self, _base: &MemberBase) -> Cow<str> {
         if self.hwnd != 0 as windef::HWND {
-            let mut wbuffer = vec![0u16; 4096];
-            let len = unsafe { winuser::GetWindowTextW(self.hwnd, wbuffer.as_mut_slice().as_mut_ptr(), 4096) };
-            Cow::Owned(String::from_utf16_lossy(&wbuffer.as_slice()[..len as usize]))
+            let wbuffer = backend::current().window_text(self.hwnd as usize);
+            Cow::Owned(String::from_utf16_lossy(&wbuffer.as_slice()[..wbuffer.len() - 1]))
         } else {
             unreachable!();
         }
@@ -55,18 +51,14 @@ impl HasLabelInner for WindowsWindow {
     fn set_label(&mut self, _: &mut MemberBase, label: Cow<str>) {
         if self.hwnd != 0 as windef::HWND {
             let control_name = OsStr::new(label.as_ref()).encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
-            unsafe {
-                winuser::SetWindowTextW(self.hwnd, control_name.as_ptr());
-            }
+            backend::current().set_window_text(self.hwnd as usize, control_name.as_slice());
         }
     }
 }
 
 impl HasVisibilityInner for WindowsWindow {
     fn on_visibility_set(&mut self, _base: &mut MemberBase, value: types::Visibility) -> bool {
-        unsafe {
-            winuser::ShowWindow(self.hwnd, if value == types::Visibility::Visible { winuser::SW_SHOW } else { winuser::SW_HIDE });
-        }
+        backend::current().show_window(self.hwnd as usize, value == types::Visibility::Visible);
         true
     }
 }
@@ -79,9 +71,8 @@ impl<O: controls::Window> NewWindowInner<O> for WindowsWindow {
     fn with_uninit_params(_: &mut mem::MaybeUninit<O>, _: &mut dyn controls::Application, _: &str, _: types::WindowStartSize, menu: types::Menu) -> Self {
    		let mut w = WindowsWindow {
             hwnd: ptr::null_mut(),
-            hwnd_menu: if menu.is_some() { unsafe { winuser::CreateMenu() } } else { ptr::null_mut() },
+            hwnd_menu: if menu.is_some() { backend::current().create_menu(false) as windef::HMENU } else { ptr::null_mut() },
             wndproc: handler::<O>,
-            msg: unsafe { mem::zeroed() },
             child: None,
             menu: if menu.is_some() { Vec::new() } else { vec![] },
             on_close: None,
@@ -96,12 +87,16 @@ impl<O: controls::Window> NewWindowInner<O> for WindowsWindow {
 impl WindowInner for WindowsWindow {
     fn with_params<S: AsRef<str>>(app: &mut dyn controls::Application, title: S, window_size: types::WindowStartSize, menu: types::Menu) -> Box<dyn controls::Window> {
         let mut rect = match window_size {
-            types::WindowStartSize::Exact(width, height) => windef::RECT {
-                left: 0,
-                top: 0,
-                right: width as i32,
-                bottom: height as i32,
-            },
+            types::WindowStartSize::Exact(width, height) => {
+                // given for 100%, the window opening on the primary monitor
+                let dpi = common::dpi(ptr::null_mut());
+                windef::RECT {
+                    left: 0,
+                    top: 0,
+                    right: crate::dpi::scale(width as i32, dpi),
+                    bottom: crate::dpi::scale(height as i32, dpi),
+                }
+            }
             types::WindowStartSize::Fullscreen => {
                 let mut rect = windef::RECT { left: 0, right: 0, top: 0, bottom: 0 };
                 if unsafe { winuser::SystemParametersInfoW(winuser::SPI_GETWORKAREA, 0, &mut rect as *mut _ as *mut c_void, 0) } == 0 {
@@ -140,26 +135,21 @@ impl WindowInner for WindowsWindow {
 	        b.as_mut_ptr().write(ab);
 	        b.assume_init()
         };
-        w.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().hwnd = unsafe { 
-        	winuser::CreateWindowExW(
-                exstyle,
-                WINDOW_CLASS.as_ptr(),
-                window_name.as_ptr() as ntdef::LPCWSTR,
-                style | winuser::WS_VISIBLE | winuser::CS_HREDRAW | winuser::CS_VREDRAW,
-                winuser::CW_USEDEFAULT,
-                winuser::CW_USEDEFAULT,
-                rect.right - rect.left,
-                rect.bottom - rect.top,
-                ptr::null_mut(),
-                ptr::null_mut(),
-                hinstance(),
-                w.as_mut() as *mut _ as *mut c_void,
-            )
-        };
+        w.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().hwnd = backend::current().create_window(&backend::CreateParams {
+            ex_style: exstyle,
+            class_name: WINDOW_CLASS.as_slice(),
+            window_name: window_name.as_slice(),
+            style: style | winuser::WS_VISIBLE | winuser::CS_HREDRAW | winuser::CS_VREDRAW,
+            x: winuser::CW_USEDEFAULT,
+            y: winuser::CW_USEDEFAULT,
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+            parent: 0,
+            menu: 0,
+            param: w.as_mut() as *mut _ as usize,
+        }) as windef::HWND;
         if !w.inner().inner().inner().inner().inner().hwnd_menu.is_null() {
-        	unsafe {
-        	    winuser::SetMenu(w.inner().inner().inner().inner().inner().hwnd, w.inner().inner().inner().inner().inner().hwnd_menu);
-            }
+            backend::current().set_menu(w.inner().inner().inner().inner().inner().hwnd as usize, w.inner().inner().inner().inner().inner().hwnd_menu as usize);
         }
         w
     }
@@ -225,9 +215,7 @@ impl SingleContainerInner for WindowsWindow {
 impl CloseableInner for WindowsWindow {
     fn close(&mut self, skip_callbacks: bool) -> bool {
         self.skip_callbacks = skip_callbacks;
-        unsafe {
-            winuser::SendMessageW(self.hwnd, winuser::WM_SYSCOMMAND, winuser::SC_CLOSE, 0);
-        }
+        backend::current().send_message(self.hwnd as usize, winuser::WM_SYSCOMMAND, winuser::SC_CLOSE, 0);
         self.hwnd.is_null()
     }
     fn on_close(&mut self, callback: Option<callbacks::OnClose>) {
@@ -261,32 +249,24 @@ impl Drop for WindowsWindow {
 unsafe fn register_window_class() -> Vec<u16> {
     let class_name = OsStr::new("PlyguiWin32Window").encode_wide().chain(Some(0).into_iter()).collect::<Vec<_>>();
 
-    let class = winuser::WNDCLASSEXW {
-        cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as minwindef::UINT,
+    backend::current().register_class(&backend::ClassParams {
+        class_name: class_name.as_slice(),
         style: winuser::CS_DBLCLKS,
-        lpfnWndProc: Some(window_handler),
-        cbClsExtra: 0,
-        cbWndExtra: 0,
-        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
-        hIcon: winuser::LoadIconW(ptr::null_mut(), winuser::IDI_APPLICATION),
-        hCursor: winuser::LoadCursorW(ptr::null_mut(), winuser::IDC_ARROW),
-        hbrBackground: (winuser::COLOR_BTNFACE + 1) as windef::HBRUSH,
-        lpszMenuName: ptr::null(),
-        lpszClassName: class_name.as_ptr(),
-        hIconSm: ptr::null_mut(),
-    };
-    winuser::RegisterClassExW(&class);
+        wndproc: window_handler as usize,
+        background: Some(winuser::COLOR_BTNFACE),
+        icon_and_cursor: true,
+    });
     class_name
 }
 
 unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     if ww == 0 {
         if winuser::WM_CREATE == msg {
             let cs: &mut winuser::CREATESTRUCTW = mem::transmute(lparam);
-            winuser::SetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA, cs.lpCreateParams as WinPtr);
+            backend::current().set_window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA, cs.lpCreateParams as isize);
         }
-        return winuser::DefWindowProcW(hwnd, msg, wparam, lparam);
+        return backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam);
     }
     
     let w: &mut Window = mem::transmute(ww);
@@ -294,18 +274,37 @@ unsafe extern "system" fn window_handler(hwnd: windef::HWND, msg: minwindef::UIN
 }
 
 unsafe extern "system" fn handler<O: controls::Window>(hwnd: windef::HWND, msg: minwindef::UINT, wparam: minwindef::WPARAM, lparam: minwindef::LPARAM) -> minwindef::LRESULT {
-    let ww = winuser::GetWindowLongPtrW(hwnd, winuser::GWLP_USERDATA);
+    let ww = backend::current().window_long_ptr(hwnd as usize, winuser::GWLP_USERDATA);
     let w: &mut Window = mem::transmute(ww);
     match msg {
-        winuser::WM_SIZE => {
+        winuser::WM_SIZE | common::WM_UPDATE_INNER => {
             let width = minwindef::LOWORD(lparam as u32);
             let height = minwindef::HIWORD(lparam as u32);
 
             w.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().redraw();
 
-            winuser::InvalidateRect(w.inner().inner().inner().inner().inner().hwnd, ptr::null_mut(), minwindef::TRUE);
+            backend::current().invalidate(w.inner().inner().inner().inner().inner().hwnd as usize, true);
 
-            w.call_on_size::<O>(width, height);
+            if msg != common::WM_UPDATE_INNER {
+                w.call_on_size::<O>(width, height);
+            }
+            return 0;
+        }
+        winuser::WM_DPICHANGED => {
+            // fonts first, so the resize that follows measures with them
+            common::update_default_fonts(hwnd, minwindef::HIWORD(wparam as u32) as u32);
+            let suggested = &*(lparam as *const windef::RECT);
+            backend::current().set_window_pos(
+                hwnd as usize,
+                suggested.left,
+                suggested.top,
+                suggested.right - suggested.left,
+                suggested.bottom - suggested.top,
+                winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE,
+            );
+            // the paddings change even if the size does not
+            w.inner_mut().inner_mut().inner_mut().inner_mut().inner_mut().redraw();
+            backend::current().invalidate(hwnd as usize, true);
             return 0;
         }
         winuser::WM_DESTROY => {
@@ -322,7 +321,15 @@ unsafe extern "system" fn handler<O: controls::Window>(hwnd: windef::HWND, msg:
                 }
             }
         }
+        winuser::WM_NOTIFY | winuser::WM_HSCROLL | winuser::WM_VSCROLL => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
+        }
         winuser::WM_COMMAND => {
+            if let Some(result) = common::reflect_notification(msg, wparam, lparam) {
+                return result;
+            }
             let id = minwindef::LOWORD(wparam as u32);
             let _evt = minwindef::HIWORD(wparam as u32);
             let w2: &mut O = mem::transmute(ww);
@@ -332,5 +339,5 @@ unsafe extern "system" fn handler<O: controls::Window>(hwnd: windef::HWND, msg:
         }
         _ => {}
     }
-    winuser::DefWindowProcW(hwnd, msg, wparam, lparam)
+    backend::current().def_window_proc(hwnd as usize, msg, wparam, lparam)
 }